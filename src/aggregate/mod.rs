@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use crate::hit::Hit;
+
+mod stats;
+pub use stats::{median, Statistics};
+
+/// A property of a Hit by which hits can be grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupKey {
+    Make,
+    Model,
+    Year,
+    Engine,
+    Fuel,
+    Currency,
+}
+
+impl GroupKey {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupKey::Make => "make",
+            GroupKey::Model => "model",
+            GroupKey::Year => "year",
+            GroupKey::Engine => "engine",
+            GroupKey::Fuel => "fuel",
+            GroupKey::Currency => "currency",
+        }
+    }
+
+    pub fn value(&self, hit: &Hit) -> String {
+        match self {
+            GroupKey::Make => hit.make.clone(),
+            GroupKey::Model => hit.model.clone(),
+            GroupKey::Year => hit.year.to_string(),
+            GroupKey::Engine => hit.search_engine.clone(),
+            GroupKey::Fuel => hit.fuel.clone().unwrap_or_else(|| "unknown".to_string()),
+            GroupKey::Currency => hit.price.currency().unwrap_or("unknown").to_string(),
+        }
+    }
+}
+
+impl TryFrom<&str> for GroupKey {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "make" => Ok(GroupKey::Make),
+            "model" => Ok(GroupKey::Model),
+            "year" => Ok(GroupKey::Year),
+            "engine" => Ok(GroupKey::Engine),
+            "fuel" => Ok(GroupKey::Fuel),
+            "currency" => Ok(GroupKey::Currency),
+            _ => Err(format!(
+                "Unrecognised key for grouping: {}. Options are: make, model, year, engine, fuel, \
+                 currency",
+                value
+            )),
+        }
+    }
+}
+
+/// A numeric property of a Hit over which statistics can be computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Price,
+    Mileage,
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Price => "price",
+            Metric::Mileage => "mileage",
+        }
+    }
+
+    fn value(&self, hit: &Hit) -> Option<f64> {
+        match self {
+            Metric::Price => hit.price.amount().map(f64::from),
+            Metric::Mileage => hit.mileage.kms().map(f64::from),
+        }
+    }
+
+    /// The unit in which this metric is expressed for the given hits. Mileage is always
+    /// normalised to kilometres; prices are reported in their currency, which groups share, as
+    /// they are grouped by currency whenever prices are summarised.
+    fn unit(&self, hits: &[&Hit]) -> String {
+        match self {
+            Metric::Mileage => "km".to_string(),
+            Metric::Price => hits
+                .iter()
+                .find_map(|hit| hit.price.currency())
+                .unwrap_or("unknown")
+                .to_string(),
+        }
+    }
+}
+
+impl TryFrom<&str> for Metric {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "price" => Ok(Metric::Price),
+            "mileage" => Ok(Metric::Mileage),
+            _ => Err(format!(
+                "Unrecognised statistic: {}. Options are: price, mileage",
+                value
+            )),
+        }
+    }
+}
+
+/// The statistics for a single metric within a group.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct MetricSummary {
+    pub metric: Metric,
+    pub unit: String,
+    pub statistics: Option<Statistics>,
+}
+
+/// A group of hits that share the same values for each of the grouping keys, along with the
+/// statistics computed over them.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Group {
+    /// The grouping keys and the values this group has for them, in the order they were requested.
+    #[serde(serialize_with = "serialize_keys")]
+    pub keys: Vec<(GroupKey, String)>,
+    pub count: usize,
+    pub metrics: Vec<MetricSummary>,
+}
+
+fn serialize_keys<S: Serializer>(
+    keys: &[(GroupKey, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(keys.len()))?;
+    for (key, value) in keys {
+        map.serialize_entry(key.name(), value)?;
+    }
+    map.end()
+}
+
+/// Groups hits by one or more keys and computes statistics for each group. Without any grouping
+/// keys, all hits are summarised as a single group. Prices in different currencies cannot be
+/// summarised together, so hits are also grouped by currency whenever prices are summarised.
+pub struct Aggregation {
+    group_by: Vec<GroupKey>,
+    metrics: Vec<Metric>,
+}
+
+impl Aggregation {
+    pub fn new(mut group_by: Vec<GroupKey>, metrics: Vec<Metric>) -> Self {
+        if metrics.contains(&Metric::Price) && !group_by.contains(&GroupKey::Currency) {
+            group_by.push(GroupKey::Currency);
+        }
+        Self { group_by, metrics }
+    }
+
+    pub fn execute(&self, hits: &[Hit]) -> Vec<Group> {
+        let mut groups: BTreeMap<Vec<String>, Vec<&Hit>> = BTreeMap::new();
+        for hit in hits {
+            let key = self.group_by.iter().map(|k| k.value(hit)).collect();
+            groups.entry(key).or_default().push(hit);
+        }
+
+        groups
+            .into_iter()
+            .map(|(values, members)| Group {
+                keys: self.group_by.iter().copied().zip(values).collect(),
+                count: members.len(),
+                metrics: self
+                    .metrics
+                    .iter()
+                    .map(|metric| {
+                        let values: Vec<f64> =
+                            members.iter().filter_map(|hit| metric.value(hit)).collect();
+                        MetricSummary {
+                            metric: *metric,
+                            unit: metric.unit(&members),
+                            statistics: Statistics::from_values(&values),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Toyota".to_string(),
+                model: "Corolla".to_string(),
+                mileage: Mileage::Km(10000),
+                year: 2019,
+                price: Price::Eur(15000),
+                url: "bla".to_string(),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Toyota".to_string(),
+                model: "Yaris".to_string(),
+                mileage: Mileage::Km(20000),
+                year: 2018,
+                price: Price::Eur(9000),
+                url: "bla".to_string(),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "bar".to_string(),
+                make: "Toyota".to_string(),
+                model: "Corolla".to_string(),
+                mileage: Mileage::Mi(10000),
+                year: 2019,
+                price: Price::Eur(17000),
+                url: "bla".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn group_by_single_key() {
+        let aggregation = Aggregation::new(vec![GroupKey::Model], vec![Metric::Price]);
+        let groups = aggregation.execute(&hits());
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[0].keys,
+            vec![
+                (GroupKey::Model, "Corolla".to_string()),
+                (GroupKey::Currency, "EUR".to_string())
+            ]
+        );
+        assert_eq!(groups[0].count, 2);
+        assert_eq!(groups[0].metrics[0].unit, "EUR");
+        let stats = groups[0].metrics[0].statistics.as_ref().unwrap();
+        assert_eq!(stats.min, 15000.0);
+        assert_eq!(stats.max, 17000.0);
+        assert_eq!(stats.mean, 16000.0);
+
+        assert_eq!(
+            groups[1].keys,
+            vec![
+                (GroupKey::Model, "Yaris".to_string()),
+                (GroupKey::Currency, "EUR".to_string())
+            ]
+        );
+        assert_eq!(groups[1].count, 1);
+    }
+
+    #[test]
+    fn group_by_multiple_keys() {
+        let aggregation = Aggregation::new(vec![GroupKey::Engine, GroupKey::Year], vec![]);
+        let groups = aggregation.execute(&hits());
+
+        let keys: Vec<Vec<(GroupKey, String)>> = groups.into_iter().map(|g| g.keys).collect();
+        assert_eq!(
+            keys,
+            vec![
+                vec![
+                    (GroupKey::Engine, "bar".to_string()),
+                    (GroupKey::Year, "2019".to_string())
+                ],
+                vec![
+                    (GroupKey::Engine, "foo".to_string()),
+                    (GroupKey::Year, "2018".to_string())
+                ],
+                vec![
+                    (GroupKey::Engine, "foo".to_string()),
+                    (GroupKey::Year, "2019".to_string())
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn no_keys_summarises_everything() {
+        let aggregation = Aggregation::new(vec![], vec![Metric::Mileage]);
+        let groups = aggregation.execute(&hits());
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].metrics[0].unit, "km");
+        let stats = groups[0].metrics[0].statistics.as_ref().unwrap();
+        assert_eq!(stats.min, 10000.0);
+        assert_eq!(stats.max, 20000.0);
+        assert_eq!(stats.median, 16093.0);
+    }
+
+    #[test]
+    fn prices_are_summarised_per_currency() {
+        let mut hits = hits();
+        hits[1].price = Price::Gbp(8000);
+        let aggregation = Aggregation::new(vec![], vec![Metric::Price]);
+        let groups = aggregation.execute(&hits);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[0].keys,
+            vec![(GroupKey::Currency, "EUR".to_string())]
+        );
+        assert_eq!(groups[0].metrics[0].unit, "EUR");
+        assert_eq!(
+            groups[0].metrics[0].statistics.as_ref().unwrap().mean,
+            16000.0
+        );
+        assert_eq!(
+            groups[1].keys,
+            vec![(GroupKey::Currency, "GBP".to_string())]
+        );
+        assert_eq!(groups[1].metrics[0].unit, "GBP");
+        assert_eq!(
+            groups[1].metrics[0].statistics.as_ref().unwrap().mean,
+            8000.0
+        );
+    }
+
+    #[test]
+    fn unknown_values_are_ignored() {
+        let mut hits = hits();
+        hits[0].mileage = Mileage::Unknown;
+        let aggregation = Aggregation::new(vec![GroupKey::Model], vec![Metric::Mileage]);
+        let groups = aggregation.execute(&hits);
+
+        assert_eq!(groups[0].count, 2);
+        let stats = groups[0].metrics[0].statistics.as_ref().unwrap();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.min, 16093.0);
+    }
+
+    #[test]
+    fn unknown_prices_are_grouped_apart() {
+        let mut hits = hits();
+        hits[0].price = Price::Unknown;
+        let aggregation = Aggregation::new(vec![GroupKey::Model], vec![Metric::Price]);
+        let groups = aggregation.execute(&hits);
+
+        assert_eq!(
+            groups[1].keys[1],
+            (GroupKey::Currency, "unknown".to_string())
+        );
+        assert_eq!(groups[1].metrics[0].unit, "unknown");
+        assert_eq!(groups[1].metrics[0].statistics, None);
+    }
+}
@@ -0,0 +1,111 @@
+use serde::Serialize;
+
+/// Descriptive statistics over a set of numeric values.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Statistics {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p10: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p90: f64,
+    pub std_dev: f64,
+}
+
+impl Statistics {
+    /// Compute the statistics for the given values. Returns None if there are no values.
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = if count > 1 {
+            sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        Some(Statistics {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            median: percentile(&sorted, 50.0),
+            p10: percentile(&sorted, 10.0),
+            p25: percentile(&sorted, 25.0),
+            p75: percentile(&sorted, 75.0),
+            p90: percentile(&sorted, 90.0),
+            std_dev: variance.sqrt(),
+        })
+    }
+}
+
+/// Percentile of an already sorted, non-empty slice, linearly interpolating between the closest
+/// ranks.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * weight
+}
+
+/// The median of a set of values, which do not need to be sorted. Returns None if there are no
+/// values.
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some(percentile(&sorted, 50.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_values() {
+        assert_eq!(Statistics::from_values(&[]), None);
+    }
+
+    #[test]
+    fn single_value() {
+        let stats = Statistics::from_values(&[42.0]).unwrap();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.min, 42.0);
+        assert_eq!(stats.max, 42.0);
+        assert_eq!(stats.median, 42.0);
+        assert_eq!(stats.p90, 42.0);
+        assert_eq!(stats.std_dev, 0.0);
+    }
+
+    #[test]
+    fn unsorted_values() {
+        let stats = Statistics::from_values(&[5.0, 1.0, 4.0, 2.0, 3.0]).unwrap();
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 5.0);
+        assert_eq!(stats.mean, 3.0);
+        assert_eq!(stats.median, 3.0);
+        assert_eq!(stats.p25, 2.0);
+        assert_eq!(stats.p75, 4.0);
+        assert!((stats.p10 - 1.4).abs() < 1e-9);
+        assert!((stats.std_dev - 2.5_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn median_interpolates_even_count() {
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+        assert_eq!(median(&[]), None);
+    }
+}
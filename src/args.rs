@@ -1,13 +1,154 @@
-use clap::Parser;
+use std::collections::HashMap;
+use std::ffi::OsString;
 
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args as _, CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use crate::aggregate::{Aggregation, GroupKey, Metric};
+use crate::calendar::current_year;
+use crate::config::{Config, Setting};
+use crate::hit::Hit;
 use crate::post_processing::{
+    anomaly::{AnomalyDetection, AnomalyMode},
+    derived::{DerivedFields, DERIVED_FIELDS},
+    filter::Filter,
     limit::Limit,
+    offset::Offset,
     sort::{Sort, SortBy, SortOrder},
+    spec::{PipelineFile, SpecError, StepSpec},
+    top_per_group::TopPerGroup,
+    valuation::Valuation,
     Action, Pipeline,
 };
+use crate::query::MakeModel;
 
+/// The command line: the global options, and either a subcommand or the options of a search.
+/// Searching without a subcommand is the same as using the search subcommand.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub search: Args,
+
+    /// The effective configuration, with the source of every value
+    #[arg(skip)]
+    pub configuration: Vec<Setting>,
+}
+
+/// Options that apply to every subcommand.
+#[derive(clap::Args, Debug)]
+pub struct GlobalArgs {
+    /// Optional, emitter for the results. Options are: csv, html, json, ndjson, text. Default is
+    /// text. ndjson writes a compact JSON object per line, as soon as search engines return
+    /// results where possible. html writes a self-contained report with a sortable table.
+    #[arg(long, global = true)]
+    pub emitter: Option<String>,
+
+    /// Optional, whether the text emitter highlights the cheapest prices, deal scores and
+    /// anomalies in colour. Options are: auto, always, never. Default is auto, which is to use
+    /// colour when writing to a terminal, unless NO_COLOR is set.
+    #[arg(long, global = true)]
+    pub colour: Option<String>,
+
+    /// Optional, comma-separated columns to emit, in order. Options are: price, year, mileage,
+    /// make, model, fuel, title, description, matched, age, km_per_year, price_per_1000_km,
+    /// expected_price, residual, deal_score, anomalies, engine, url. In CSV and JSON, prices and
+    /// mileages are split into price_amount and price_currency, and mileage_value and mileage_unit.
+    /// Example: ./findcar [other opts] --columns price,year,mileage,make,model,url
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub columns: Option<Vec<String>>,
+
+    /// Optional, emit JSON results in the same flat representation as CSV, with every column
+    /// unless --columns is given
+    #[arg(long, global = true)]
+    pub flatten: bool,
+
+    /// Optional, character separating the fields of CSV output, or "tab". Default is a comma.
+    #[arg(long, global = true)]
+    pub csv_delimiter: Option<String>,
+
+    /// Optional, leave out the header of CSV output
+    #[arg(long, global = true)]
+    pub csv_no_header: bool,
+
+    /// Optional, search engine to use. See `findcar engines` for the options. Default is to use all available engines.
+    /// Example: ./findcar [other opts] --search-engine carzone_ie --search-engine donedeal_ie
+    #[arg(long, global = true)]
+    pub search_engine: Option<Vec<String>>,
+
+    /// Optional, search engine not to use, even if selected with --search-engine. Useful to switch
+    /// search engines off in the configuration file or a profile.
+    /// Example: ./findcar [other opts] --disable-engine donedeal_ie
+    #[arg(long, global = true)]
+    pub disable_engine: Option<Vec<String>>,
+
+    /// Optional, TOML file of additional make and model aliases, extending the bundled taxonomy.
+    /// Each [[make]] table has a name, optionally aliases, the names particular search engines
+    /// expect (engines = { donedeal_ie = "..." }), and a list of models in the same form.
+    #[arg(long, global = true)]
+    pub aliases: Option<String>,
+
+    /// Optional, configuration file setting defaults for any option. Default is
+    /// $XDG_CONFIG_HOME/findcar/config.toml, if it exists.
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Optional, profile of the configuration file to overlay the defaults with
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Optional, number of seconds after which a request to a search engine times out. Default
+    /// is 30.
+    #[arg(long, global = true, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Optional, user agent to send with requests to search engines
+    #[arg(long, global = true)]
+    pub user_agent: Option<String>,
+
+    /// Optional, maximum number of requests per second to any single search engine, or 0 for no
+    /// maximum. Default is 1 for search engines whose pages are scraped, and no maximum for others.
+    #[arg(long, global = true)]
+    pub requests_per_second: Option<f64>,
+
+    /// Optional, number of seconds to cache responses of search engines for. Default is 0, which
+    /// disables caching.
+    #[arg(long, global = true, default_value_t = 0)]
+    pub cache_ttl: u64,
+
+    /// Optional, directory to cache responses in. Default is $XDG_CACHE_HOME/findcar.
+    #[arg(long, global = true)]
+    pub cache_dir: Option<String>,
+
+    /// Optional, directory of searcher plugins: executables that implement a search engine. Default
+    /// is $XDG_CONFIG_HOME/findcar/plugins.
+    #[arg(long, global = true)]
+    pub plugin_dir: Option<String>,
+
+    /// Optional, number of seconds after which a plugin is stopped. Default is 120.
+    #[arg(long, global = true, default_value_t = 120)]
+    pub plugin_timeout: u64,
+
+    /// Optional, directory of specs (TOML or YAML) that describe search engines with a JSON API.
+    /// Default is $XDG_CONFIG_HOME/findcar/specs.
+    #[arg(long, global = true)]
+    pub spec_dir: Option<String>,
+
+    /// Optional, do not record searches in the history listed by `findcar history`. Can be set in
+    /// the configuration file to never record them.
+    #[arg(long, global = true)]
+    pub no_history: bool,
+}
+
+/// The options of a search.
+#[derive(clap::Args, Debug)]
 pub struct Args {
     /// Optional, make of the car to search for
     #[arg(long)]
@@ -17,6 +158,13 @@ pub struct Args {
     #[arg(long)]
     pub model: Option<String>,
 
+    /// Optional, make and optionally model of a car to search for, separated by a colon. Can be
+    /// repeated to search for several cars at once, in which case each result is tagged with the
+    /// make and model it matched. Cannot be combined with --make and --model.
+    /// Example: ./findcar [other opts] --vehicle Volkswagen:Golf --vehicle Ford:Focus --vehicle Opel
+    #[arg(long, conflicts_with_all = ["make", "model"])]
+    pub vehicle: Option<Vec<MakeModel>>,
+
     /// Optional, minimum year of registration
     #[arg(long)]
     pub min_year: Option<String>,
@@ -41,7 +189,35 @@ pub struct Args {
     #[arg(long)]
     pub max_price: Option<String>,
 
-    /// Optional, value to sort by. Options are: price, year, mileage
+    /// Optional, postcode (or ZIP code) to search around, for search engines that search by
+    /// distance, such as autotrader_co_uk. Search engines that do not are searched nationwide.
+    #[arg(long)]
+    pub postcode: Option<String>,
+
+    /// Optional, distance in km around --postcode to search within. Search engines that only
+    /// offer a fixed set of distances use the nearest one that covers it.
+    #[arg(long, requires = "postcode")]
+    pub radius: Option<String>,
+
+    /// Optional, keyword or phrase that must appear in the title or description of a listing.
+    /// Can be repeated, in which case every keyword must appear.
+    /// Example: ./findcar [other opts] --keyword towbar --keyword "full service history"
+    #[arg(long)]
+    pub keyword: Option<Vec<String>>,
+
+    /// Optional, keyword or phrase that must not appear in the title or description of a listing.
+    /// Can be repeated. Example: ./findcar [other opts] --exclude-keyword "spares or repairs"
+    #[arg(long)]
+    pub exclude_keyword: Option<Vec<String>>,
+
+    /// Optional, currency of the results to keep; results priced in other currencies are
+    /// removed. Options are: EUR, GBP, USD.
+    #[arg(long)]
+    pub currency: Option<String>,
+
+    /// Optional, value to sort by. Options are: price, year, mileage, deal_score, age, km_per_year,
+    /// price_per_1000_km. Prices in different currencies cannot be compared, so prices are sorted
+    /// by currency (EUR, GBP, USD) first. Results without a value are sorted last.
     #[arg(long)]
     pub sort_by: Option<String>,
 
@@ -50,29 +226,331 @@ pub struct Args {
     #[arg(long)]
     pub sort_order: Option<String>,
 
-    /// Optional, maximum number of results to return
+    /// Optional, filter to apply to the results, in the form <key><operator><value>. Keys are:
+    /// price, year, mileage, deal_score, age, km_per_year, price_per_1000_km. Operators are: <, <=,
+    /// =, >=, >. Can be repeated.
+    /// Example: ./findcar [other opts] --filter "year>=2018" --filter "deal_score>=10"
     #[arg(long)]
-    pub limit: Option<usize>,
+    pub filter: Option<Vec<String>>,
 
-    /// Optional, emitter for the results. Options are: csv, json, text. Default is text.
+    /// Optional, detect implausible and suspicious results, such as placeholder prices or
+    /// mileages, or prices and mileages far out of line with the same make, model and year.
+    /// Options are: flag, remove. Flagged results include the reasons in the output.
     #[arg(long)]
-    pub emitter: Option<String>,
+    pub anomalies: Option<String>,
 
-    /// Optional, search engine to use. Options are donedeal_ie, carzone_ie. Default is to use all available engines.
-    /// Example: ./findcar [other opts] --search-engine carzone_ie --search-engine donedeal_ie
+    /// Optional, compute the age of every result, the average kms driven per year, and the price
+    /// per 1,000 km. Implied when sorting or filtering by any of these.
     #[arg(long)]
-    pub search_engine: Option<Vec<String>>,
+    pub derived_fields: bool,
+
+    /// Optional, estimate a fair price for every result based on its make, model, year and mileage,
+    /// scoring how far below the expected price it is. The score can be sorted and filtered on as
+    /// deal_score.
+    #[arg(long)]
+    pub estimate_value: bool,
+
+    /// Optional, JSON file of previously emitted results to estimate prices from, instead of the
+    /// current results. Implies --estimate-value.
+    #[arg(long)]
+    pub valuation_reference: Option<String>,
+
+    /// Optional, number of results to skip, e.g. to page through results together with --limit
+    #[arg(long)]
+    pub offset: Option<usize>,
+
+    /// Optional, maximum number of results to return for every group, where groups are identified
+    /// by one or more keys separated by commas. Options for keys are: make, model, year, engine,
+    /// fuel, currency. Example: ./findcar [other opts] --sort-by price --top-per-group model,year 3
+    #[arg(long, num_args = 2, value_names = ["KEYS", "N"])]
+    pub top_per_group: Option<Vec<String>>,
+
+    /// Optional, maximum number of results to return. Applied after filtering, sorting,
+    /// offsetting and top-per-group, wherever it is given.
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Optional, keys to group results by, separated by commas. Options are: make, model, year,
+    /// engine, fuel, currency. When grouping, statistics per group are emitted instead of the
+    /// results. Price statistics are always grouped by currency as well.
+    /// Example: ./findcar --make Toyota --group-by model,year --stats price
+    #[arg(long, value_delimiter = ',')]
+    pub group_by: Option<Vec<String>>,
+
+    /// Optional, values to compute statistics for, separated by commas. Options are: price,
+    /// mileage. If not specified, but a group-by value *is*, then both will be used by default.
+    #[arg(long, value_delimiter = ',')]
+    pub stats: Option<Vec<String>>,
+
+    /// Optional, a step of the post-processing pipeline, as its name followed by its arguments,
+    /// separated by colons. Can be repeated; steps are performed in the order given. Steps are:
+    /// anomalies:<flag|remove>, derive, valuation[:<reference file>], filter:<expression>,
+    /// sort:<key>[:<asc|desc>], offset:<n>, top_per_group:<keys>:<n>, limit:<n>. Derived fields
+    /// are derived before the first step that sorts or filters on them.
+    /// Example: ./findcar [other opts] --step filter:year>=2018 --step sort:price --step limit:10
+    #[arg(long, conflicts_with_all = PIPELINE_OPTIONS)]
+    pub step: Option<Vec<StepSpec>>,
+
+    /// Optional, TOML file specifying the post-processing pipeline as a list of [[pipeline]]
+    /// tables, each with a step name and its arguments, e.g. step = "sort", args = ["price"]
+    #[arg(long, conflicts_with_all = PIPELINE_OPTIONS, conflicts_with = "step")]
+    pub pipeline_file: Option<String>,
+
+    /// The positions at which options were given on the command line, by option id
+    #[arg(skip)]
+    pub positions: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Search for cars. This is the default when no subcommand is given
+    Search(Args),
+    /// List the available search engines
+    Engines,
+    /// List the makes that the search engines support, and which search engine supports which
+    Makes,
+    /// List the models of a make that the search engines support, and which search engine
+    /// supports which
+    Models {
+        /// Make to list the models of
+        #[arg(long)]
+        make: String,
+    },
+    /// List previous searches
+    History(HistoryArgs),
+    /// Repeat a search at an interval, emitting only results that have not been seen before
+    Watch(WatchArgs),
+    /// Serve searches over HTTP, e.g. GET /search?make=Toyota&sort-by=price returns the results
+    /// as JSON. Every search option can be given as a query parameter, except those that read
+    /// files
+    Serve(ServeArgs),
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HistoryArgs {
+    /// Optional, maximum number of searches to list, most recent last. Default is 20.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Optional, remove all previous searches from the history
+    #[arg(long)]
+    pub clear: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct WatchArgs {
+    /// Optional, number of seconds to wait between searches. Default is 900.
+    #[arg(long, default_value_t = 900)]
+    pub interval: u64,
+
+    #[command(flatten)]
+    pub search: Args,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// Optional, address to listen on. Default is 127.0.0.1:8080.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub address: String,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the location of the configuration directory
+    Path,
+    /// Print the effective configuration, with the source of every value
+    Show,
+}
+
+/// Options that each result in a single action of the pipeline, which cannot be combined with an
+/// explicitly specified pipeline.
+const PIPELINE_OPTIONS: [&str; 10] = [
+    "anomalies",
+    "derived_fields",
+    "estimate_value",
+    "valuation_reference",
+    "filter",
+    "sort_by",
+    "sort_order",
+    "offset",
+    "top_per_group",
+    "limit",
+];
+
+/// Options that result in an action which reorders or cuts the results. These actions are
+/// performed in the order in which the options were given, followed by --limit.
+const ORDERED_OPTIONS: [&str; 4] = ["filter", "sort_by", "offset", "top_per_group"];
+
+impl Cli {
+    /// Parse the arguments from the command line, recording the positions at which the options
+    /// that make up the pipeline were given.
+    pub fn parse_ordered() -> Self {
+        let arguments: Vec<OsString> = std::env::args_os().collect();
+        let config = Config::load(&arguments).unwrap_or_else(|e| {
+            Cli::command().error(ErrorKind::InvalidValue, e).exit();
+        });
+        let command = config.apply(Cli::command());
+        let matches = command.clone().get_matches_from(arguments);
+        // The options of a search, without the search subcommand, cannot be combined with a
+        // subcommand; global options can.
+        if let Some((name, _)) = matches.subcommand() {
+            let search = Args::augment_args(clap::Command::new("findcar"));
+            let misplaced = search.get_arguments().find(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            });
+            if let Some(arg) = misplaced {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        format!(
+                            "the argument '--{}' cannot be used before the '{}' subcommand",
+                            arg.get_long().unwrap_or_default(),
+                            name
+                        ),
+                    )
+                    .exit();
+            }
+        }
+        let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        let (search, search_matches) = match (&mut cli.command, matches.subcommand()) {
+            (Some(Command::Search(args)), Some((_, m))) => (args, m),
+            (Some(Command::Watch(watch)), Some((_, m))) => (&mut watch.search, m),
+            _ => (&mut cli.search, &matches),
+        };
+        search.positions = positions(search_matches);
+        cli.configuration = config.describe(&command, &matches);
+        cli
+    }
+}
+
+impl Args {
+    /// Parse the options of a search, recording the positions at which the options that make up
+    /// the pipeline were given.
+    pub fn try_parse_ordered_from<I, T>(itr: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches =
+            Args::augment_args(clap::Command::new("findcar")).try_get_matches_from(itr)?;
+        let mut args = Args::from_arg_matches(&matches)?;
+        args.positions = positions(&matches);
+        Ok(args)
+    }
+
+    /// The post-processing pipeline, either as explicitly specified through steps or a pipeline
+    /// file, or as implied by the individual options.
+    pub fn pipeline(&self) -> Result<Pipeline, Vec<String>> {
+        let specs = match (&self.step, &self.pipeline_file) {
+            (Some(steps), _) => steps.clone(),
+            (None, Some(path)) => {
+                let file = std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| toml::from_str::<PipelineFile>(&s).map_err(|e| e.to_string()));
+                match file {
+                    Ok(file) => file.pipeline,
+                    Err(e) => {
+                        return Err(vec![format!(
+                            "Unable to read pipeline file {}: {}",
+                            path, e
+                        )])
+                    }
+                }
+            }
+            (None, None) => return Ok(self.into()),
+        };
+        Pipeline::try_from(specs.as_slice())
+            .map_err(|errors| errors.iter().map(SpecError::to_string).collect())
+    }
+
+    /// Whether any option refers to one of the derived fields
+    fn uses_derived_fields(&self) -> bool {
+        let sorts = self
+            .sort_by
+            .as_ref()
+            .is_some_and(|key| DERIVED_FIELDS.contains(&key.as_str()));
+        let filters = self
+            .filter
+            .iter()
+            .flatten()
+            .any(|expression| DERIVED_FIELDS.iter().any(|key| expression.starts_with(key)));
+        sorts || filters
+    }
+
+    /// The position of the nth occurrence of an option, or the provided default if unknown
+    fn position(&self, id: &str, n: usize, default: usize) -> usize {
+        self.positions
+            .get(id)
+            .and_then(|p| p.get(n))
+            .copied()
+            .unwrap_or(default)
+    }
+}
+
+fn positions(matches: &ArgMatches) -> HashMap<String, Vec<usize>> {
+    ORDERED_OPTIONS
+        .iter()
+        .filter_map(|id| {
+            let indices: Vec<usize> = matches.indices_of(id)?.collect();
+            // Options with several values per occurrence report an index per value; only the
+            // first value of each occurrence matters for ordering.
+            let step = match *id {
+                "top_per_group" => 2,
+                _ => 1,
+            };
+            Some((id.to_string(), indices.into_iter().step_by(step).collect()))
+        })
+        .collect()
 }
 
 impl From<&Args> for Pipeline {
     fn from(args: &Args) -> Self {
         let mut actions: Vec<Box<dyn Action>> = vec![];
 
+        let anomalies: Option<Box<AnomalyDetection>> = args.into();
+        if let Some(a) = anomalies {
+            actions.push(a);
+        }
+
+        if args.derived_fields || args.uses_derived_fields() {
+            actions.push(Box::new(DerivedFields::new(current_year())));
+        }
+
+        let valuation: Option<Box<Valuation>> = args.into();
+        if let Some(v) = valuation {
+            actions.push(v);
+        }
+
+        // Without known positions, the options are ordered as listed in ORDERED_OPTIONS
+        let mut ordered: Vec<(usize, Box<dyn Action>)> = vec![];
+
+        let filters: Vec<Box<Filter>> = args.into();
+        for (n, f) in filters.into_iter().enumerate() {
+            ordered.push((args.position("filter", n, 0), f));
+        }
+
         let sort: Option<Box<Sort>> = args.into();
         if let Some(s) = sort {
-            actions.push(s);
+            ordered.push((args.position("sort_by", 0, 1), s));
+        }
+
+        if let Some(o) = args.offset {
+            ordered.push((args.position("offset", 0, 2), Box::new(Offset::new(o))));
+        }
+
+        let top: Option<Box<TopPerGroup>> = args.into();
+        if let Some(t) = top {
+            ordered.push((args.position("top_per_group", 0, 3), t));
         }
 
+        ordered.sort_by_key(|(position, _)| *position);
+        actions.extend(ordered.into_iter().map(|(_, action)| action));
+
         let limit: Option<Box<Limit>> = args.into();
         if let Some(l) = limit {
             actions.push(l);
@@ -87,25 +565,16 @@ impl From<&Args> for Option<Box<Sort>> {
         match &args.sort_by {
             None => None,
             Some(key) => {
-                let sort_by = match key.as_str() {
-                    "price" => SortBy::Price,
-                    "mileage" => SortBy::Mileage,
-                    "year" => SortBy::Year,
-                    _ => {
-                        println!("Unrecognised key for sorting: {}, defaulting to price", key);
-                        SortBy::Price
-                    }
-                };
+                let sort_by = SortBy::try_from(key.as_str()).unwrap_or_else(|e| {
+                    eprintln!("{}, defaulting to price", e);
+                    SortBy::Price
+                });
 
                 let sort_order = match &args.sort_order {
-                    Some(o) => match o.as_str() {
-                        "ASC" => SortOrder::Asc,
-                        "DESC" => SortOrder::Desc,
-                        _ => {
-                            println!("Unrecognised sort order: {}, default to ascending", o);
-                            SortOrder::Asc
-                        }
-                    },
+                    Some(o) => SortOrder::try_from(o.as_str()).unwrap_or_else(|e| {
+                        eprintln!("{}, default to ascending", e);
+                        SortOrder::Asc
+                    }),
                     None => SortOrder::Asc,
                 };
 
@@ -117,9 +586,214 @@ impl From<&Args> for Option<Box<Sort>> {
 
 impl From<&Args> for Option<Box<Limit>> {
     fn from(args: &Args) -> Self {
-        match args.limit {
-            Some(lim) => Some(Box::new(Limit::new(lim))),
+        args.limit.map(|lim| Box::new(Limit::new(lim)))
+    }
+}
+
+impl From<&Args> for Option<Box<TopPerGroup>> {
+    fn from(args: &Args) -> Self {
+        let values = args.top_per_group.as_ref()?;
+        let (keys, n) = (&values[0], &values[1]);
+
+        let n: usize = match n.parse() {
+            Ok(n) => n,
+            Err(_) => {
+                eprintln!("Invalid number for top-per-group: {}, ignoring", n);
+                return None;
+            }
+        };
+
+        let group_by: Result<Vec<GroupKey>, String> = keys
+            .split(',')
+            .map(|key| GroupKey::try_from(key.trim()))
+            .collect();
+        match group_by {
+            Ok(group_by) => Some(Box::new(TopPerGroup::new(group_by, n))),
+            Err(e) => {
+                eprintln!("{}, ignoring top-per-group", e);
+                None
+            }
+        }
+    }
+}
+
+impl From<&Args> for Option<Box<AnomalyDetection>> {
+    fn from(args: &Args) -> Self {
+        args.anomalies
+            .as_ref()
+            .and_then(|mode| match AnomalyMode::try_from(mode.as_str()) {
+                Ok(m) => Some(Box::new(AnomalyDetection::new(m, current_year()))),
+                Err(e) => {
+                    eprintln!("{}, not detecting anomalies", e);
+                    None
+                }
+            })
+    }
+}
+
+impl From<&Args> for Option<Box<Valuation>> {
+    fn from(args: &Args) -> Self {
+        match &args.valuation_reference {
+            Some(path) => {
+                let reference = std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| serde_json::from_str::<Vec<Hit>>(&s).map_err(|e| e.to_string()));
+                match reference {
+                    Ok(hits) => Some(Box::new(Valuation::with_reference(hits))),
+                    Err(e) => {
+                        eprintln!(
+                            "Unable to read valuation reference {}: {}, valuing against current results",
+                            path, e
+                        );
+                        Some(Box::new(Valuation::new()))
+                    }
+                }
+            }
+            None if args.estimate_value => Some(Box::new(Valuation::new())),
             None => None,
         }
     }
 }
+
+impl From<&Args> for Vec<Box<Filter>> {
+    fn from(args: &Args) -> Self {
+        args.filter
+            .iter()
+            .flatten()
+            .filter_map(|expression| match Filter::try_from(expression.as_str()) {
+                Ok(f) => Some(Box::new(f)),
+                Err(e) => {
+                    eprintln!("{}, ignoring", e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl From<&Args> for Option<Aggregation> {
+    fn from(args: &Args) -> Self {
+        if args.group_by.is_none() && args.stats.is_none() {
+            return None;
+        }
+
+        let group_by = args
+            .group_by
+            .iter()
+            .flatten()
+            .filter_map(|key| match GroupKey::try_from(key.as_str()) {
+                Ok(k) => Some(k),
+                Err(e) => {
+                    eprintln!("{}, ignoring", e);
+                    None
+                }
+            })
+            .collect();
+
+        let metrics = match &args.stats {
+            None => vec![Metric::Price, Metric::Mileage],
+            Some(stats) => stats
+                .iter()
+                .filter_map(|stat| match Metric::try_from(stat.as_str()) {
+                    Ok(m) => Some(m),
+                    Err(e) => {
+                        eprintln!("{}, ignoring", e);
+                        None
+                    }
+                })
+                .collect(),
+        };
+
+        Some(Aggregation::new(group_by, metrics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        [
+            ("Fabia", 9000),
+            ("Octavia", 15000),
+            ("Fabia", 8000),
+            ("Octavia", 14000),
+        ]
+        .into_iter()
+        .map(|(model, price)| Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: model.to_string(),
+            mileage: Mileage::Km(1000),
+            year: 2018,
+            price: Price::Eur(price),
+            url: "bla".to_string(),
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    fn prices(args: &[&str]) -> Vec<Price> {
+        let args =
+            Args::try_parse_ordered_from(std::iter::once("findcar").chain(args.iter().copied()))
+                .unwrap();
+        let pipeline: Pipeline = (&args).into();
+        pipeline
+            .execute(hits())
+            .into_iter()
+            .map(|h| h.price)
+            .collect()
+    }
+
+    #[test]
+    fn limit_applies_last() {
+        assert_eq!(
+            prices(&["--sort-by", "price", "--limit", "1"]),
+            vec![Price::Eur(8000)]
+        );
+        assert_eq!(
+            prices(&["--limit", "1", "--sort-by", "price"]),
+            vec![Price::Eur(8000)]
+        );
+        assert_eq!(
+            prices(&["--limit", "1", "--top-per-group", "model", "1"]),
+            vec![Price::Eur(9000)]
+        );
+    }
+
+    #[test]
+    fn top_per_group_and_offset_compose_with_sort() {
+        assert_eq!(
+            prices(&["--sort-by", "price", "--top-per-group", "model", "1"]),
+            vec![Price::Eur(8000), Price::Eur(14000)]
+        );
+        assert_eq!(
+            prices(&["--top-per-group", "model", "1", "--sort-by", "price"]),
+            vec![Price::Eur(9000), Price::Eur(15000)]
+        );
+        assert_eq!(
+            prices(&[
+                "--offset",
+                "1",
+                "--sort-by",
+                "price",
+                "--sort-order",
+                "DESC"
+            ]),
+            vec![Price::Eur(15000), Price::Eur(14000), Price::Eur(8000)]
+        );
+    }
+
+    #[test]
+    fn sorting_by_derived_field_derives_fields() {
+        let args = Cli::parse_from(["findcar", "--sort-by", "age", "--sort-order", "DESC"]).search;
+        let pipeline: Pipeline = (&args).into();
+        let result = pipeline.execute(hits());
+
+        assert!(result
+            .iter()
+            .all(|h| h.age.is_some() && h.km_per_year.is_some()));
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of seconds since 1970-01-01 (UTC), according to the system clock.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The current calendar year (UTC), according to the system clock.
+pub fn current_year() -> u16 {
+    year_from_days((now() / 86_400) as i64)
+}
+
+/// Format a number of seconds since 1970-01-01 as a UTC date and time, e.g. `2023-05-17 14:03`.
+pub fn format_timestamp(seconds: u64) -> String {
+    let (year, month, day) = date_from_days((seconds / 86_400) as i64);
+    let minutes = seconds % 86_400 / 60;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// The year of the date that is `days` days after 1970-01-01, in the proleptic Gregorian calendar.
+fn year_from_days(days: i64) -> u16 {
+    date_from_days(days).0
+}
+
+/// The year, month and day of the date that is `days` days after 1970-01-01, in the proleptic
+/// Gregorian calendar.
+fn date_from_days(days: i64) -> (u16, u8, u8) {
+    // Shift the epoch to 0000-03-01 so that leap days fall at the end of each 400 year era
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    // Months are counted from March, so that January and February belong to the next year
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as u16, month as u8, day as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch() {
+        assert_eq!(year_from_days(0), 1970);
+    }
+
+    #[test]
+    fn year_boundaries() {
+        // 2023-12-31 and 2024-01-01
+        assert_eq!(year_from_days(19_722), 2023);
+        assert_eq!(year_from_days(19_723), 2024);
+        // 2024-02-29 and 2024-12-31
+        assert_eq!(year_from_days(19_782), 2024);
+        assert_eq!(year_from_days(20_088), 2024);
+        assert_eq!(year_from_days(20_089), 2025);
+    }
+
+    #[test]
+    fn dates_and_timestamps() {
+        assert_eq!(date_from_days(0), (1970, 1, 1));
+        assert_eq!(date_from_days(19_782), (2024, 2, 29));
+        assert_eq!(date_from_days(20_088), (2024, 12, 31));
+        assert_eq!(format_timestamp(1_684_332_180), "2023-05-17 14:03");
+    }
+}
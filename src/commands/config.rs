@@ -0,0 +1,30 @@
+use crate::args::{Cli, ConfigAction};
+use crate::paths;
+
+/// Render a value as a TOML string, so that the effective configuration can be used as a
+/// configuration file.
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+pub fn run(cli: &Cli, action: &ConfigAction) {
+    match action {
+        ConfigAction::Path => println!("{}", paths::config_dir().display()),
+        ConfigAction::Show => {
+            for setting in &cli.configuration {
+                let value = match setting.values.as_slice() {
+                    [value] => quote(value),
+                    values => format!(
+                        "[{}]",
+                        values
+                            .iter()
+                            .map(|v| quote(v))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                };
+                println!("{} = {}  # {}", setting.name, value, setting.source);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::args::GlobalArgs;
+use crate::emit::render_table;
+use crate::search::Registry;
+
+use super::{registry, selected_engines};
+
+/// A table of the registered search engines, and whether each of them is enabled.
+fn listing(registry: &Registry, enabled: &[String]) -> String {
+    let header = ["name", "country", "currency", "enabled", "description"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let rows = registry
+        .registrations()
+        .iter()
+        .map(|r| {
+            vec![
+                r.name.clone(),
+                r.country.clone(),
+                r.currency.clone(),
+                if enabled.contains(&r.name) {
+                    "yes"
+                } else {
+                    "no"
+                }
+                .to_string(),
+                r.description.clone(),
+            ]
+        })
+        .collect();
+    render_table(header, rows, 5)
+}
+
+/// List the available search engines, and which of them the global options enable.
+pub async fn run(global: &GlobalArgs) {
+    let registry = registry(global).await;
+    let enabled = selected_engines(&registry, global);
+    println!("{}", listing(&registry, &enabled));
+}
+
+#[cfg(all(test, feature = "carzone_ie", feature = "donedeal_ie"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_engine() {
+        let registry = Registry::builtin();
+        let listing = listing(&registry, &["donedeal_ie".to_string()]);
+        let rows: Vec<Vec<&str>> = listing
+            .lines()
+            .map(|line| line.split_whitespace().take(4).collect())
+            .collect();
+
+        // Other search engines may be enabled by features
+        assert_eq!(rows.len(), registry.registrations().len() + 2);
+        assert_eq!(rows[0], ["name", "country", "currency", "enabled"]);
+        assert_eq!(rows[2], ["carzone_ie", "IE", "EUR", "no"]);
+        assert_eq!(rows[3], ["donedeal_ie", "IE", "EUR", "yes"]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::args::HistoryArgs;
+use crate::calendar::{format_timestamp, now};
+use crate::emit::render_table;
+use crate::paths;
+
+/// A previous search, as stored in the history file.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since 1970-01-01 (UTC)
+    timestamp: u64,
+    /// The command line arguments of the search, excluding the program name
+    command: Vec<String>,
+    /// The number of results found
+    results: usize,
+}
+
+/// The history is stored as JSON lines, with one entry per search.
+fn path() -> PathBuf {
+    paths::data_dir().join("history.jsonl")
+}
+
+/// Quote an argument if it would otherwise not be read back as a single argument by a shell.
+fn quote(argument: &str) -> String {
+    if argument.is_empty()
+        || argument.contains(|c: char| c.is_whitespace() || "'\"<>|&;".contains(c))
+    {
+        format!("'{}'", argument.replace('\'', "'\\''"))
+    } else {
+        argument.to_string()
+    }
+}
+
+fn append(entry: &Entry) -> std::io::Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+fn read() -> std::io::Result<Vec<Entry>> {
+    match std::fs::read_to_string(path()) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Record a search that was just performed with the current command line.
+pub fn record(results: usize) {
+    let entry = Entry {
+        timestamp: now(),
+        command: std::env::args().skip(1).collect(),
+        results,
+    };
+    if let Err(e) = append(&entry) {
+        eprintln!("Unable to record search in history: {}", e);
+    }
+}
+
+fn render(entries: &[Entry], limit: usize) -> String {
+    let header = ["#", "time", "results", "command"]
+        .map(String::from)
+        .to_vec();
+    let rows = entries
+        .iter()
+        .enumerate()
+        .skip(entries.len().saturating_sub(limit))
+        .map(|(i, entry)| {
+            let command: Vec<String> = entry.command.iter().map(|a| quote(a)).collect();
+            vec![
+                (i + 1).to_string(),
+                format_timestamp(entry.timestamp),
+                entry.results.to_string(),
+                format!("findcar {}", command.join(" ")),
+            ]
+        })
+        .collect();
+    render_table(header, rows, 4)
+}
+
+pub fn run(args: &HistoryArgs) {
+    if args.clear {
+        match std::fs::remove_file(path()) {
+            Ok(()) => println!("History cleared"),
+            Err(e) if e.kind() == ErrorKind::NotFound => println!("History cleared"),
+            Err(e) => eprintln!("Unable to clear history: {}", e),
+        }
+        return;
+    }
+
+    match read() {
+        Ok(entries) if entries.is_empty() => println!("No searches yet"),
+        Ok(entries) => println!("{}", render(&entries, args.limit)),
+        Err(e) => eprintln!("Unable to read history: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, command: &[&str], results: usize) -> Entry {
+        Entry {
+            timestamp,
+            command: command.iter().map(|a| a.to_string()).collect(),
+            results,
+        }
+    }
+
+    #[test]
+    fn renders_most_recent_entries() {
+        let entries = vec![
+            entry(1_684_332_180, &["--make", "Skoda"], 12),
+            entry(1_684_335_780, &["--make", "Toyota"], 3),
+            entry(1_684_339_380, &["--keyword", "full service history"], 140),
+        ];
+
+        assert_eq!(
+            render(&entries, 2),
+            [
+                "#  time              results  command",
+                "-  ----------------  -------  ----------------------------------------",
+                "2  2023-05-17 15:03  3        findcar --make Toyota",
+                "3  2023-05-17 16:03  140      findcar --keyword 'full service history'",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,12 @@
+use crate::args::GlobalArgs;
+use crate::taxonomy::{discovery::support_matrix, Taxonomy};
+
+use super::searchers;
+
+/// List the makes or, if a make is provided, the models of that make, that the selected search
+/// engines support.
+pub async fn run(global: &GlobalArgs, make: Option<&str>) {
+    let taxonomy: Taxonomy = global.into();
+    let matrix = support_matrix(&searchers(global).await, &taxonomy, make).await;
+    println!("{}", matrix);
+}
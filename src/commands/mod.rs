@@ -0,0 +1,192 @@
+use crate::args::GlobalArgs;
+use crate::emit::{
+    colour_supported, Column, CsvEmitter, Emit, HtmlEmitter, JsonEmitter, NdjsonEmitter,
+    TextEmitter,
+};
+use crate::engine::Engine;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::paths;
+use crate::query::Query;
+use crate::search::{discover_plugins, discover_specs, Registration, Registry, Searcher};
+
+pub mod config;
+pub mod engines;
+pub mod history;
+pub mod makes;
+pub mod search;
+pub mod serve;
+pub mod watch;
+
+/// The directory given by an option, or else the named directory in the configuration directory.
+fn dir(option: &Option<String>, name: &str) -> PathBuf {
+    option
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| paths::config_dir().join(name))
+}
+
+/// The built-in search engines, those described by the specs in the spec directory, and those of
+/// the plugins in the plugin directory. None of these can replace a search engine of the same name.
+async fn registry(global: &GlobalArgs) -> Registry {
+    let mut registry = Registry::builtin();
+    let specs = discover_specs(&dir(&global.spec_dir, "specs"))
+        .into_iter()
+        .map(|searcher| searcher.registration());
+    let plugins = discover_plugins(
+        &dir(&global.plugin_dir, "plugins"),
+        Duration::from_secs(global.plugin_timeout),
+    )
+    .await
+    .into_iter()
+    .map(|plugin| plugin.registration());
+
+    let additions: Vec<Registration> = specs.chain(plugins).collect();
+    for registration in additions {
+        if registry.get(&registration.name).is_some() {
+            eprintln!(
+                "Ignoring search engine {}: a search engine of that name already exists",
+                registration.name
+            );
+            continue;
+        }
+        registry.register(registration);
+    }
+    registry
+}
+
+/// The names of the search engines selected through the global options: all of them if none are
+/// selected, less the disabled ones. Exits if any name is not that of a search engine.
+fn selected_engines(registry: &Registry, global: &GlobalArgs) -> Vec<String> {
+    let selected = global.search_engine.clone().unwrap_or_default();
+    let disabled = global.disable_engine.clone().unwrap_or_default();
+    match registry.select(&selected, &disabled) {
+        Ok(names) => names.into_iter().map(String::from).collect(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// The searchers of the search engines selected through the global options.
+async fn searchers(global: &GlobalArgs) -> Vec<Box<dyn Searcher>> {
+    let registry = registry(global).await;
+    let names = selected_engines(&registry, global);
+    registry.searchers(&names.iter().map(String::as_str).collect::<Vec<&str>>())
+}
+
+/// The root engine, driving the selected search engines.
+async fn engine(global: &GlobalArgs) -> Engine {
+    Engine::with_searchers(searchers(global).await).with_taxonomy(global.into())
+}
+
+/// The emitter selected through the global options, for the hits of the query.
+fn emitter(global: &GlobalArgs, query: &Query) -> Box<dyn Emit> {
+    match &global.emitter {
+        Some(val) => {
+            if val.to_uppercase() == "JSON" {
+                Box::new(json_emitter(global))
+            } else if ["NDJSON", "JSONL"].contains(&val.to_uppercase().as_str()) {
+                Box::new(ndjson_emitter(global))
+            } else if val.to_uppercase() == "CSV" {
+                Box::new(csv_emitter(global))
+            } else if val.to_uppercase() == "HTML" {
+                Box::new(html_emitter(global, query))
+            } else {
+                Box::new(text_emitter(global))
+            }
+        }
+        None => Box::new(text_emitter(global)),
+    }
+}
+
+/// The text emitter, in colour as selected through the global options.
+fn text_emitter(global: &GlobalArgs) -> TextEmitter {
+    let colour = match global.colour.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("auto") => colour_supported(),
+        Some("always") => true,
+        Some("never") => false,
+        Some(other) => {
+            eprintln!(
+                "Unrecognised colour option: {}, defaulting to auto. Options are: auto, always, never",
+                other
+            );
+            colour_supported()
+        }
+    };
+    let emitter = TextEmitter::new().with_colour(colour);
+    match columns(global) {
+        Some(columns) => emitter.with_columns(columns),
+        None => emitter,
+    }
+}
+
+/// The JSON emitter, emitting the columns selected through the global options if any.
+fn json_emitter(global: &GlobalArgs) -> JsonEmitter {
+    match columns(global) {
+        Some(columns) => JsonEmitter::new().with_columns(columns),
+        None if global.flatten => JsonEmitter::new().flattened(),
+        None => JsonEmitter::new(),
+    }
+}
+
+/// The NDJSON emitter, emitting the columns selected through the global options if any.
+fn ndjson_emitter(global: &GlobalArgs) -> NdjsonEmitter {
+    match columns(global) {
+        Some(columns) => NdjsonEmitter::new().with_columns(columns),
+        None if global.flatten => NdjsonEmitter::new().flattened(),
+        None => NdjsonEmitter::new(),
+    }
+}
+
+/// The HTML emitter, showing the query and the columns selected through the global options if any.
+fn html_emitter(global: &GlobalArgs, query: &Query) -> HtmlEmitter {
+    let emitter = HtmlEmitter::new().with_query(query.clone());
+    match columns(global) {
+        Some(columns) => emitter.with_columns(columns),
+        None => emitter,
+    }
+}
+
+/// The CSV emitter, with the columns, delimiter and header selected through the global options.
+fn csv_emitter(global: &GlobalArgs) -> CsvEmitter {
+    let delimiter = match global.csv_delimiter.as_deref() {
+        None => b',',
+        Some("tab") | Some("\\t") => b'\t',
+        Some(delimiter) if delimiter.len() == 1 => delimiter.as_bytes()[0],
+        Some(other) => {
+            eprintln!(
+                "Unrecognised CSV delimiter: {}, defaulting to a comma. The delimiter must be a \
+                 single character, or \"tab\"",
+                other
+            );
+            b','
+        }
+    };
+    let emitter = CsvEmitter::new()
+        .with_delimiter(delimiter)
+        .with_header(!global.csv_no_header);
+    match columns(global) {
+        Some(columns) => emitter.with_columns(columns),
+        None => emitter,
+    }
+}
+
+/// The columns selected through the global options, leaving out those that are not recognised.
+fn columns(global: &GlobalArgs) -> Option<Vec<Column>> {
+    let columns: Vec<Column> = global
+        .columns
+        .iter()
+        .flatten()
+        .filter_map(|name| match Column::try_from(name.trim()) {
+            Ok(column) => Some(column),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        })
+        .collect();
+    Some(columns).filter(|columns| !columns.is_empty())
+}
@@ -0,0 +1,54 @@
+use futures::StreamExt;
+
+use crate::aggregate::Aggregation;
+use crate::args::{Args, GlobalArgs};
+use crate::post_processing::{Action, Pipeline};
+use crate::query::Query;
+
+use super::{emitter, engine, history};
+
+/// The post-processing pipeline of a search. If it is invalid, every error is reported and the
+/// process exits before searching.
+pub fn pipeline_or_exit(args: &Args) -> Pipeline {
+    args.pipeline().unwrap_or_else(|errors| {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        std::process::exit(2);
+    })
+}
+
+pub async fn run(global: &GlobalArgs, args: &Args) {
+    let pipeline = pipeline_or_exit(args);
+    let query: Query = args.into();
+    let emitter = emitter(global, &query);
+    let aggregation: Option<Aggregation> = args.into();
+    let engine = engine(global).await;
+
+    // Hits are emitted as search engines return them, unless they have to be processed or
+    // emitted all at once
+    if emitter.streams() && pipeline.per_hit() && aggregation.is_none() {
+        let mut count = 0;
+        let mut batches = std::pin::pin!(engine.stream(&query));
+        while let Some(hits) = batches.next().await {
+            let processed = pipeline.execute(hits);
+            count += processed.len();
+            emitter.emit(processed);
+        }
+        if !global.no_history {
+            history::record(count);
+        }
+        return;
+    }
+
+    let results = engine.search(&query).await;
+    let processed = pipeline.execute(results);
+    if !global.no_history {
+        history::record(processed.len());
+    }
+
+    match aggregation {
+        Some(aggregation) => emitter.emit_groups(aggregation.execute(&processed)),
+        None => emitter.emit(processed),
+    }
+}
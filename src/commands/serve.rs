@@ -0,0 +1,267 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::aggregate::Aggregation;
+use crate::args::{Args, GlobalArgs, ServeArgs};
+use crate::engine::Engine;
+use crate::post_processing::Action;
+use crate::query::Query;
+
+use super::engine;
+
+/// The maximum size of a request's head; bodies are not read.
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+
+/// The time a client has to send the head of its request, after which the connection is closed.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn decode(value: &str) -> String {
+    let value = value.replace('+', " ");
+    urlencoding::decode(&value)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or(value)
+}
+
+/// Turn a query string into the command line arguments of a search, e.g.
+/// `make=Toyota&sort-by=price&derived-fields` into `--make Toyota --sort-by price --derived-fields`.
+fn arguments(query_string: &str) -> Vec<String> {
+    let mut arguments = vec!["findcar".to_string()];
+    for pair in query_string.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        arguments.push(format!("--{}", decode(key)));
+        if !value.is_empty() {
+            arguments.push(decode(value));
+        }
+    }
+    arguments
+}
+
+fn error(reason: &str) -> String {
+    serde_json::json!({ "error": reason }).to_string()
+}
+
+/// The option of a search that reads a local file, if any. Clients of the server may not use
+/// these, as they would be able to probe the files of the machine it runs on.
+fn file_option(args: &Args) -> Option<&'static str> {
+    let valuation_reference = args
+        .step
+        .iter()
+        .flatten()
+        .any(|step| step.name == "valuation" && !step.args.is_empty());
+    if args.pipeline_file.is_some() {
+        Some("pipeline-file")
+    } else if args.valuation_reference.is_some() {
+        Some("valuation-reference")
+    } else if valuation_reference {
+        Some("step=valuation:<reference file>")
+    } else {
+        None
+    }
+}
+
+async fn search(engine: &Engine, query_string: &str) -> (&'static str, String) {
+    let args = match Args::try_parse_ordered_from(arguments(query_string)) {
+        Ok(args) => args,
+        Err(e) => return ("400 Bad Request", error(e.to_string().trim())),
+    };
+    if let Some(option) = file_option(&args) {
+        return (
+            "400 Bad Request",
+            error(&format!(
+                "{} reads a local file, which is not supported when serving",
+                option
+            )),
+        );
+    }
+    let pipeline = match args.pipeline() {
+        Ok(pipeline) => pipeline,
+        Err(errors) => return ("400 Bad Request", error(&errors.join("; "))),
+    };
+
+    let query: Query = (&args).into();
+    let processed = pipeline.execute(engine.search(&query).await);
+    let aggregation: Option<Aggregation> = (&args).into();
+    let body = match aggregation {
+        Some(aggregation) => serde_json::to_string(&aggregation.execute(&processed)),
+        None => serde_json::to_string(&processed),
+    };
+    match body {
+        Ok(body) => ("200 OK", body),
+        Err(e) => ("500 Internal Server Error", error(&e.to_string())),
+    }
+}
+
+/// The status and body of the response to a request, given its request line.
+async fn respond(engine: &Engine, request_line: &str) -> (&'static str, String) {
+    let mut parts = request_line.split_whitespace();
+    let target = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => target,
+        _ => {
+            return (
+                "405 Method Not Allowed",
+                error("only GET requests are supported"),
+            )
+        }
+    };
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+    match path {
+        "/search" => search(engine, query_string).await,
+        _ => (
+            "404 Not Found",
+            error("unknown path, searches are served at /search"),
+        ),
+    }
+}
+
+/// Read the head of a request, giving up if it is not complete within the provided time.
+async fn read_head(stream: &mut TcpStream, timeout: Duration) -> std::io::Result<Vec<u8>> {
+    let read = async {
+        let mut request = vec![];
+        let mut buffer = [0; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+            let n = stream.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..n]);
+        }
+        Ok(request)
+    };
+    tokio::time::timeout(timeout, read).await.map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "timed out reading the request",
+        )
+    })?
+}
+
+async fn handle(mut stream: TcpStream, engine: &Engine) -> std::io::Result<()> {
+    let request = read_head(&mut stream, READ_TIMEOUT).await?;
+
+    let request = String::from_utf8_lossy(&request);
+    let (status, body) = respond(engine, request.lines().next().unwrap_or_default()).await;
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await
+}
+
+pub async fn run(global: &GlobalArgs, args: &ServeArgs) {
+    let listener = TcpListener::bind(&args.address).await.unwrap_or_else(|e| {
+        eprintln!("Unable to listen on {}: {}", args.address, e);
+        std::process::exit(1);
+    });
+    eprintln!("Serving searches at http://{}/search", args.address);
+
+    let engine = Arc::new(engine(global).await);
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("Unable to accept connection: {}", e);
+                continue;
+            }
+        };
+        let engine = engine.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &engine).await {
+                eprintln!("Unable to respond to request: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_string_to_arguments() {
+        assert_eq!(
+            arguments(
+                "make=Toyota&keyword=full+service%20history&derived-fields&filter=year%3E%3D2018"
+            ),
+            vec![
+                "findcar",
+                "--make",
+                "Toyota",
+                "--keyword",
+                "full service history",
+                "--derived-fields",
+                "--filter",
+                "year>=2018",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_requests() {
+        let engine = Engine::with_searchers(vec![]);
+
+        assert_eq!(
+            respond(&engine, "POST /search HTTP/1.1").await.0,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(
+            respond(&engine, "GET /cars HTTP/1.1").await.0,
+            "404 Not Found"
+        );
+        assert_eq!(
+            respond(&engine, "GET /search?colour=red HTTP/1.1").await.0,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            respond(&engine, "GET /search?step=shuffle HTTP/1.1")
+                .await
+                .0,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            respond(&engine, "GET /search?make=Skoda HTTP/1.1").await,
+            ("200 OK", "[]".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn options_that_read_files_are_rejected() {
+        let engine = Engine::with_searchers(vec![]);
+
+        for query_string in [
+            "pipeline-file=%2Fetc%2Fpasswd",
+            "pipeline-file%3D%2Fetc%2Fpasswd",
+            "valuation-reference=%2Fetc%2Fpasswd",
+            "step=valuation%3A%2Fetc%2Fpasswd",
+        ] {
+            let (status, body) =
+                respond(&engine, &format!("GET /search?{} HTTP/1.1", query_string)).await;
+            assert_eq!(status, "400 Bad Request");
+            assert!(body.contains("reads a local file"), "{}", body);
+            assert!(!body.contains("passwd"), "{}", body);
+        }
+        assert_eq!(
+            respond(&engine, "GET /search?step=valuation HTTP/1.1").await,
+            ("200 OK", "[]".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn idle_clients_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(address).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let error = read_head(&mut stream, Duration::from_millis(100))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::args::{GlobalArgs, WatchArgs};
+use crate::hit::Hit;
+use crate::post_processing::Action;
+use crate::query::Query;
+
+use super::{emitter, engine, search::pipeline_or_exit};
+
+/// The hits that have not been seen before, by URL, marking them as seen.
+fn unseen(seen: &mut HashSet<String>, hits: Vec<Hit>) -> Vec<Hit> {
+    hits.into_iter()
+        .filter(|hit| seen.insert(hit.url.clone()))
+        .collect()
+}
+
+pub async fn run(global: &GlobalArgs, args: &WatchArgs) {
+    let pipeline = pipeline_or_exit(&args.search);
+    let query: Query = (&args.search).into();
+    let engine = engine(global).await;
+    let emitter = emitter(global, &query);
+
+    eprintln!("Searching every {} seconds", args.interval);
+    let mut seen = HashSet::new();
+    loop {
+        let processed = pipeline.execute(engine.search(&query).await);
+        let new = unseen(&mut seen, processed);
+        if !new.is_empty() {
+            emitter.emit(new);
+        }
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(url: &str) -> Hit {
+        Hit {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn only_new_hits_are_kept() {
+        let mut seen = HashSet::new();
+        let first = unseen(&mut seen, vec![hit("a"), hit("b")]);
+        let second = unseen(&mut seen, vec![hit("b"), hit("c"), hit("c")]);
+
+        assert_eq!(first, vec![hit("a"), hit("b")]);
+        assert_eq!(second, vec![hit("c")]);
+    }
+}
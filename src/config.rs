@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use serde::Deserialize;
+
+use crate::paths;
+
+/// The subcommands whose options can be configured, besides the global options and the options
+/// of a search without a subcommand.
+const CONFIGURABLE_SUBCOMMANDS: [&str; 2] = ["search", "watch"];
+
+type Options = BTreeMap<String, toml::Value>;
+
+/// A configuration file, which sets defaults for any option by its name, e.g.
+/// `sort_by = "price"`, and may define named profiles that overlay these defaults:
+///
+/// ```toml
+/// emitter = "json"
+///
+/// [profiles.commute-car]
+/// vehicle = ["Toyota:Corolla", "Honda:Civic"]
+/// max_price = 15000
+/// ```
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, Options>,
+    #[serde(flatten)]
+    options: Options,
+}
+
+/// The configuration in effect: the options set by the configuration file, and those set by the
+/// selected profile. Options given on the command line take precedence over environment
+/// variables (`FINDCAR_<OPTION>`), which take precedence over the profile, which takes
+/// precedence over the configuration file.
+#[derive(Debug, Default)]
+pub struct Config {
+    path: Option<PathBuf>,
+    profile: Option<String>,
+    file: BTreeMap<String, Vec<String>>,
+    overlay: BTreeMap<String, Vec<String>>,
+}
+
+/// A single option of the effective configuration, and where its value comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub name: String,
+    pub values: Vec<String>,
+    pub source: String,
+}
+
+/// The value of an option given on the command line, either as `--option value` or as
+/// `--option=value`, or otherwise of its environment variable.
+fn find_option(arguments: &[OsString], option: &str) -> Option<String> {
+    let flag = format!("--{}", option);
+    let prefix = format!("{}=", flag);
+    let mut arguments = arguments.iter().map(|a| a.to_string_lossy());
+    while let Some(argument) = arguments.next() {
+        if argument == flag {
+            return arguments.next().map(|a| a.to_string());
+        }
+        if let Some(value) = argument.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+    std::env::var(format!("FINDCAR_{}", option.to_uppercase())).ok()
+}
+
+/// The values of an option as they would be given on the command line.
+fn values(name: &str, value: &toml::Value) -> Result<Vec<String>, String> {
+    match value {
+        toml::Value::String(s) => Ok(vec![s.clone()]),
+        toml::Value::Integer(i) => Ok(vec![i.to_string()]),
+        toml::Value::Float(f) => Ok(vec![f.to_string()]),
+        toml::Value::Boolean(b) => Ok(vec![b.to_string()]),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                toml::Value::Array(_) | toml::Value::Table(_) => Err(format!(
+                    "invalid value for {}: nested values are not supported",
+                    name
+                )),
+                item => values(name, item).map(|mut v| v.remove(0)),
+            })
+            .collect(),
+        _ => Err(format!(
+            "invalid value for {}: expected a value or a list",
+            name
+        )),
+    }
+}
+
+/// Options by their identifier, which is their name with underscores rather than hyphens.
+fn normalise(options: &Options) -> Result<BTreeMap<String, Vec<String>>, String> {
+    options
+        .iter()
+        .map(|(name, value)| Ok((name.replace('-', "_"), values(name, value)?)))
+        .collect()
+}
+
+/// The identifiers of the options of a command, excluding help and version.
+fn option_ids(command: &Command) -> Vec<String> {
+    command
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .filter(|id| id != "help" && id != "version")
+        .collect()
+}
+
+impl Config {
+    /// Load the configuration file given with `--config`, or else the one at the default
+    /// location if it exists, and select the profile given with `--profile`, if any.
+    pub fn load(arguments: &[OsString]) -> Result<Self, String> {
+        let profile = find_option(arguments, "profile");
+        let path = match find_option(arguments, "config") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Some(paths::config_dir().join("config.toml")).filter(|path| path.exists()),
+        };
+
+        let file: ConfigFile = match &path {
+            None => ConfigFile::default(),
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| toml::from_str(&s).map_err(|e| e.to_string()))
+                .map_err(|e| format!("Unable to read configuration {}: {}", path.display(), e))?,
+        };
+        Self::from_file(path, file, profile)
+    }
+
+    fn from_file(
+        path: Option<PathBuf>,
+        file: ConfigFile,
+        profile: Option<String>,
+    ) -> Result<Self, String> {
+        let overlay = match &profile {
+            None => BTreeMap::new(),
+            Some(name) => match file.profiles.get(name) {
+                Some(options) => normalise(options)?,
+                None => {
+                    let known: Vec<&str> = file.profiles.keys().map(String::as_str).collect();
+                    return Err(format!(
+                        "Unknown profile: {}. Profiles are: {}",
+                        name,
+                        if known.is_empty() {
+                            "none".to_string()
+                        } else {
+                            known.join(", ")
+                        }
+                    ));
+                }
+            },
+        };
+
+        Ok(Self {
+            path,
+            profile,
+            file: normalise(&file.options)?,
+            overlay,
+        })
+    }
+
+    /// The configured value of every option, the profile's taking precedence over the file's.
+    fn defaults(&self) -> BTreeMap<String, Vec<String>> {
+        let mut defaults = self.file.clone();
+        defaults.extend(self.overlay.clone());
+        defaults
+    }
+
+    fn apply_to(mut command: Command, defaults: &BTreeMap<String, Vec<String>>) -> Command {
+        for id in option_ids(&command) {
+            let env = format!("FINDCAR_{}", id.to_uppercase());
+            let values = defaults.get(&id).cloned();
+            command = command.mut_arg(&id, |arg| {
+                let arg = arg.env(env);
+                match values {
+                    Some(values) => arg.default_values(values),
+                    None => arg,
+                }
+            });
+        }
+        command
+    }
+
+    /// Make the configured values the defaults of the command's options, and let every option be
+    /// set through its environment variable as well.
+    pub fn apply(&self, mut command: Command) -> Command {
+        let defaults = self.defaults();
+
+        let mut known = option_ids(&command);
+        for name in CONFIGURABLE_SUBCOMMANDS {
+            if let Some(subcommand) = command.find_subcommand(name) {
+                known.extend(option_ids(subcommand));
+            }
+        }
+        for name in defaults.keys().filter(|name| !known.contains(name)) {
+            eprintln!("Unknown option in configuration: {}, ignoring", name);
+        }
+
+        command = Self::apply_to(command, &defaults);
+        for name in CONFIGURABLE_SUBCOMMANDS {
+            if command.find_subcommand(name).is_some() {
+                command = command
+                    .mut_subcommand(name, |subcommand| Self::apply_to(subcommand, &defaults));
+            }
+        }
+        command
+    }
+
+    /// Every option that has a value, and where its value comes from.
+    pub fn describe(&self, command: &Command, matches: &ArgMatches) -> Vec<Setting> {
+        option_ids(command)
+            .into_iter()
+            .filter_map(|id| {
+                let source = match matches.value_source(&id)? {
+                    ValueSource::CommandLine => "command line".to_string(),
+                    ValueSource::EnvVariable => format!("FINDCAR_{}", id.to_uppercase()),
+                    _ if self.overlay.contains_key(&id) => {
+                        format!("profile {}", self.profile.as_deref().unwrap_or_default())
+                    }
+                    _ if self.file.contains_key(&id) => match &self.path {
+                        Some(path) => path.display().to_string(),
+                        None => "configuration file".to_string(),
+                    },
+                    _ => "default".to_string(),
+                };
+                let values = matches
+                    .get_raw(&id)?
+                    .map(|v| v.to_string_lossy().to_string())
+                    .collect();
+                Some(Setting {
+                    name: id,
+                    values,
+                    source,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{Arg, ArgAction};
+
+    use super::*;
+
+    fn config(profile: Option<&str>) -> Result<Config, String> {
+        let file: ConfigFile = toml::from_str(
+            r#"
+            emitter = "json"
+            sort-by = "year"
+            search_engine = ["carzone_ie", "donedeal_ie"]
+
+            [profiles.commute-car]
+            sort_by = "price"
+            max_price = 15000
+            "#,
+        )
+        .unwrap();
+        Config::from_file(None, file, profile.map(String::from))
+    }
+
+    fn command() -> Command {
+        Command::new("findcar")
+            .arg(Arg::new("emitter").long("emitter"))
+            .arg(Arg::new("sort_by").long("sort-by"))
+            .arg(Arg::new("max_price").long("max-price"))
+            .arg(
+                Arg::new("search_engine")
+                    .long("search-engine")
+                    .action(ArgAction::Append),
+            )
+            .arg(Arg::new("limit").long("limit").default_value("10"))
+    }
+
+    #[test]
+    fn profiles_overlay_the_file() {
+        let config = config(Some("commute-car")).unwrap();
+        let command = config.apply(command());
+        let matches = command
+            .clone()
+            .get_matches_from(["findcar", "--emitter", "csv"]);
+        let settings = config.describe(&command, &matches);
+
+        let describe = |name: &str| {
+            settings
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| (s.values.join(","), s.source.clone()))
+                .unwrap()
+        };
+        assert_eq!(
+            describe("emitter"),
+            ("csv".to_string(), "command line".to_string())
+        );
+        assert_eq!(
+            describe("sort_by"),
+            ("price".to_string(), "profile commute-car".to_string())
+        );
+        assert_eq!(
+            describe("max_price"),
+            ("15000".to_string(), "profile commute-car".to_string())
+        );
+        assert_eq!(
+            describe("search_engine"),
+            (
+                "carzone_ie,donedeal_ie".to_string(),
+                "configuration file".to_string()
+            )
+        );
+        assert_eq!(describe("limit"), ("10".to_string(), "default".to_string()));
+    }
+
+    #[test]
+    fn without_a_profile_only_the_file_applies() {
+        let config = config(None).unwrap();
+        let matches = config.apply(command()).get_matches_from(["findcar"]);
+
+        assert_eq!(matches.get_one::<String>("sort_by").unwrap(), "year");
+        assert_eq!(matches.get_one::<String>("max_price"), None);
+    }
+
+    #[test]
+    fn unknown_profiles_are_rejected() {
+        assert_eq!(
+            config(Some("sports-car")).err().unwrap(),
+            "Unknown profile: sports-car. Profiles are: commute-car"
+        );
+    }
+
+    #[test]
+    fn options_are_found_on_the_command_line() {
+        let arguments: Vec<OsString> = ["findcar", "--profile=commute-car", "--config", "a.toml"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+
+        assert_eq!(
+            find_option(&arguments, "profile"),
+            Some("commute-car".to_string())
+        );
+        assert_eq!(
+            find_option(&arguments, "config"),
+            Some("a.toml".to_string())
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::hit::{Hit, Mileage, Price};
+
+/// A property of a Hit that can be emitted as a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Price,
+    Year,
+    Mileage,
+    Make,
+    Model,
+    Fuel,
+    Title,
+    Description,
+    Matched,
+    Age,
+    KmPerYear,
+    PricePer1000Km,
+    ExpectedPrice,
+    Residual,
+    DealScore,
+    Anomalies,
+    Engine,
+    Url,
+}
+
+/// Every column, in the order in which they are emitted.
+pub const ALL: [Column; 18] = [
+    Column::Price,
+    Column::Year,
+    Column::Mileage,
+    Column::Make,
+    Column::Model,
+    Column::Fuel,
+    Column::Title,
+    Column::Description,
+    Column::Matched,
+    Column::Age,
+    Column::KmPerYear,
+    Column::PricePer1000Km,
+    Column::ExpectedPrice,
+    Column::Residual,
+    Column::DealScore,
+    Column::Anomalies,
+    Column::Engine,
+    Column::Url,
+];
+
+impl Column {
+    /// The columns that are emitted unless others are chosen. Of those that only some hits have a
+    /// value for, such as the deal score, only the ones that any hit has a value for are emitted.
+    /// The residual is left out, as the deal score expresses it relative to the expected price.
+    pub fn defaults(hits: &[Hit]) -> Vec<Column> {
+        ALL.into_iter()
+            .filter(|column| {
+                !matches!(
+                    column,
+                    Column::Fuel | Column::Title | Column::Description | Column::Residual
+                )
+            })
+            .filter(|column| {
+                column.is_always_set() || hits.iter().any(|hit| column.value(hit).is_some())
+            })
+            .collect()
+    }
+
+    /// Whether every hit has a value for this column, if only an unknown one.
+    fn is_always_set(&self) -> bool {
+        matches!(
+            self,
+            Column::Price
+                | Column::Year
+                | Column::Mileage
+                | Column::Make
+                | Column::Model
+                | Column::Engine
+                | Column::Url
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Price => "price",
+            Column::Year => "year",
+            Column::Mileage => "mileage",
+            Column::Make => "make",
+            Column::Model => "model",
+            Column::Fuel => "fuel",
+            Column::Title => "title",
+            Column::Description => "description",
+            Column::Matched => "matched",
+            Column::Age => "age",
+            Column::KmPerYear => "km_per_year",
+            Column::PricePer1000Km => "price_per_1000_km",
+            Column::ExpectedPrice => "expected_price",
+            Column::Residual => "residual",
+            Column::DealScore => "deal_score",
+            Column::Anomalies => "anomalies",
+            Column::Engine => "engine",
+            Column::Url => "url",
+        }
+    }
+
+    /// Whether the values of this column are numbers, and so are aligned right.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Column::Price
+                | Column::Year
+                | Column::Mileage
+                | Column::Age
+                | Column::KmPerYear
+                | Column::PricePer1000Km
+                | Column::ExpectedPrice
+                | Column::Residual
+                | Column::DealScore
+        )
+    }
+
+    /// The value of this column for a hit as text, e.g. `€14950` or `62000 km`, if it has one.
+    pub fn value(&self, hit: &Hit) -> Option<String> {
+        match self {
+            Column::Price => price(&hit.price),
+            Column::Year => Some(hit.year)
+                .filter(|year| *year > 0)
+                .map(|year| year.to_string()),
+            Column::Mileage => Some(&hit.mileage)
+                .filter(|mileage| **mileage != Mileage::Unknown)
+                .map(Mileage::to_string),
+            Column::Make => Some(hit.make.clone()),
+            Column::Model => Some(hit.model.clone()),
+            Column::Fuel => hit.fuel.clone(),
+            Column::Title => hit.title.clone(),
+            Column::Description => hit.description.clone(),
+            Column::Matched => hit.matched.clone(),
+            Column::Age => hit.age.map(|age| age.to_string()),
+            Column::KmPerYear => hit.km_per_year.map(|kms| kms.to_string()),
+            Column::PricePer1000Km => hit.price_per_1000_km.as_ref().and_then(price),
+            Column::ExpectedPrice => hit.expected_price.as_ref().and_then(price),
+            Column::Residual => hit.residual.map(|residual| residual.to_string()),
+            Column::DealScore => hit.deal_score.map(|score| score.to_string()),
+            Column::Anomalies => {
+                Some(hit.anomalies.join("; ")).filter(|anomalies| !anomalies.is_empty())
+            }
+            Column::Engine => Some(hit.search_engine.clone()),
+            Column::Url => Some(hit.url.clone()),
+        }
+    }
+
+    /// The fields of this column in the flat representation of a hit, in which prices are split
+    /// into `price_amount` and `price_currency`, and mileages into `mileage_value` and
+    /// `mileage_unit`. Expected prices, residuals and prices per 1000 km are amounts in the
+    /// currency of the price. Fields that the hit has no value for are null.
+    pub fn fields(&self, hit: &Hit) -> Vec<(&'static str, Value)> {
+        let number = |number: Option<i32>| number.map_or(Value::Null, Value::from);
+        let text = |text: Option<&str>| text.map_or(Value::Null, Value::from);
+        match self {
+            Column::Price => vec![
+                ("price_amount", number(hit.price.amount())),
+                ("price_currency", text(hit.price.currency())),
+            ],
+            Column::Mileage => {
+                let (value, unit) = match hit.mileage {
+                    Mileage::Km(kms) => (Some(kms), Some("km")),
+                    Mileage::Mi(mis) => (Some(mis), Some("mi")),
+                    Mileage::Unknown => (None, None),
+                };
+                vec![
+                    ("mileage_value", number(value)),
+                    ("mileage_unit", text(unit)),
+                ]
+            }
+            Column::Year => vec![(
+                self.name(),
+                number(Some(hit.year.into()).filter(|year| *year > 0)),
+            )],
+            Column::Age => vec![(self.name(), number(hit.age.map(i32::from)))],
+            Column::KmPerYear => vec![(self.name(), number(hit.km_per_year))],
+            Column::PricePer1000Km => vec![(
+                self.name(),
+                number(hit.price_per_1000_km.as_ref().and_then(Price::amount)),
+            )],
+            Column::ExpectedPrice => vec![(
+                self.name(),
+                number(hit.expected_price.as_ref().and_then(Price::amount)),
+            )],
+            Column::Residual => vec![(self.name(), number(hit.residual))],
+            Column::DealScore => vec![(self.name(), number(hit.deal_score))],
+            _ => vec![(self.name(), text(self.value(hit).as_deref()))],
+        }
+    }
+
+    /// The names of the fields of this column in the flat representation of a hit.
+    pub fn field_names(&self) -> Vec<&'static str> {
+        self.fields(&Hit::default())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// A hit in its flat representation, with the fields of the columns in their order.
+pub struct Flat<'a> {
+    pub hit: &'a Hit,
+    pub columns: &'a [Column],
+}
+
+impl Serialize for Flat<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for column in self.columns {
+            for (name, value) in column.fields(self.hit) {
+                map.serialize_entry(name, &value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// A price as text, with the symbol of its currency, e.g. `€14950`.
+fn price(price: &Price) -> Option<String> {
+    let symbol = match price {
+        Price::Eur(_) => "€",
+        Price::Usd(_) => "$",
+        Price::Gbp(_) => "£",
+        Price::Unknown => return None,
+    };
+    Some(format!("{}{}", symbol, price.amount()?))
+}
+
+impl TryFrom<&str> for Column {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        ALL.into_iter()
+            .find(|column| column.name() == value)
+            .ok_or_else(|| {
+                format!(
+                    "Unrecognised column: {}. Options are: {}",
+                    value,
+                    ALL.map(|column| column.name()).join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_columns_leave_out_those_without_values() {
+        let hit = Hit {
+            deal_score: Some(12),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Column::defaults(&[hit]),
+            vec![
+                Column::Price,
+                Column::Year,
+                Column::Mileage,
+                Column::Make,
+                Column::Model,
+                Column::DealScore,
+                Column::Engine,
+                Column::Url,
+            ]
+        );
+    }
+
+    #[test]
+    fn prices_and_mileages_are_flattened() {
+        let hit = Hit {
+            price: Price::Gbp(8995),
+            mileage: Mileage::Mi(42500),
+            year: 2017,
+            expected_price: Some(Price::Gbp(9500)),
+            residual: Some(-505),
+            ..Default::default()
+        };
+        let columns = [
+            Column::Year,
+            Column::Price,
+            Column::Mileage,
+            Column::ExpectedPrice,
+            Column::Residual,
+            Column::DealScore,
+        ];
+
+        assert_eq!(
+            serde_json::to_string(&Flat {
+                hit: &hit,
+                columns: &columns
+            })
+            .unwrap(),
+            r#"{"year":2017,"price_amount":8995,"price_currency":"GBP","mileage_value":42500,"#
+                .to_string()
+                + r#""mileage_unit":"mi","expected_price":9500,"residual":-505,"deal_score":null}"#
+        );
+        assert_eq!(
+            Column::Price.field_names(),
+            vec!["price_amount", "price_currency"]
+        );
+    }
+
+    #[test]
+    fn columns_are_parsed_by_name() {
+        assert_eq!(Column::try_from("km_per_year"), Ok(Column::KmPerYear));
+        assert!(Column::try_from("colour")
+            .unwrap_err()
+            .starts_with("Unrecognised column: colour. Options are: price, year, mileage,"));
+    }
+}
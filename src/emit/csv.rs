@@ -1,22 +1,197 @@
+use serde_json::Value;
+
+use crate::aggregate::{Group, Statistics};
 use crate::hit::Hit;
 
+use super::column::{Column, ALL};
 use super::Emit;
 
-pub struct CsvEmitter {}
+/// Emits hits as CSV in their flat representation, with a field per column, or the groups of an
+/// aggregation with a record per group and statistic.
+pub struct CsvEmitter {
+    columns: Vec<Column>,
+    delimiter: u8,
+    header: bool,
+}
+
+impl Default for CsvEmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl CsvEmitter {
+    /// An emitter of every column, separated by commas, with a header.
     pub fn new() -> Self {
-        CsvEmitter {}
+        CsvEmitter {
+            columns: ALL.to_vec(),
+            delimiter: b',',
+            header: true,
+        }
+    }
+
+    pub fn with_columns(self, columns: Vec<Column>) -> Self {
+        Self { columns, ..self }
+    }
+
+    pub fn with_delimiter(self, delimiter: u8) -> Self {
+        Self { delimiter, ..self }
+    }
+
+    /// Whether to start with a record of the names of the fields.
+    pub fn with_header(self, header: bool) -> Self {
+        Self { header, ..self }
+    }
+
+    fn writer<W: std::io::Write>(&self, writer: W) -> csv::Writer<W> {
+        csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer)
+    }
+
+    fn write_hits<W: std::io::Write>(&self, writer: W, hits: &[Hit]) -> csv::Result<()> {
+        let mut wtr = self.writer(writer);
+        if self.header {
+            wtr.write_record(self.columns.iter().flat_map(Column::field_names))?;
+        }
+        for hit in hits {
+            wtr.write_record(
+                self.columns
+                    .iter()
+                    .flat_map(|column| column.fields(hit))
+                    .map(|(_, value)| match value {
+                        Value::String(text) => text,
+                        Value::Null => String::new(),
+                        value => value.to_string(),
+                    }),
+            )?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+const STATISTICS_HEADER: [&str; 10] = [
+    "n", "min", "max", "mean", "median", "p10", "p25", "p75", "p90", "std_dev",
+];
+
+fn statistics_record(stats: &Option<Statistics>) -> Vec<String> {
+    match stats {
+        None => vec![String::new(); 10],
+        Some(s) => vec![
+            s.count.to_string(),
+            s.min.to_string(),
+            s.max.to_string(),
+            s.mean.to_string(),
+            s.median.to_string(),
+            s.p10.to_string(),
+            s.p25.to_string(),
+            s.p75.to_string(),
+            s.p90.to_string(),
+            s.std_dev.to_string(),
+        ],
     }
 }
 
 impl Emit for CsvEmitter {
     fn emit(&self, hits: Vec<Hit>) {
-        let mut wtr = csv::Writer::from_writer(std::io::stdout());
+        self.write_hits(std::io::stdout(), &hits)
+            .expect("Error writing CSV to stdout");
+    }
 
-        for hit in hits {
-            wtr.serialize(hit).unwrap();
+    /// Emits one record per group and metric, with the group's key values as the leading columns.
+    fn emit_groups(&self, groups: Vec<Group>) {
+        let mut wtr = self.writer(std::io::stdout());
+
+        if let Some(first) = groups.first().filter(|_| self.header) {
+            let mut header: Vec<&str> = first.keys.iter().map(|(key, _)| key.name()).collect();
+            header.extend(["count", "metric", "unit"]);
+            header.extend(STATISTICS_HEADER);
+            wtr.write_record(header).unwrap();
+        }
+
+        for group in groups {
+            let leading: Vec<String> = group
+                .keys
+                .iter()
+                .map(|(_, value)| value.clone())
+                .chain([group.count.to_string()])
+                .collect();
+
+            if group.metrics.is_empty() {
+                let mut record = leading.clone();
+                record.extend(vec![String::new(); 2 + STATISTICS_HEADER.len()]);
+                wtr.write_record(record).unwrap();
+            }
+
+            for summary in &group.metrics {
+                let mut record = leading.clone();
+                record.push(summary.metric.name().to_string());
+                record.push(summary.unit.clone());
+                record.extend(statistics_record(&summary.statistics));
+                wtr.write_record(record).unwrap();
+            }
         }
         wtr.flush().expect("Error flushing CSV to stdout");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn write(emitter: &CsvEmitter, hits: &[Hit]) -> String {
+        let mut out = vec![];
+        emitter.write_hits(&mut out, hits).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn hits_are_written_flat_in_the_chosen_columns() {
+        let hits = vec![
+            Hit {
+                make: "Skoda".to_string(),
+                model: "Octavia".to_string(),
+                price: Price::Eur(19995),
+                mileage: Mileage::Km(84000),
+                year: 2018,
+                ..Default::default()
+            },
+            Hit {
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                ..Default::default()
+            },
+        ];
+        let emitter = CsvEmitter::new().with_columns(vec![
+            Column::Model,
+            Column::Price,
+            Column::Mileage,
+            Column::Year,
+        ]);
+
+        assert_eq!(
+            write(&emitter, &hits),
+            "model,price_amount,price_currency,mileage_value,mileage_unit,year\n\
+             Octavia,19995,EUR,84000,km,2018\n\
+             Fabia,,,,,\n"
+        );
+    }
+
+    #[test]
+    fn delimiter_and_header_can_be_chosen() {
+        let hit = Hit {
+            make: "Kia".to_string(),
+            price: Price::Eur(9950),
+            ..Default::default()
+        };
+        let emitter = CsvEmitter::new()
+            .with_columns(vec![Column::Make, Column::Price])
+            .with_delimiter(b';')
+            .with_header(false);
+
+        assert_eq!(write(&emitter, &[hit]), "Kia;9950;EUR\n");
+    }
+}
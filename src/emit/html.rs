@@ -0,0 +1,593 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::aggregate::{Aggregation, Group, GroupKey, Statistics};
+use crate::hit::Hit;
+use crate::query::{MakeModel, Query};
+
+use super::column::Column;
+use super::Emit;
+
+/// The styling of a report, inlined so that the report is a single file.
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+header dl, .summary dl { display: grid; grid-template-columns: max-content auto; gap: 0.25em 1em; }
+dt { font-weight: bold; }
+dd { margin: 0; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; text-align: left; }
+th { background: #f4f4f4; }
+table.sortable th { cursor: pointer; user-select: none; }
+th[aria-sort="ascending"]::after { content: " ▲"; }
+th[aria-sort="descending"]::after { content: " ▼"; }
+td.number { text-align: right; white-space: nowrap; }
+td.cheapest, td.good { color: #1a7f37; font-weight: bold; }
+td.bad { color: #cf222e; }
+td.anomalies { color: #9a6700; }
+input[type="search"] { padding: 0.3em; width: 20em; }
+"#;
+
+/// The sorting and filtering of the tables of a report, inlined so that the report is a single
+/// file. Cells are sorted by their `data-sort` value if they have one, numerically if both values
+/// are numbers, and with missing values last.
+const SCRIPT: &str = r##"
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  var body = table.tBodies[0];
+  var headers = table.querySelectorAll("th");
+  headers.forEach(function (th, i) {
+    th.addEventListener("click", function () {
+      var ascending = th.getAttribute("aria-sort") !== "ascending";
+      headers.forEach(function (other) { other.removeAttribute("aria-sort"); });
+      th.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+      var key = function (row) {
+        var cell = row.cells[i];
+        return cell.dataset.sort !== undefined ? cell.dataset.sort : cell.textContent;
+      };
+      var rows = Array.from(body.rows);
+      rows.sort(function (a, b) {
+        var x = key(a), y = key(b);
+        if (x === "" || y === "") {
+          return (x === "") - (y === "");
+        }
+        var order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : Number(x) - Number(y);
+        return ascending ? order : -order;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+
+var filter = document.getElementById("filter");
+filter && filter.addEventListener("input", function () {
+  var terms = filter.value.toLowerCase().split(/\s+/).filter(Boolean);
+  var rows = document.querySelectorAll("#results tbody tr");
+  var shown = 0;
+  rows.forEach(function (row) {
+    var text = Array.from(row.cells).map(function (cell) { return cell.textContent; })
+      .join(" ").toLowerCase();
+    row.hidden = !terms.every(function (term) { return text.indexOf(term) !== -1; });
+    shown += row.hidden ? 0 : 1;
+  });
+  document.getElementById("shown").textContent = shown + " of " + rows.length + " shown";
+});
+"##;
+
+/// Emits hits as a self-contained HTML report to share with those who would rather not use a
+/// terminal: the query in the header, summary statistics, the number of hits per search engine,
+/// and a table of hits that can be sorted by clicking a header and filtered by typing.
+#[derive(Default)]
+pub struct HtmlEmitter {
+    /// The columns to emit, or None for the defaults
+    columns: Option<Vec<Column>>,
+    /// The query that the hits were found with, to show in the header
+    query: Option<Query>,
+}
+
+impl HtmlEmitter {
+    pub fn new() -> Self {
+        HtmlEmitter {
+            columns: None,
+            query: None,
+        }
+    }
+
+    /// Emit these columns instead of the default ones.
+    pub fn with_columns(self, columns: Vec<Column>) -> Self {
+        Self {
+            columns: Some(columns),
+            ..self
+        }
+    }
+
+    /// Show the parameters of this query in the header of the report.
+    pub fn with_query(self, query: Query) -> Self {
+        Self {
+            query: Some(query),
+            ..self
+        }
+    }
+
+    /// The report of hits, with a summary and the number of hits per search engine.
+    fn report(&self, hits: &[Hit]) -> String {
+        let mut body = self.header();
+        if hits.is_empty() {
+            body.push_str("<p>No cars were found.</p>\n");
+            return page(&self.title(), &body);
+        }
+        body.push_str(&summary(hits));
+        body.push_str(&engines(hits));
+        body.push_str(&filter(hits.len()));
+        body.push_str(&self.table(hits));
+        page(&self.title(), &body)
+    }
+
+    /// The report of the groups of an aggregation, with a row per group and statistic.
+    fn group_report(&self, groups: &[Group]) -> String {
+        let mut body = self.header();
+        let Some(first) = groups.first() else {
+            body.push_str("<p>No cars were found.</p>\n");
+            return page(&self.title(), &body);
+        };
+
+        let rows: usize = groups.iter().map(|g| g.metrics.len().max(1)).sum();
+        body.push_str(&filter(rows));
+        body.push_str("<table id=\"results\" class=\"sortable\">\n<thead><tr>");
+        for (key, _) in &first.keys {
+            write!(body, "<th>{}</th>", key.name()).unwrap();
+        }
+        for header in [
+            "stat", "unit", "count", "min", "p25", "median", "mean", "p75", "p90", "max", "std dev",
+        ] {
+            write!(body, "<th>{}</th>", header).unwrap();
+        }
+        body.push_str("</tr></thead>\n<tbody>\n");
+        for group in groups {
+            let keys: String = group
+                .keys
+                .iter()
+                .map(|(_, value)| format!("<td>{}</td>", escape(value)))
+                .collect();
+            let count = number_cell(&group.count.to_string(), Some(group.count as f64), None);
+            if group.metrics.is_empty() {
+                writeln!(
+                    body,
+                    "<tr>{}<td></td><td></td>{}{}</tr>",
+                    keys,
+                    count,
+                    "<td></td>".repeat(8)
+                )
+                .unwrap();
+            }
+            for summary in &group.metrics {
+                let statistics: String = match &summary.statistics {
+                    None => number_cell("-", None, None).repeat(8),
+                    Some(s) => [
+                        s.min, s.p25, s.median, s.mean, s.p75, s.p90, s.max, s.std_dev,
+                    ]
+                    .map(|v| number_cell(&format!("{:.0}", v), Some(v), None))
+                    .concat(),
+                };
+                writeln!(
+                    body,
+                    "<tr>{}<td>{}</td><td>{}</td>{}{}</tr>",
+                    keys,
+                    summary.metric.name(),
+                    escape(&summary.unit),
+                    count,
+                    statistics
+                )
+                .unwrap();
+            }
+        }
+        body.push_str("</tbody>\n</table>\n");
+        page(&self.title(), &body)
+    }
+
+    /// The title of the report, naming the makes and models of the query.
+    fn title(&self) -> String {
+        let labels: Vec<String> = self
+            .query
+            .iter()
+            .flat_map(|query| query.make_models.iter().map(MakeModel::label))
+            .collect();
+        match labels.is_empty() {
+            true => "findcar".to_string(),
+            false => format!("findcar: {}", labels.join(", ")),
+        }
+    }
+
+    /// The heading of the report and, if there is a query, its parameters.
+    fn header(&self) -> String {
+        let mut header = format!("<header>\n<h1>{}</h1>\n", escape(&self.title()));
+        if let Some(query) = &self.query {
+            header.push_str("<dl>\n");
+            for (name, value) in parameters(query) {
+                writeln!(header, "<dt>{}</dt><dd>{}</dd>", name, escape(&value)).unwrap();
+            }
+            header.push_str("</dl>\n");
+        }
+        header.push_str("</header>\n");
+        header
+    }
+
+    /// The table of hits, with the cheapest price in every currency highlighted, deal scores
+    /// highlighted as good if below the expected price and bad if above it, and links to listings.
+    fn table(&self, hits: &[Hit]) -> String {
+        let columns = self
+            .columns
+            .clone()
+            .unwrap_or_else(|| Column::defaults(hits));
+        let mut cheapest: BTreeMap<&str, i32> = BTreeMap::new();
+        for hit in hits {
+            if let (Some(currency), Some(amount)) = (hit.price.currency(), hit.price.amount()) {
+                let min = cheapest.entry(currency).or_insert(amount);
+                *min = (*min).min(amount);
+            }
+        }
+
+        let mut table = String::from("<table id=\"results\" class=\"sortable\">\n<thead><tr>");
+        for column in &columns {
+            write!(table, "<th>{}</th>", column.name()).unwrap();
+        }
+        table.push_str("</tr></thead>\n<tbody>\n");
+        for hit in hits {
+            table.push_str("<tr>");
+            for column in &columns {
+                let value = column.value(hit);
+                let text = value.clone().unwrap_or_else(|| "-".to_string());
+                let class = match column {
+                    Column::Price => hit
+                        .price
+                        .currency()
+                        .zip(hit.price.amount())
+                        .filter(|(currency, amount)| cheapest.get(currency) == Some(amount))
+                        .map(|_| "cheapest"),
+                    Column::DealScore => match hit.deal_score {
+                        Some(score) if score > 0 => Some("good"),
+                        Some(score) if score < 0 => Some("bad"),
+                        _ => None,
+                    },
+                    Column::Anomalies => Some("anomalies"),
+                    _ => None,
+                };
+                let cell = match column {
+                    Column::Url if is_link(&hit.url) => format!(
+                        "<td><a href=\"{url}\" target=\"_blank\" rel=\"noopener\">{url}</a></td>",
+                        url = escape(&hit.url)
+                    ),
+                    _ if column.is_numeric() => number_cell(&text, sort_key(column, hit), class),
+                    _ => match (class, value) {
+                        (_, None) => "<td data-sort=\"\">-</td>".to_string(),
+                        (Some(class), Some(_)) => {
+                            format!("<td class=\"{}\">{}</td>", class, escape(&text))
+                        }
+                        (None, Some(_)) => format!("<td>{}</td>", escape(&text)),
+                    },
+                };
+                table.push_str(&cell);
+            }
+            table.push_str("</tr>\n");
+        }
+        table.push_str("</tbody>\n</table>\n");
+        table
+    }
+}
+
+/// A report with the title and body, and the styling and scripts it needs inlined.
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}<script>{}</script>\n\
+         </body>\n</html>",
+        escape(title),
+        STYLE,
+        body,
+        SCRIPT
+    )
+}
+
+/// The parameters of a query that are set, as names and values, e.g. ("Price", "up to 15000").
+fn parameters(query: &Query) -> Vec<(&'static str, String)> {
+    let labels: Vec<String> = query.make_models.iter().map(MakeModel::label).collect();
+    let mut parameters = vec![(
+        "Make and model",
+        match labels.is_empty() {
+            true => "any".to_string(),
+            false => labels.join(", "),
+        },
+    )];
+    let ranges = [
+        ("Price", &query.min_price, &query.max_price),
+        ("Year", &query.min_year, &query.max_year),
+        ("Kilometres", &query.min_kms, &query.max_kms),
+    ];
+    for (name, min, max) in ranges {
+        let range = match (min, max) {
+            (Some(min), Some(max)) => format!("{} – {}", min, max),
+            (Some(min), None) => format!("from {}", min),
+            (None, Some(max)) => format!("up to {}", max),
+            (None, None) => continue,
+        };
+        parameters.push((name, range));
+    }
+    let values = [
+        ("Postcode", &query.postcode),
+        ("Radius (km)", &query.radius),
+        ("Currency", &query.currency),
+    ];
+    for (name, value) in values {
+        if let Some(value) = value {
+            parameters.push((name, value.clone()));
+        }
+    }
+    if !query.keywords.is_empty() {
+        parameters.push(("Keywords", query.keywords.join(", ")));
+    }
+    if !query.exclude_keywords.is_empty() {
+        parameters.push(("Excluding", query.exclude_keywords.join(", ")));
+    }
+    parameters
+}
+
+/// The summary of the hits: their number, and the range and median of their prices (in every
+/// currency), mileages (normalised to kilometres) and years.
+fn summary(hits: &[Hit]) -> String {
+    let mut summary = String::from("<section class=\"summary\">\n<h2>Summary</h2>\n<dl>\n");
+    writeln!(summary, "<dt>Results</dt><dd>{}</dd>", hits.len()).unwrap();
+
+    let mut prices: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for hit in hits {
+        if let (Some(currency), Some(amount)) = (hit.price.currency(), hit.price.amount()) {
+            prices.entry(currency).or_default().push(amount.into());
+        }
+    }
+    for (currency, amounts) in prices {
+        if let Some(statistics) = Statistics::from_values(&amounts) {
+            writeln!(
+                summary,
+                "<dt>Price ({})</dt><dd>{}</dd>",
+                currency,
+                range(&statistics, "")
+            )
+            .unwrap();
+        }
+    }
+
+    let kms: Vec<f64> = hits
+        .iter()
+        .filter_map(|hit| hit.mileage.kms())
+        .map(f64::from)
+        .collect();
+    if let Some(statistics) = Statistics::from_values(&kms) {
+        writeln!(
+            summary,
+            "<dt>Mileage</dt><dd>{}</dd>",
+            range(&statistics, " km")
+        )
+        .unwrap();
+    }
+
+    let years = hits.iter().map(|hit| hit.year).filter(|year| *year > 0);
+    if let (Some(oldest), Some(newest)) = (years.clone().min(), years.max()) {
+        writeln!(summary, "<dt>Year</dt><dd>{} – {}</dd>", oldest, newest).unwrap();
+    }
+    summary.push_str("</dl>\n</section>\n");
+    summary
+}
+
+/// The range and median of values, e.g. `9950 – 24500 (median 15995)`.
+fn range(statistics: &Statistics, unit: &str) -> String {
+    format!(
+        "{:.0}{unit} – {:.0}{unit} (median {:.0}{unit})",
+        statistics.min,
+        statistics.max,
+        statistics.median,
+        unit = unit
+    )
+}
+
+/// The number of hits of every search engine that returned any.
+fn engines(hits: &[Hit]) -> String {
+    let mut engines = String::from(
+        "<section class=\"engines\">\n<h2>Search engines</h2>\n<table>\n\
+         <thead><tr><th>engine</th><th>results</th></tr></thead>\n<tbody>\n",
+    );
+    for group in Aggregation::new(vec![GroupKey::Engine], vec![]).execute(hits) {
+        let engine = group.keys.first().map_or("", |(_, name)| name.as_str());
+        writeln!(
+            engines,
+            "<tr><td>{}</td>{}</tr>",
+            escape(engine),
+            number_cell(&group.count.to_string(), Some(group.count as f64), None)
+        )
+        .unwrap();
+    }
+    engines.push_str("</tbody>\n</table>\n</section>\n");
+    engines
+}
+
+/// The box to filter the rows of the results with, and the number of rows shown.
+fn filter(rows: usize) -> String {
+    format!(
+        "<p><input type=\"search\" id=\"filter\" placeholder=\"Filter, e.g. diesel 2019\" \
+         aria-label=\"Filter\"> <span id=\"shown\">{} of {} shown</span></p>\n",
+        rows, rows
+    )
+}
+
+/// A cell of a number, aligned right and sorted by its value rather than its text. Cells without
+/// a value are sorted last.
+fn number_cell(text: &str, value: Option<f64>, class: Option<&str>) -> String {
+    let sort = value.map(|v| v.to_string()).unwrap_or_default();
+    let class = class.map_or("number".to_string(), |class| format!("number {}", class));
+    format!(
+        "<td class=\"{}\" data-sort=\"{}\">{}</td>",
+        class,
+        sort,
+        escape(text)
+    )
+}
+
+/// The value by which a numeric column of a hit is sorted: the amount of a price regardless of
+/// its currency, and a mileage in kilometres.
+fn sort_key(column: &Column, hit: &Hit) -> Option<f64> {
+    match column {
+        Column::Mileage => hit.mileage.kms().map(f64::from),
+        _ => column
+            .fields(hit)
+            .first()
+            .and_then(|(_, value)| value.as_f64()),
+    }
+}
+
+/// Whether a URL can be linked to; others, such as `javascript:` URLs, are shown as text only.
+fn is_link(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// The text with the characters that are special in HTML escaped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Emit for HtmlEmitter {
+    fn emit(&self, hits: Vec<Hit>) {
+        println!("{}", self.report(&hits));
+    }
+
+    fn emit_groups(&self, groups: Vec<Group>) {
+        println!("{}", self.group_report(&groups));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aggregate::Metric;
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            Hit {
+                search_engine: "carzone.ie".to_string(),
+                make: "Skoda".to_string(),
+                model: "Octavia".to_string(),
+                mileage: Mileage::Km(84000),
+                year: 2018,
+                price: Price::Eur(19995),
+                url: "https://www.carzone.ie/1".to_string(),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "donedeal.ie".to_string(),
+                make: "Skoda".to_string(),
+                model: "Octavia <RS>".to_string(),
+                mileage: Mileage::Mi(31000),
+                year: 2020,
+                price: Price::Eur(24500),
+                url: "javascript:alert(1)".to_string(),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "carzone.ie".to_string(),
+                make: "Skoda".to_string(),
+                model: "Octavia".to_string(),
+                year: 2016,
+                price: Price::Eur(12950),
+                url: "https://www.carzone.ie/3".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn query() -> Query {
+        Query {
+            make_models: vec!["Skoda:Octavia".parse().unwrap()],
+            max_price: Some("25000".to_string()),
+            min_year: Some("2016".to_string()),
+            keywords: vec!["towbar".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn header_shows_the_parameters_of_the_query() {
+        let report = HtmlEmitter::new().with_query(query()).report(&hits());
+
+        assert!(report.contains("<title>findcar: Skoda Octavia</title>"));
+        assert!(report.contains("<dt>Make and model</dt><dd>Skoda Octavia</dd>"));
+        assert!(report.contains("<dt>Price</dt><dd>up to 25000</dd>"));
+        assert!(report.contains("<dt>Year</dt><dd>from 2016</dd>"));
+        assert!(report.contains("<dt>Keywords</dt><dd>towbar</dd>"));
+        assert!(!report.contains("<dt>Kilometres</dt>"));
+    }
+
+    #[test]
+    fn summarises_hits_and_counts_them_per_engine() {
+        let report = HtmlEmitter::new().report(&hits());
+
+        assert!(report.contains("<dt>Results</dt><dd>3</dd>"));
+        assert!(report.contains("<dt>Price (EUR)</dt><dd>12950 – 24500 (median 19995)</dd>"));
+        assert!(report.contains("<dt>Mileage</dt><dd>49890 km – 84000 km (median 66945 km)</dd>"));
+        assert!(report.contains("<dt>Year</dt><dd>2016 – 2020</dd>"));
+        assert!(report.contains(
+            "<tr><td>carzone.ie</td><td class=\"number\" data-sort=\"2\">2</td></tr>\n\
+             <tr><td>donedeal.ie</td><td class=\"number\" data-sort=\"1\">1</td></tr>"
+        ));
+        assert!(report.contains("<span id=\"shown\">3 of 3 shown</span>"));
+    }
+
+    #[test]
+    fn rows_are_sortable_by_value_and_link_to_listings() {
+        let table = HtmlEmitter::new()
+            .with_columns(vec![
+                Column::Price,
+                Column::Mileage,
+                Column::Model,
+                Column::Url,
+            ])
+            .table(&hits());
+
+        assert!(table.contains(
+            "<tr><td class=\"number\" data-sort=\"19995\">€19995</td>\
+             <td class=\"number\" data-sort=\"84000\">84000 km</td><td>Octavia</td>\
+             <td><a href=\"https://www.carzone.ie/1\" target=\"_blank\" rel=\"noopener\">\
+             https://www.carzone.ie/1</a></td></tr>"
+        ));
+        // Miles are sorted by their distance in kilometres, and text is escaped
+        assert!(table.contains(
+            "<td class=\"number\" data-sort=\"49890\">31000 mi</td><td>Octavia &lt;RS&gt;</td>\
+             <td>javascript:alert(1)</td>"
+        ));
+        assert!(table.contains("<td class=\"number cheapest\" data-sort=\"12950\">€12950</td>"));
+        assert!(table.contains("<td class=\"number\" data-sort=\"\">-</td>"));
+    }
+
+    #[test]
+    fn groups_are_reported_with_a_row_per_statistic() {
+        let groups = Aggregation::new(vec![GroupKey::Engine], vec![Metric::Price]).execute(&hits());
+        let report = HtmlEmitter::new().group_report(&groups);
+
+        assert!(report
+            .contains("<th>engine</th><th>currency</th><th>stat</th><th>unit</th><th>count</th>"));
+        assert!(report.contains(
+            "<tr><td>donedeal.ie</td><td>EUR</td><td>price</td><td>EUR</td>\
+             <td class=\"number\" data-sort=\"1\">1</td>\
+             <td class=\"number\" data-sort=\"24500\">24500</td>"
+        ));
+        assert!(report.contains("<span id=\"shown\">2 of 2 shown</span>"));
+    }
+}
@@ -1,18 +1,114 @@
+use crate::aggregate::Group;
 use crate::hit::Hit;
 
+use super::column::{Column, Flat, ALL};
 use super::Emit;
 
-pub struct JsonEmitter {}
+/// Emits hits as a JSON array, either as they are or, to be easier to process in other tools, in
+/// their flat representation.
+#[derive(Default)]
+pub struct JsonEmitter {
+    /// The columns of the flat representation, or None to emit hits as they are
+    columns: Option<Vec<Column>>,
+}
 
 impl JsonEmitter {
     pub fn new() -> Self {
-        JsonEmitter {}
+        JsonEmitter { columns: None }
+    }
+
+    /// Emit hits in their flat representation, with only these columns.
+    pub fn with_columns(self, columns: Vec<Column>) -> Self {
+        Self {
+            columns: Some(columns),
+        }
+    }
+
+    /// Emit hits in their flat representation, with every column.
+    pub fn flattened(self) -> Self {
+        self.with_columns(ALL.to_vec())
+    }
+
+    fn serialize(&self, hits: &[Hit]) -> String {
+        match &self.columns {
+            None => serde_json::to_string_pretty(hits),
+            Some(columns) => serde_json::to_string_pretty(
+                &hits
+                    .iter()
+                    .map(|hit| Flat { hit, columns })
+                    .collect::<Vec<Flat>>(),
+            ),
+        }
+        .unwrap()
     }
 }
 
 impl Emit for JsonEmitter {
     fn emit(&self, hits: Vec<Hit>) {
-        let serialized = serde_json::to_string_pretty(&hits).unwrap();
+        println!("{}", self.serialize(&hits));
+    }
+
+    fn emit_groups(&self, groups: Vec<Group>) {
+        let serialized = serde_json::to_string_pretty(&groups).unwrap();
         println!("{}", serialized);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hit() -> Hit {
+        Hit {
+            make: "Skoda".to_string(),
+            price: Price::Eur(19995),
+            mileage: Mileage::Km(84000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hits_are_emitted_as_they_are_by_default() {
+        let json = JsonEmitter::new().serialize(&[hit()]);
+
+        assert_eq!(
+            serde_json::from_str::<Vec<Hit>>(&json).unwrap(),
+            vec![hit()]
+        );
+    }
+
+    #[test]
+    fn anomalies_are_emitted_as_a_list() {
+        let hit = Hit {
+            anomalies: vec!["price of 1".to_string(), "no year".to_string()],
+            ..hit()
+        };
+        let json = JsonEmitter::new().serialize(std::slice::from_ref(&hit));
+
+        assert!(json.contains(
+            r#""anomalies": [
+      "price of 1",
+      "no year"
+    ]"#
+        ));
+        assert_eq!(serde_json::from_str::<Vec<Hit>>(&json).unwrap(), vec![hit]);
+    }
+
+    #[test]
+    fn columns_are_emitted_flat_in_their_order() {
+        let emitter = JsonEmitter::new().with_columns(vec![Column::Price, Column::Make]);
+
+        assert_eq!(
+            emitter.serialize(&[hit()]),
+            r#"[
+  {
+    "price_amount": 19995,
+    "price_currency": "EUR",
+    "make": "Skoda"
+  }
+]"#
+        );
+    }
+}
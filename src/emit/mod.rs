@@ -1,14 +1,32 @@
+use crate::aggregate::Group;
 use crate::hit::Hit;
 
+mod column;
 mod csv;
+mod html;
 mod json;
+mod ndjson;
+mod table;
 mod text;
 pub use self::csv::CsvEmitter;
+pub use column::Column;
+pub use html::HtmlEmitter;
 pub use json::JsonEmitter;
-pub use text::TextEmitter;
+pub use ndjson::{NdjsonEmitter, SCHEMA_VERSION};
+pub(crate) use table::render_table;
+pub use text::{colour_supported, TextEmitter};
 
 /// Any emitter (a struct that takes all Hits and emits them in a given format) must implement this
 /// trait.
 pub trait Emit {
     fn emit(&self, hits: Vec<Hit>);
+
+    /// Emit the groups and statistics produced by an aggregation, in place of individual hits.
+    fn emit_groups(&self, groups: Vec<Group>);
+
+    /// Whether this emitter writes every hit on its own, so that hits can be emitted in batches as
+    /// search engines return them, rather than all at once.
+    fn streams(&self) -> bool {
+        false
+    }
 }
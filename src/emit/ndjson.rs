@@ -0,0 +1,148 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::aggregate::Group;
+use crate::hit::Hit;
+
+use super::column::{Column, Flat, ALL};
+use super::Emit;
+
+/// The version of the records that the NDJSON emitter writes. It is raised whenever a field is
+/// renamed or removed, or changes its meaning, so that consumers can tell which records they read.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A record of NDJSON output: a hit or group, tagged with the version of the schema.
+#[derive(Serialize)]
+struct Record<T: Serialize> {
+    schema_version: u32,
+    #[serde(flatten)]
+    record: T,
+}
+
+/// Emits hits as newline-delimited JSON (JSON Lines), a compact object per line, either as they
+/// are or in their flat representation. As every hit is written on its own, hits can be emitted
+/// as search engines return them.
+#[derive(Default)]
+pub struct NdjsonEmitter {
+    /// The columns of the flat representation, or None to emit hits as they are
+    columns: Option<Vec<Column>>,
+}
+
+impl NdjsonEmitter {
+    pub fn new() -> Self {
+        NdjsonEmitter { columns: None }
+    }
+
+    /// Emit hits in their flat representation, with only these columns.
+    pub fn with_columns(self, columns: Vec<Column>) -> Self {
+        Self {
+            columns: Some(columns),
+        }
+    }
+
+    /// Emit hits in their flat representation, with every column.
+    pub fn flattened(self) -> Self {
+        self.with_columns(ALL.to_vec())
+    }
+
+    fn write_hits<W: Write>(&self, mut writer: W, hits: &[Hit]) -> std::io::Result<()> {
+        for hit in hits {
+            let line = match &self.columns {
+                None => serde_json::to_string(&Record {
+                    schema_version: SCHEMA_VERSION,
+                    record: hit,
+                }),
+                Some(columns) => serde_json::to_string(&Record {
+                    schema_version: SCHEMA_VERSION,
+                    record: Flat { hit, columns },
+                }),
+            }?;
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()
+    }
+
+    fn write_groups<W: Write>(&self, mut writer: W, groups: &[Group]) -> std::io::Result<()> {
+        for group in groups {
+            let line = serde_json::to_string(&Record {
+                schema_version: SCHEMA_VERSION,
+                record: group,
+            })?;
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()
+    }
+}
+
+impl Emit for NdjsonEmitter {
+    fn emit(&self, hits: Vec<Hit>) {
+        self.write_hits(std::io::stdout().lock(), &hits)
+            .expect("Error writing NDJSON to stdout");
+    }
+
+    fn emit_groups(&self, groups: Vec<Group>) {
+        self.write_groups(std::io::stdout().lock(), &groups)
+            .expect("Error writing NDJSON to stdout");
+    }
+
+    fn streams(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            Hit {
+                make: "Skoda".to_string(),
+                model: "Octavia".to_string(),
+                price: Price::Eur(19995),
+                mileage: Mileage::Km(84000),
+                ..Default::default()
+            },
+            Hit {
+                make: "Kia".to_string(),
+                model: "Ceed".to_string(),
+                anomalies: vec!["price of 1".to_string()],
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn write(emitter: &NdjsonEmitter) -> String {
+        let mut out = vec![];
+        emitter.write_hits(&mut out, &hits()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn every_hit_is_a_line_tagged_with_the_schema_version() {
+        let out = write(&NdjsonEmitter::new());
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"schema_version":1,"search_engine":"","make":"Skoda""#));
+        let hits: Vec<Hit> = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(hits, super::tests::hits());
+    }
+
+    #[test]
+    fn columns_are_written_flat() {
+        let emitter = NdjsonEmitter::new().with_columns(vec![Column::Model, Column::Price]);
+
+        assert_eq!(
+            write(&emitter),
+            "{\"schema_version\":1,\"model\":\"Octavia\",\"price_amount\":19995,\"price_currency\":\"EUR\"}\n\
+             {\"schema_version\":1,\"model\":\"Ceed\",\"price_amount\":null,\"price_currency\":null}\n"
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// The text between the columns of a table.
+const GAP: &str = "  ";
+
+const ELLIPSIS: char = '…';
+
+/// A colour or emphasis of a cell, on terminals that support it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    Bold,
+    Green,
+    Red,
+    Yellow,
+}
+
+impl Style {
+    /// The ANSI escape code that starts the style.
+    fn code(&self) -> &'static str {
+        match self {
+            Style::Bold => "\x1b[1m",
+            Style::Green => "\x1b[32m",
+            Style::Red => "\x1b[31m",
+            Style::Yellow => "\x1b[33m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+/// A cell of a table, and its style if it is to stand out.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cell {
+    pub text: String,
+    pub style: Option<Style>,
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell { text, style: None }
+    }
+}
+
+struct TableColumn {
+    header: String,
+    right_aligned: bool,
+    truncatable: bool,
+}
+
+/// A table with a header, rendered with every column padded to the width of its widest cell, as
+/// displayed on a terminal.
+#[derive(Default)]
+pub(crate) struct Table {
+    columns: Vec<TableColumn>,
+    rows: Vec<Vec<Cell>>,
+}
+
+/// The text cut down to the width, as displayed on a terminal, ending in an ellipsis if it had to
+/// be cut. Characters such as CJK ideographs and emoji take up two columns.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width - 1 {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+impl Table {
+    /// Add a column. Numbers are aligned right; columns that are truncatable are cut down when the
+    /// table is wider than it may be, but never to less than the width of their header.
+    pub fn column(mut self, header: &str, right_aligned: bool, truncatable: bool) -> Self {
+        self.columns.push(TableColumn {
+            header: header.to_string(),
+            right_aligned,
+            truncatable,
+        });
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    /// The width of every column, fitted to the width of the table if there is one by narrowing
+    /// the widest truncatable column one character at a time.
+    fn widths(&self, width: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.columns.iter().map(|c| c.header.width()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.text.width());
+            }
+        }
+
+        let Some(width) = width else {
+            return widths;
+        };
+        let gaps = GAP.len() * widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + gaps > width {
+            let widest = (0..widths.len())
+                .filter(|i| {
+                    let column = &self.columns[*i];
+                    column.truncatable && widths[*i] > column.header.width()
+                })
+                .max_by_key(|i| widths[*i]);
+            match widest {
+                Some(i) => widths[i] -= 1,
+                None => break,
+            }
+        }
+        widths
+    }
+
+    /// Render the table, within the width if one is provided and with styled cells in colour if
+    /// `colour` is set.
+    pub fn render(&self, width: Option<usize>, colour: bool) -> String {
+        let widths = self.widths(width);
+        let render_row = |row: &[Cell]| -> String {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let text = truncate(&cell.text, widths[i]);
+                    let padding = " ".repeat(widths[i].saturating_sub(text.width()));
+                    let text = match cell.style {
+                        Some(style) if colour => format!("{}{}{}", style.code(), text, RESET),
+                        _ => text,
+                    };
+                    if self.columns[i].right_aligned {
+                        padding + &text
+                    } else {
+                        text + &padding
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(GAP)
+                .trim_end()
+                .to_string()
+        };
+
+        let header: Vec<Cell> = self
+            .columns
+            .iter()
+            .map(|c| Cell {
+                text: c.header.clone(),
+                style: Some(Style::Bold),
+            })
+            .collect();
+        let separator: Vec<Cell> = widths.iter().map(|w| Cell::from("-".repeat(*w))).collect();
+        std::iter::once(&header)
+            .chain(std::iter::once(&separator))
+            .chain(self.rows.iter())
+            .map(|row| render_row(row))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Render rows as a table with a header, padding every column to the width of its widest cell.
+/// The first `left_aligned` columns are aligned left; all others are numeric and aligned right.
+pub(crate) fn render_table(
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    left_aligned: usize,
+) -> String {
+    let mut table = header
+        .iter()
+        .enumerate()
+        .fold(Table::default(), |table, (i, header)| {
+            table.column(header, i >= left_aligned, false)
+        });
+    for row in rows {
+        table.row(row.into_iter().map(Cell::from).collect());
+    }
+    table.render(None, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        let mut table = Table::default()
+            .column("make", false, true)
+            .column("price", true, false)
+            .column("url", false, false);
+        table.row(vec![
+            Cell::from("Volkswagen".to_string()),
+            Cell {
+                text: "€9950".to_string(),
+                style: Some(Style::Green),
+            },
+            Cell::from("https://example.com/1".to_string()),
+        ]);
+        table.row(vec![
+            Cell::from("Kia".to_string()),
+            Cell::from("€14950".to_string()),
+            Cell::from("https://example.com/2".to_string()),
+        ]);
+        table
+    }
+
+    #[test]
+    fn table_pads_and_aligns_columns() {
+        let table = render_table(
+            vec!["model".to_string(), "count".to_string()],
+            vec![
+                vec!["Corolla".to_string(), "2".to_string()],
+                vec!["Yaris".to_string(), "10".to_string()],
+            ],
+            1,
+        );
+
+        assert_eq!(
+            table,
+            "model    count\n-------  -----\nCorolla      2\nYaris       10"
+        );
+    }
+
+    #[test]
+    fn truncates_columns_to_fit_the_width() {
+        assert_eq!(
+            table().render(Some(36), false),
+            "make    price  url\n\
+             -----  ------  ---------------------\n\
+             Volk…   €9950  https://example.com/1\n\
+             Kia    €14950  https://example.com/2"
+        );
+        // Columns are not narrowed beyond their header
+        assert!(table()
+            .render(Some(10), false)
+            .ends_with("\nKia   €14950  https://example.com/2"));
+    }
+
+    #[test]
+    fn colours_styled_cells_without_affecting_alignment() {
+        assert_eq!(
+            table().render(None, true),
+            "\x1b[1mmake\x1b[0m         \x1b[1mprice\x1b[0m  \x1b[1murl\x1b[0m\n\
+             ----------  ------  ---------------------\n\
+             Volkswagen   \x1b[32m€9950\x1b[0m  https://example.com/1\n\
+             Kia         €14950  https://example.com/2"
+        );
+    }
+
+    #[test]
+    fn truncation_ends_in_an_ellipsis() {
+        assert_eq!(truncate("Volkswagen", 10), "Volkswagen");
+        assert_eq!(truncate("Volkswagen", 5), "Volk…");
+        assert_eq!(truncate("Volkswagen", 0), "");
+    }
+
+    #[test]
+    fn wide_characters_are_measured_by_their_display_width() {
+        assert_eq!(truncate("トヨタ", 6), "トヨタ");
+        assert_eq!(truncate("トヨタ", 5), "トヨ…");
+        assert_eq!(truncate("トヨタ", 4), "ト…");
+
+        let mut table = Table::default()
+            .column("make", false, false)
+            .column("price", true, false);
+        table.row(vec![
+            Cell::from("トヨタ".to_string()),
+            Cell::from("¥1".to_string()),
+        ]);
+        table.row(vec![
+            Cell::from("Kia".to_string()),
+            Cell::from("€14950".to_string()),
+        ]);
+        assert_eq!(
+            table.render(None, false),
+            "make     price\n------  ------\nトヨタ      ¥1\nKia     €14950"
+        );
+    }
+}
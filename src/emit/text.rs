@@ -1,8 +1,27 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+use crate::aggregate::Group;
 use crate::hit::{Hit, Mileage, Price};
+use crate::taxonomy::discovery::{Support, SupportMatrix};
 
+use super::column::Column;
+use super::table::{render_table, Cell, Style, Table};
 use super::Emit;
 
-pub struct TextEmitter {}
+/// Emits hits as a table with a column per property, and aggregations as a table per statistic.
+pub struct TextEmitter {
+    /// The columns to emit, or None for the defaults
+    columns: Option<Vec<Column>>,
+    width: Option<usize>,
+    colour: bool,
+}
+
+impl Default for TextEmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl std::fmt::Display for Mileage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -31,26 +50,301 @@ impl std::fmt::Display for Hit {
             f,
             "{} - ({}, {}) {} {} [{}: {}]",
             self.price,
-            self.year.to_string(),
+            self.year,
             self.mileage,
             self.make,
             self.model,
             self.search_engine,
             self.url,
-        )
+        )?;
+        if let (Some(age), Some(km_per_year)) = (self.age, self.km_per_year) {
+            write!(f, " (age: {}, {} km/year", age, km_per_year)?;
+            if let Some(per_1000_km) = &self.price_per_1000_km {
+                write!(f, ", {} per 1000 km", per_1000_km)?;
+            }
+            write!(f, ")")?;
+        }
+        if let (Some(expected), Some(score)) = (&self.expected_price, self.deal_score) {
+            write!(f, " (expected: {}, deal score: {})", expected, score)?;
+        }
+        if !self.anomalies.is_empty() {
+            write!(f, " [!] {}", self.anomalies.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SupportMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header: Vec<String> = std::iter::once(self.kind.to_string())
+            .chain(self.engines.iter().cloned())
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|(name, support)| {
+                std::iter::once(name.clone())
+                    .chain(support.iter().map(|s| match s {
+                        Support::Listed => "yes".to_string(),
+                        Support::Bundled => "bundled".to_string(),
+                        Support::Unsupported => "-".to_string(),
+                    }))
+                    .collect()
+            })
+            .collect();
+        let columns = header.len();
+        write!(f, "{}", render_table(header, rows, columns))?;
+        if self
+            .rows
+            .iter()
+            .flat_map(|(_, s)| s)
+            .any(|s| *s == Support::Bundled)
+        {
+            write!(
+                f,
+                "\n\nbundled: the search engine cannot list its {}s, so the bundled taxonomy is shown",
+                self.kind
+            )?;
+        }
+        Ok(())
     }
 }
 
 impl TextEmitter {
+    /// An emitter of the default columns, fitted to the width of the terminal and in colour if
+    /// standard output is a terminal that does not opt out of it.
     pub fn new() -> Self {
-        TextEmitter {}
+        TextEmitter {
+            columns: None,
+            width: terminal_width(),
+            colour: colour_supported(),
+        }
+    }
+
+    /// Emit these columns instead of the default ones.
+    pub fn with_columns(self, columns: Vec<Column>) -> Self {
+        Self {
+            columns: Some(columns),
+            ..self
+        }
+    }
+
+    /// Fit tables to this width, truncating text where necessary, or not at all if None.
+    pub fn with_width(self, width: Option<usize>) -> Self {
+        Self { width, ..self }
+    }
+
+    /// Whether to highlight the cheapest prices, deal scores and anomalies in colour.
+    pub fn with_colour(self, colour: bool) -> Self {
+        Self { colour, ..self }
+    }
+
+    /// The table of hits, with the cheapest price in every currency in green, and deal scores
+    /// green if below the expected price and red if above it.
+    fn table(&self, hits: &[Hit]) -> Table {
+        let columns = self
+            .columns
+            .clone()
+            .unwrap_or_else(|| Column::defaults(hits));
+        let mut cheapest: HashMap<&str, i32> = HashMap::new();
+        for hit in hits {
+            if let (Some(currency), Some(amount)) = (hit.price.currency(), hit.price.amount()) {
+                let min = cheapest.entry(currency).or_insert(amount);
+                *min = (*min).min(amount);
+            }
+        }
+
+        let mut table = columns.iter().fold(Table::default(), |table, column| {
+            // URLs are of no use once truncated
+            table.column(
+                column.name(),
+                column.is_numeric(),
+                !column.is_numeric() && *column != Column::Url,
+            )
+        });
+        for hit in hits {
+            table.row(
+                columns
+                    .iter()
+                    .map(|column| Cell {
+                        text: column.value(hit).unwrap_or_else(|| "-".to_string()),
+                        style: match column {
+                            Column::Price => hit
+                                .price
+                                .currency()
+                                .zip(hit.price.amount())
+                                .filter(|(currency, amount)| cheapest.get(currency) == Some(amount))
+                                .map(|_| Style::Green),
+                            Column::DealScore => match hit.deal_score {
+                                Some(score) if score > 0 => Some(Style::Green),
+                                Some(score) if score < 0 => Some(Style::Red),
+                                _ => None,
+                            },
+                            Column::Anomalies => Some(Style::Yellow),
+                            _ => None,
+                        },
+                    })
+                    .collect(),
+            );
+        }
+        table
     }
 }
 
+/// Whether standard output is a terminal that colour can be used on, which it cannot if the user
+/// has opted out by setting NO_COLOR.
+pub fn colour_supported() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// The width of the terminal that standard output is, if it is one: the COLUMNS environment
+/// variable if set, or as the terminal reports it otherwise.
+fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+}
+
 impl Emit for TextEmitter {
     fn emit(&self, hits: Vec<Hit>) {
-        for hit in hits {
-            println!("{}", hit);
+        if hits.is_empty() {
+            return;
         }
+        println!("{}", self.table(&hits).render(self.width, self.colour));
+    }
+
+    fn emit_groups(&self, groups: Vec<Group>) {
+        let Some(first) = groups.first() else {
+            return;
+        };
+
+        let key_columns = first.keys.len();
+        let mut header: Vec<String> = first
+            .keys
+            .iter()
+            .map(|(key, _)| key.name().to_string())
+            .collect();
+        header.extend(
+            [
+                "stat", "unit", "count", "min", "p25", "median", "mean", "p75", "p90", "max",
+                "std dev",
+            ]
+            .map(String::from),
+        );
+
+        let mut rows = vec![];
+        for group in &groups {
+            let keys: Vec<String> = group.keys.iter().map(|(_, v)| v.clone()).collect();
+            if group.metrics.is_empty() {
+                let mut row = keys.clone();
+                row.extend(["".to_string(), "".to_string(), group.count.to_string()]);
+                row.extend(vec!["".to_string(); 8]);
+                rows.push(row);
+            }
+            for summary in &group.metrics {
+                let mut row = keys.clone();
+                row.push(summary.metric.name().to_string());
+                row.push(summary.unit.clone());
+                row.push(group.count.to_string());
+                match &summary.statistics {
+                    None => row.extend(vec!["-".to_string(); 8]),
+                    Some(s) => row.extend(
+                        [
+                            s.min, s.p25, s.median, s.mean, s.p75, s.p90, s.max, s.std_dev,
+                        ]
+                        .map(|v| format!("{:.0}", v)),
+                    ),
+                }
+                rows.push(row);
+            }
+        }
+
+        println!("{}", render_table(header, rows, key_columns + 2));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emitter() -> TextEmitter {
+        TextEmitter::new().with_width(None).with_colour(false)
+    }
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            Hit {
+                search_engine: "carzone.ie".to_string(),
+                make: "Volkswagen".to_string(),
+                model: "Golf".to_string(),
+                mileage: Mileage::Km(62000),
+                year: 2019,
+                price: Price::Eur(18490),
+                url: "https://www.carzone.ie/1".to_string(),
+                deal_score: Some(-4),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "autotrader.co.uk".to_string(),
+                make: "Kia".to_string(),
+                model: "Ceed".to_string(),
+                mileage: Mileage::Mi(31000),
+                year: 2020,
+                price: Price::Gbp(12995),
+                url: "https://www.autotrader.co.uk/2".to_string(),
+                deal_score: Some(11),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn hits_are_emitted_as_a_table_with_numbers_aligned_right() {
+        assert_eq!(
+            emitter().table(&hits()).render(None, false),
+            " price  year   mileage  make        model  deal_score  engine            url\n\
+             ------  ----  --------  ----------  -----  ----------  ----------------  \
+             ------------------------------\n\
+             €18490  2019  62000 km  Volkswagen  Golf           -4  carzone.ie        \
+             https://www.carzone.ie/1\n\
+             £12995  2020  31000 mi  Kia         Ceed           11  autotrader.co.uk  \
+             https://www.autotrader.co.uk/2"
+        );
+    }
+
+    #[test]
+    fn columns_can_be_chosen() {
+        let emitter = emitter().with_columns(vec![Column::Model, Column::Price]);
+
+        assert_eq!(
+            emitter.table(&hits()).render(None, false),
+            "model   price\n-----  ------\nGolf   €18490\nCeed   £12995"
+        );
+    }
+
+    #[test]
+    fn cheapest_prices_and_deal_scores_are_coloured() {
+        let emitter = emitter().with_columns(vec![Column::Price, Column::DealScore]);
+        let mut hits = hits();
+        hits.push(Hit {
+            price: Price::Eur(21000),
+            ..Default::default()
+        });
+
+        let table = emitter.table(&hits).render(None, true);
+        let rows: Vec<&str> = table.lines().skip(2).collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                "\x1b[32m€18490\x1b[0m          \x1b[31m-4\x1b[0m",
+                "\x1b[32m£12995\x1b[0m          \x1b[32m11\x1b[0m",
+                "€21000           -",
+            ]
+        );
     }
 }
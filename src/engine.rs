@@ -1,41 +1,125 @@
-use futures::stream::{FuturesUnordered, StreamExt};
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
 
 use crate::hit::Hit;
+use crate::post_processing::keyword::{self, KeywordFilter};
+use crate::post_processing::Action;
 use crate::query::Query;
 use crate::search::{SearchResult, Searcher};
+use crate::taxonomy::Taxonomy;
+
+/// Apply the query's keywords to the hits of a searcher, as far as the searcher does not support
+/// them natively.
+fn filter_keywords(searcher: &dyn Searcher, query: &Query, hits: Vec<Hit>) -> Vec<Hit> {
+    let keywords: &[String] = if searcher.supports_keywords() {
+        &[]
+    } else {
+        &query.keywords
+    };
+    if keywords.is_empty() && query.exclude_keywords.is_empty() {
+        return hits;
+    }
+    let unchecked = hits.iter().filter(|hit| !keyword::has_text(hit)).count();
+    if !keywords.is_empty() && unchecked > 0 {
+        eprintln!(
+            "{} results of {} have no title or description to check the keywords against, and are kept",
+            unchecked,
+            searcher.name()
+        );
+    }
+    KeywordFilter::new(keywords, &query.exclude_keywords).execute(hits)
+}
+
+/// Keep only the hits that are priced in the query's currency, if it has one.
+fn filter_currency(query: &Query, hits: Vec<Hit>) -> Vec<Hit> {
+    match &query.currency {
+        None => hits,
+        Some(currency) => hits
+            .into_iter()
+            .filter(|hit| hit.price.currency() == Some(currency.as_str()))
+            .collect(),
+    }
+}
+
+/// Replace the make and model that a hit is tagged with, as the searcher spelled them in its query,
+/// with their canonical names. A searcher that searches by identifier would otherwise tag its hits
+/// with identifiers.
+fn relabel(hit: &mut Hit, engine_query: &Query, canonical: &Query) {
+    let Some(matched) = &hit.matched else {
+        return;
+    };
+    if let Some(i) = engine_query
+        .make_models
+        .iter()
+        .position(|make_model| &make_model.label() == matched)
+    {
+        hit.matched = Some(canonical.make_models[i].label());
+    }
+}
 
 /// The root engine which drives several search engines, combining their results.
 pub struct Engine {
     searchers: Vec<Box<dyn Searcher>>,
+    taxonomy: Option<Taxonomy>,
 }
 
 impl Engine {
     /// Create a new root engine with the provided searchers
     pub fn with_searchers(searchers: Vec<Box<dyn Searcher>>) -> Self {
-        Engine { searchers }
+        Engine {
+            searchers,
+            taxonomy: None,
+        }
     }
 
-    /// Run a search
-    pub async fn search(&self, query: &Query) -> Vec<Hit> {
-        let futures = FuturesUnordered::new();
-        for searcher in &self.searchers {
-            futures.push(searcher.search(query));
-        }
+    /// Use the provided taxonomy to translate the makes and models of the query into the names
+    /// each searcher expects, and those of the results into their canonical names.
+    pub fn with_taxonomy(mut self, taxonomy: Taxonomy) -> Self {
+        self.taxonomy = Some(taxonomy);
+        self
+    }
 
-        let results: Vec<SearchResult> = futures.collect().await;
-        let mut successes: Vec<Hit> = vec![];
-        for mut result in results {
-            match result {
-                Ok(ref mut inner) => {
-                    successes.append(inner);
-                }
-                Err(error) => {
-                    println!("An error was encountered in a search engine, results unavailable.\nReason: {}", error);
-                }
+    /// Search a single search engine, applying the query's keywords and currency to its hits.
+    async fn search_with(&self, searcher: &dyn Searcher, query: &Query) -> SearchResult {
+        let result = match &self.taxonomy {
+            Some(taxonomy) => {
+                let engine_query = taxonomy.query_for(searcher.name(), query);
+                let canonical = taxonomy.canonical_query(query);
+                searcher.search(&engine_query).await.map(|mut hits| {
+                    for hit in hits.iter_mut() {
+                        taxonomy.normalise(searcher.name(), hit);
+                        relabel(hit, &engine_query, &canonical);
+                    }
+                    hits
+                })
             }
-        }
+            None => searcher.search(query).await,
+        };
+        result
+            .map(|hits| filter_keywords(searcher, query, hits))
+            .map(|hits| filter_currency(query, hits))
+    }
 
-        successes
+    /// Run a search, yielding the hits of every search engine as soon as it has returned them.
+    /// Errors of a search engine are reported on stderr, and it yields no hits.
+    pub fn stream<'a>(&'a self, query: &'a Query) -> impl Stream<Item = Vec<Hit>> + 'a {
+        self.searchers
+            .iter()
+            .map(|searcher| self.search_with(searcher.as_ref(), query))
+            .collect::<FuturesUnordered<_>>()
+            .filter_map(|result| async move {
+                match result {
+                    Ok(hits) => Some(hits),
+                    Err(error) => {
+                        eprintln!("An error was encountered in a search engine, results unavailable.\nReason: {}", error);
+                        None
+                    }
+                }
+            })
+    }
+
+    /// Run a search
+    pub async fn search(&self, query: &Query) -> Vec<Hit> {
+        self.stream(query).concat().await
     }
 }
 
@@ -45,13 +129,17 @@ mod tests {
     use async_trait::async_trait;
 
     use crate::hit::{Mileage, Price};
-    use std::io::{Error, ErrorKind};
+    use std::io::Error;
 
     #[tokio::test]
     async fn single_searcher() {
         struct S {}
         #[async_trait]
         impl Searcher for S {
+            fn name(&self) -> &str {
+                "s"
+            }
+
             async fn search(&self, _q: &Query) -> SearchResult {
                 Ok(vec![
                     Hit {
@@ -62,6 +150,7 @@ mod tests {
                         model: "Fabia".to_string(),
                         price: Price::Eur(19995),
                         url: "https://mycar.com/car".to_string(),
+                        ..Default::default()
                     },
                     Hit {
                         mileage: Mileage::Km(10000),
@@ -71,6 +160,7 @@ mod tests {
                         model: "Fabia".to_string(),
                         price: Price::Eur(19995),
                         url: "https://mycar.com/car".to_string(),
+                        ..Default::default()
                     },
                 ])
             }
@@ -92,6 +182,7 @@ mod tests {
                     model: "Fabia".to_string(),
                     price: Price::Eur(19995),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     mileage: Mileage::Km(10000),
@@ -101,6 +192,7 @@ mod tests {
                     model: "Fabia".to_string(),
                     price: Price::Eur(19995),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
             )
         );
@@ -113,6 +205,10 @@ mod tests {
 
         #[async_trait]
         impl Searcher for S0 {
+            fn name(&self) -> &str {
+                "s0"
+            }
+
             async fn search(&self, _target: &Query) -> SearchResult {
                 Ok(vec![
                     Hit {
@@ -123,6 +219,7 @@ mod tests {
                         model: "Fabia".to_string(),
                         price: Price::Eur(19995),
                         url: "https://mycar.com/car".to_string(),
+                        ..Default::default()
                     },
                     Hit {
                         mileage: Mileage::Km(10000),
@@ -132,6 +229,7 @@ mod tests {
                         model: "Fabia".to_string(),
                         price: Price::Eur(19995),
                         url: "https://mycar.com/car".to_string(),
+                        ..Default::default()
                     },
                 ])
             }
@@ -139,6 +237,10 @@ mod tests {
 
         #[async_trait]
         impl Searcher for S1 {
+            fn name(&self) -> &str {
+                "s1"
+            }
+
             async fn search(&self, _target: &Query) -> SearchResult {
                 Ok(vec![Hit {
                     mileage: Mileage::Km(10000),
@@ -148,6 +250,7 @@ mod tests {
                     model: "Golf".to_string(),
                     price: Price::Eur(25000),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 }])
             }
         }
@@ -168,6 +271,7 @@ mod tests {
                     model: "Fabia".to_string(),
                     price: Price::Eur(19995),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     mileage: Mileage::Km(10000),
@@ -177,6 +281,7 @@ mod tests {
                     model: "Fabia".to_string(),
                     price: Price::Eur(19995),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     mileage: Mileage::Km(10000),
@@ -186,6 +291,7 @@ mod tests {
                     model: "Golf".to_string(),
                     price: Price::Eur(25000),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
             )
         );
@@ -198,6 +304,10 @@ mod tests {
 
         #[async_trait]
         impl Searcher for S0 {
+            fn name(&self) -> &str {
+                "s0"
+            }
+
             async fn search(&self, _target: &Query) -> SearchResult {
                 Ok(vec![
                     Hit {
@@ -208,6 +318,7 @@ mod tests {
                         model: "Fabia".to_string(),
                         price: Price::Eur(19995),
                         url: "https://mycar.com/car".to_string(),
+                        ..Default::default()
                     },
                     Hit {
                         mileage: Mileage::Km(10000),
@@ -217,6 +328,7 @@ mod tests {
                         model: "Fabia".to_string(),
                         price: Price::Eur(19995),
                         url: "https://mycar.com/car".to_string(),
+                        ..Default::default()
                     },
                 ])
             }
@@ -224,8 +336,12 @@ mod tests {
 
         #[async_trait]
         impl Searcher for S1 {
+            fn name(&self) -> &str {
+                "s1"
+            }
+
             async fn search(&self, _target: &Query) -> SearchResult {
-                Err(Error::new(ErrorKind::Other, "oh no"))
+                Err(Error::other("oh no"))
             }
         }
 
@@ -245,6 +361,7 @@ mod tests {
                     model: "Fabia".to_string(),
                     price: Price::Eur(19995),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     mileage: Mileage::Km(10000),
@@ -254,8 +371,228 @@ mod tests {
                     model: "Fabia".to_string(),
                     price: Price::Eur(19995),
                     url: "https://mycar.com/car".to_string(),
+                    ..Default::default()
                 },
             )
         );
     }
+
+    #[tokio::test]
+    async fn keywords_are_applied_unless_supported_natively() {
+        struct Native {}
+        struct ClientSide {}
+
+        fn hits(search_engine: &str) -> Vec<Hit> {
+            ["Golf with towbar", "Golf, crashed", "Golf"]
+                .into_iter()
+                .map(|title| Hit {
+                    mileage: Mileage::Km(10000),
+                    year: 2022,
+                    search_engine: search_engine.to_string(),
+                    make: "Volkswagen".to_string(),
+                    model: "Golf".to_string(),
+                    price: Price::Eur(25000),
+                    url: "https://mycar.com/car".to_string(),
+                    title: Some(title.to_string()),
+                    ..Default::default()
+                })
+                .collect()
+        }
+
+        #[async_trait]
+        impl Searcher for Native {
+            fn name(&self) -> &str {
+                "native"
+            }
+
+            async fn search(&self, _target: &Query) -> SearchResult {
+                Ok(hits("native"))
+            }
+
+            fn supports_keywords(&self) -> bool {
+                true
+            }
+        }
+
+        #[async_trait]
+        impl Searcher for ClientSide {
+            fn name(&self) -> &str {
+                "clientside"
+            }
+
+            async fn search(&self, _target: &Query) -> SearchResult {
+                Ok(hits("client"))
+            }
+        }
+
+        let searchers: Vec<Box<dyn Searcher>> = vec![Box::new(Native {}), Box::new(ClientSide {})];
+        let engine = Engine::with_searchers(searchers);
+
+        let target = Query {
+            keywords: vec!["towbar".to_string()],
+            exclude_keywords: vec!["crashed".to_string()],
+            ..Default::default()
+        };
+        let mut results: Vec<(String, String)> = engine
+            .search(&target)
+            .await
+            .into_iter()
+            .map(|h| (h.search_engine, h.title.unwrap()))
+            .collect();
+        results.sort();
+
+        assert_eq!(
+            results,
+            vec![
+                ("client".to_string(), "Golf with towbar".to_string()),
+                ("native".to_string(), "Golf".to_string()),
+                ("native".to_string(), "Golf with towbar".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn taxonomy_translates_queries_and_hits() {
+        struct S {}
+
+        #[async_trait]
+        impl Searcher for S {
+            fn name(&self) -> &str {
+                "s"
+            }
+
+            /// Echo the make and model that were searched for, in this engine's own spelling
+            async fn search(&self, target: &Query) -> SearchResult {
+                Ok(target
+                    .make_models
+                    .iter()
+                    .map(|make_model| Hit {
+                        search_engine: "s".to_string(),
+                        make: make_model.make.clone().unwrap_or_default(),
+                        model: make_model.model.clone().unwrap_or_default(),
+                        url: "https://mycar.com/car".to_string(),
+                        matched: Some(make_model.label()),
+                        ..Default::default()
+                    })
+                    .collect())
+            }
+        }
+
+        let taxonomy: Taxonomy = toml::from_str(
+            r#"
+            [[make]]
+            name = "Volkswagen"
+            aliases = ["VW"]
+            engines = { s = "VOLKSWAGEN" }
+            model = [{ name = "Golf", engines = { s = "golf-all" } }]
+            "#,
+        )
+        .unwrap();
+        let engine = Engine::with_searchers(vec![Box::new(S {})]).with_taxonomy(taxonomy);
+
+        let target = Query {
+            make_models: vec!["vw:Golf".parse().unwrap()],
+            ..Default::default()
+        };
+        let results = engine.search(&target).await;
+
+        assert_eq!(results[0].make, "Volkswagen");
+        assert_eq!(results[0].model, "Golf");
+        assert_eq!(results[0].matched, Some("Volkswagen Golf".to_string()));
+    }
+
+    #[tokio::test]
+    async fn only_hits_in_the_query_currency_are_kept() {
+        struct S {}
+
+        #[async_trait]
+        impl Searcher for S {
+            fn name(&self) -> &str {
+                "s"
+            }
+
+            async fn search(&self, _target: &Query) -> SearchResult {
+                Ok([Price::Eur(9000), Price::Gbp(8000), Price::Unknown]
+                    .into_iter()
+                    .map(|price| Hit {
+                        search_engine: "s".to_string(),
+                        price,
+                        ..Default::default()
+                    })
+                    .collect())
+            }
+        }
+
+        let engine = Engine::with_searchers(vec![Box::new(S {})]);
+        let target = Query {
+            currency: Some("GBP".to_string()),
+            ..Default::default()
+        };
+        let prices: Vec<Price> = engine
+            .search(&target)
+            .await
+            .into_iter()
+            .map(|h| h.price)
+            .collect();
+
+        assert_eq!(prices, vec![Price::Gbp(8000)]);
+    }
+
+    #[tokio::test]
+    async fn hits_are_streamed_as_each_searcher_returns_them() {
+        /// A searcher that takes its time, returning a single hit.
+        struct Slow(&'static str, u64);
+
+        #[async_trait]
+        impl Searcher for Slow {
+            fn name(&self) -> &str {
+                self.0
+            }
+
+            async fn search(&self, _target: &Query) -> SearchResult {
+                tokio::time::sleep(std::time::Duration::from_millis(self.1)).await;
+                Ok(vec![Hit {
+                    search_engine: self.0.to_string(),
+                    ..Default::default()
+                }])
+            }
+        }
+
+        struct Failing {}
+
+        #[async_trait]
+        impl Searcher for Failing {
+            fn name(&self) -> &str {
+                "failing"
+            }
+
+            async fn search(&self, _target: &Query) -> SearchResult {
+                Err(Error::other("unavailable"))
+            }
+        }
+
+        let searchers: Vec<Box<dyn Searcher>> = vec![
+            Box::new(Slow("slow", 200)),
+            Box::new(Failing {}),
+            Box::new(Slow("medium", 100)),
+            Box::new(Slow("fast", 0)),
+        ];
+        let engine = Engine::with_searchers(searchers);
+
+        let query = Query::default();
+        let batches: Vec<Vec<String>> = engine
+            .stream(&query)
+            .map(|hits| hits.into_iter().map(|hit| hit.search_engine).collect())
+            .collect()
+            .await;
+
+        assert_eq!(
+            batches,
+            vec![
+                vec!["fast".to_string()],
+                vec!["medium".to_string()],
+                vec!["slow".to_string()]
+            ]
+        );
+    }
 }
@@ -1,23 +1,78 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize)]
+#[derive(Debug, Default, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum Price {
     Eur(i32),
     Usd(i32),
     Gbp(i32),
+    #[default]
     Unknown,
 }
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize)]
+impl Price {
+    /// A price in the currency with the provided ISO 4217 code (case-insensitive), which is
+    /// unknown for currencies other than EUR, USD and GBP.
+    pub fn in_currency(currency: &str, amount: i32) -> Self {
+        match currency.to_uppercase().as_str() {
+            "EUR" => Price::Eur(amount),
+            "USD" => Price::Usd(amount),
+            "GBP" => Price::Gbp(amount),
+            _ => Price::Unknown,
+        }
+    }
+
+    /// The numeric amount of this price, regardless of currency.
+    pub fn amount(&self) -> Option<i32> {
+        match self {
+            Price::Eur(amount) | Price::Usd(amount) | Price::Gbp(amount) => Some(*amount),
+            Price::Unknown => None,
+        }
+    }
+
+    /// The ISO 4217 code of this price's currency.
+    pub fn currency(&self) -> Option<&'static str> {
+        match self {
+            Price::Eur(_) => Some("EUR"),
+            Price::Usd(_) => Some("USD"),
+            Price::Gbp(_) => Some("GBP"),
+            Price::Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum Mileage {
     Km(i32),
     Mi(i32),
+    #[default]
     Unknown,
 }
 
+const KM_PER_MILE: f64 = 1.609344;
+
+impl Mileage {
+    /// The mileage expressed in kilometres, converting from miles where necessary.
+    pub fn kms(&self) -> Option<i32> {
+        match self {
+            Mileage::Km(kms) => Some(*kms),
+            Mileage::Mi(mis) => Some((*mis as f64 * KM_PER_MILE).round() as i32),
+            Mileage::Unknown => None,
+        }
+    }
+
+    /// The mileage expressed in miles, converting from kilometres where necessary.
+    pub fn miles(&self) -> Option<i32> {
+        match self {
+            Mileage::Km(kms) => Some((*kms as f64 / KM_PER_MILE).round() as i32),
+            Mileage::Mi(mis) => Some(*mis),
+            Mileage::Unknown => None,
+        }
+    }
+}
+
 /// The domain object that encodes a search hit, agnostic of which search engine has provided it.
 /// This is the core model that drives further processing.
-#[derive(Debug, Eq, PartialEq, Serialize, Clone)]
+#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Hit {
     pub search_engine: String,
     pub make: String,
@@ -26,4 +81,24 @@ pub struct Hit {
     pub year: u16,
     pub price: Price,
     pub url: String,
+    pub fuel: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// The make and model of the query that this hit matched, when searching for several
+    pub matched: Option<String>,
+    /// The fair price estimated by the valuation, in the same currency as `price`
+    pub expected_price: Option<Price>,
+    /// The difference between the price and the expected price; negative is below market
+    pub residual: Option<i32>,
+    /// How far below the expected price this hit is, as a percentage of the expected price
+    pub deal_score: Option<i32>,
+    /// The age of the vehicle in years, based on its year of registration
+    pub age: Option<u16>,
+    /// The average distance driven per year, in kilometres
+    pub km_per_year: Option<i32>,
+    /// The price per 1,000 km driven, in the same currency as `price`
+    pub price_per_1000_km: Option<Price>,
+    /// The reasons this hit was flagged as implausible or suspicious, if any
+    #[serde(default)]
+    pub anomalies: Vec<String>,
 }
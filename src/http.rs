@@ -0,0 +1,217 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Error;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use reqwest::{Client, Request};
+
+use crate::args::GlobalArgs;
+use crate::paths;
+
+/// The maximum number of requests per second to any single host that scrapers make, unless
+/// configured otherwise. Scraped sites offer no API meant for findcar, so they are not hurried.
+pub const POLITE_REQUESTS_PER_SECOND: f64 = 1.0;
+
+/// The settings that apply to every request made by the search engines.
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub timeout: Duration,
+    pub user_agent: Option<String>,
+    /// The maximum number of requests per second to any single host, if configured; zero is
+    /// unlimited. If not configured, only scrapers are limited, to `POLITE_REQUESTS_PER_SECOND`.
+    pub requests_per_second: Option<f64>,
+    /// How long responses are cached for; responses are not cached if zero
+    pub cache_ttl: Duration,
+    pub cache_dir: PathBuf,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            user_agent: None,
+            requests_per_second: None,
+            cache_ttl: Duration::ZERO,
+            cache_dir: paths::cache_dir(),
+        }
+    }
+}
+
+impl From<&GlobalArgs> for HttpSettings {
+    fn from(args: &GlobalArgs) -> Self {
+        let defaults = HttpSettings::default();
+        Self {
+            timeout: Duration::from_secs(args.timeout),
+            user_agent: args.user_agent.clone(),
+            requests_per_second: args.requests_per_second,
+            cache_ttl: Duration::from_secs(args.cache_ttl),
+            cache_dir: args
+                .cache_dir
+                .as_ref()
+                .map(PathBuf::from)
+                .unwrap_or(defaults.cache_dir),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+
+/// The time at which the next request to a host may be made, by host.
+static NEXT_REQUEST: LazyLock<Mutex<HashMap<String, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Set the HTTP settings for the rest of the process. Only the first call has any effect.
+pub fn configure(settings: HttpSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> &'static HttpSettings {
+    SETTINGS.get_or_init(HttpSettings::default)
+}
+
+/// A client that applies the timeout and user agent of the HTTP settings.
+pub fn client() -> Client {
+    let settings = settings();
+    let mut builder = Client::builder().timeout(settings.timeout);
+    if let Some(user_agent) = &settings.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    builder.build().unwrap_or_else(|_| Client::new())
+}
+
+/// Reserve the next slot for a request to a host, returning how long to wait for it. Slots are
+/// `interval` apart, so that concurrent requests to the same host are spread out as well.
+fn reserve(
+    next: &mut HashMap<String, Instant>,
+    host: &str,
+    now: Instant,
+    interval: Duration,
+) -> Duration {
+    let slot = next
+        .get(host)
+        .copied()
+        .filter(|slot| *slot > now)
+        .unwrap_or(now);
+    next.insert(host.to_string(), slot + interval);
+    slot - now
+}
+
+/// Wait for the next slot for a request to a host, at the configured rate or else the provided
+/// default rate, if any.
+async fn throttle(host: &str, default: Option<f64>) {
+    let Some(requests_per_second) = settings()
+        .requests_per_second
+        .or(default)
+        .filter(|r| *r > 0.0)
+    else {
+        return;
+    };
+    let interval = Duration::from_secs_f64(1.0 / requests_per_second);
+    let wait = {
+        let mut next = NEXT_REQUEST.lock().unwrap_or_else(|e| e.into_inner());
+        reserve(&mut next, host, Instant::now(), interval)
+    };
+    tokio::time::sleep(wait).await;
+}
+
+/// The file in which the response to a request is cached, identified by the method, URL and body
+/// of the request.
+fn cache_path(request: &Request) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    request.method().as_str().hash(&mut hasher);
+    request.url().as_str().hash(&mut hasher);
+    request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .hash(&mut hasher);
+    settings()
+        .cache_dir
+        .join(format!("{:016x}.json", hasher.finish()))
+}
+
+fn read_cache(path: &PathBuf, ttl: Duration) -> Option<String> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > ttl {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+/// Send a request and return the body of its response, applying the HTTP settings: requests are
+/// rate limited per host if configured, and successful responses are cached if caching is enabled.
+pub async fn fetch(client: &Client, request: Request) -> Result<String, Error> {
+    send(client, request, None).await
+}
+
+/// Send a request for a page to scrape, as `fetch` does, but limited to
+/// `POLITE_REQUESTS_PER_SECOND` per host unless the rate is configured.
+pub async fn fetch_politely(client: &Client, request: Request) -> Result<String, Error> {
+    send(client, request, Some(POLITE_REQUESTS_PER_SECOND)).await
+}
+
+async fn send(
+    client: &Client,
+    request: Request,
+    default_rate: Option<f64>,
+) -> Result<String, Error> {
+    let settings = settings();
+    let cache = Some(cache_path(&request)).filter(|_| !settings.cache_ttl.is_zero());
+    if let Some(body) = cache
+        .as_ref()
+        .and_then(|path| read_cache(path, settings.cache_ttl))
+    {
+        return Ok(body);
+    }
+
+    // Services on different ports of a host are throttled separately
+    let url = request.url();
+    let host = format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    );
+    throttle(&host, default_rate).await;
+    let body = client
+        .execute(request)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(Error::other)?
+        .text()
+        .await
+        .map_err(Error::other)?;
+
+    if let Some(path) = cache {
+        // A failure to cache is not a reason to fail the request
+        let _ =
+            std::fs::create_dir_all(&settings.cache_dir).and_then(|_| std::fs::write(path, &body));
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_to_a_host_are_spread_out() {
+        let mut next = HashMap::new();
+        let now = Instant::now();
+        let interval = Duration::from_millis(500);
+
+        assert_eq!(reserve(&mut next, "a", now, interval), Duration::ZERO);
+        assert_eq!(reserve(&mut next, "a", now, interval), interval);
+        assert_eq!(reserve(&mut next, "a", now, interval), interval * 2);
+        assert_eq!(reserve(&mut next, "b", now, interval), Duration::ZERO);
+        // Once the reserved slots have passed, requests are no longer delayed
+        assert_eq!(
+            reserve(&mut next, "a", now + interval * 4, interval),
+            Duration::ZERO
+        );
+    }
+}
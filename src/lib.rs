@@ -0,0 +1,51 @@
+//! findcar searches multiple car search engines at once, and filters, sorts and aggregates their
+//! results.
+//!
+//! The library exposes the pieces the command line interface is built from: the [`Engine`] that
+//! drives a number of [`Searcher`]s with a [`Query`], the [`Hit`]s they find, and the
+//! [`Pipeline`] of post-processing [`Action`]s that filter, sort and limit them. Every search
+//! engine is behind a cargo feature of the same name (e.g. `carzone_ie`). Those of Irish sites
+//! (`carzone_ie`, `donedeal_ie`, `carsireland_ie` and `adverts_ie`) are enabled by default; the
+//! others are opt-in.
+
+pub mod aggregate;
+pub mod emit;
+pub mod engine;
+pub mod hit;
+pub mod http;
+pub mod post_processing;
+pub mod query;
+pub mod search;
+pub mod taxonomy;
+
+mod args;
+mod calendar;
+mod commands;
+mod config;
+mod paths;
+
+pub use engine::Engine;
+pub use hit::Hit;
+pub use post_processing::{Action, Pipeline};
+pub use query::Query;
+pub use search::{Registry, SearchResult, Searcher};
+
+use args::{Cli, Command};
+
+/// Run the command line interface with the arguments of the process.
+pub async fn run() {
+    let cli = Cli::parse_ordered();
+    http::configure((&cli.global).into());
+
+    match &cli.command {
+        None => commands::search::run(&cli.global, &cli.search).await,
+        Some(Command::Search(args)) => commands::search::run(&cli.global, args).await,
+        Some(Command::Engines) => commands::engines::run(&cli.global).await,
+        Some(Command::Makes) => commands::makes::run(&cli.global, None).await,
+        Some(Command::Models { make }) => commands::makes::run(&cli.global, Some(make)).await,
+        Some(Command::History(args)) => commands::history::run(args),
+        Some(Command::Watch(args)) => commands::watch::run(&cli.global, args).await,
+        Some(Command::Serve(args)) => commands::serve::run(&cli.global, args).await,
+        Some(Command::Config { action }) => commands::config::run(&cli, action),
+    }
+}
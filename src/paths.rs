@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+/// The directory of an XDG base directory variable, falling back to the provided directory
+/// relative to the home directory.
+fn xdg_dir(variable: &str, fallback: &str) -> PathBuf {
+    let base = std::env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| {
+            std::env::var_os("HOME")
+                .map(PathBuf::from)
+                .unwrap_or_default()
+                .join(fallback)
+        });
+    base.join("findcar")
+}
+
+/// The directory in which configuration is kept, e.g. `~/.config/findcar`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// The directory in which data such as the search history is kept, e.g. `~/.local/share/findcar`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// The directory in which cached responses are kept, e.g. `~/.cache/findcar`.
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use crate::aggregate::median;
+use crate::hit::Hit;
+
+use super::Action;
+
+/// Prices at or below this amount are considered placeholders rather than asking prices.
+const PLACEHOLDER_PRICE: i32 = 100;
+/// Cars that are at least this many years old are expected to have at least MIN_PLAUSIBLE_KMS.
+const MIN_AGE_FOR_MILEAGE_CHECK: u16 = 2;
+const MIN_PLAUSIBLE_KMS: i32 = 1000;
+/// The minimum number of comparable hits needed before outliers are detected within a group.
+const MIN_GROUP_SIZE: usize = 5;
+/// Hits whose modified z-score exceeds this threshold are considered outliers.
+const OUTLIER_THRESHOLD: f64 = 3.5;
+
+/// An anomaly detection action, which identifies hits with placeholder values (such as a price of
+/// €1 or a missing year) and hits whose price or mileage is a statistical outlier among cars of
+/// the same make, model and year. Outliers are detected using the median absolute deviation,
+/// which is robust against the very outliers it is looking for.
+pub struct AnomalyDetection {
+    mode: AnomalyMode,
+    current_year: u16,
+}
+
+pub enum AnomalyMode {
+    /// Record the reasons on each anomalous hit, but keep it.
+    Flag,
+    /// Remove anomalous hits altogether.
+    Remove,
+}
+
+impl AnomalyDetection {
+    pub fn new(mode: AnomalyMode, current_year: u16) -> Self {
+        Self { mode, current_year }
+    }
+
+    fn placeholder_reasons(&self, hit: &Hit) -> Vec<String> {
+        let mut reasons = vec![];
+
+        if let (Some(amount), Some(currency)) = (hit.price.amount(), hit.price.currency()) {
+            if amount <= PLACEHOLDER_PRICE {
+                reasons.push(format!(
+                    "price of {} {} looks like a placeholder",
+                    amount, currency
+                ));
+            }
+        }
+
+        if hit.year == 0 {
+            reasons.push("year of registration is missing".to_string());
+        } else if hit.year > self.current_year + 1 {
+            reasons.push(format!("year {} is in the future", hit.year));
+        } else if let Some(kms) = hit.mileage.kms() {
+            if kms < MIN_PLAUSIBLE_KMS
+                && self.current_year.saturating_sub(hit.year) >= MIN_AGE_FOR_MILEAGE_CHECK
+            {
+                reasons.push(format!(
+                    "mileage of {} km is implausible for a {} car",
+                    kms, hit.year
+                ));
+            }
+        }
+
+        reasons
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Measure {
+    Price,
+    Mileage,
+}
+
+impl Measure {
+    fn value(&self, hit: &Hit) -> Option<i32> {
+        match self {
+            Measure::Price => hit.price.amount().filter(|a| *a > PLACEHOLDER_PRICE),
+            Measure::Mileage => hit.mileage.kms().filter(|k| *k >= MIN_PLAUSIBLE_KMS),
+        }
+    }
+
+    fn unit(&self, hit: &Hit) -> &'static str {
+        match self {
+            Measure::Price => hit.price.currency().unwrap_or(""),
+            Measure::Mileage => "km",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Measure::Price => "price",
+            Measure::Mileage => "mileage",
+        }
+    }
+}
+
+type GroupKey = (String, String, u16, &'static str);
+
+fn group_key(hit: &Hit, measure: Measure) -> Option<GroupKey> {
+    if hit.year == 0 {
+        return None;
+    }
+    let unit = match measure {
+        Measure::Price => hit.price.currency()?,
+        Measure::Mileage => "km",
+    };
+    Some((
+        hit.make.to_lowercase(),
+        hit.model.to_lowercase(),
+        hit.year,
+        unit,
+    ))
+}
+
+/// The median and median absolute deviation of a group's values.
+struct Spread {
+    median: f64,
+    mad: f64,
+}
+
+fn outlier_reasons(hits: &[Hit], measure: Measure) -> Vec<Option<String>> {
+    let mut groups: HashMap<GroupKey, Vec<f64>> = HashMap::new();
+    for hit in hits {
+        if let (Some(key), Some(value)) = (group_key(hit, measure), measure.value(hit)) {
+            groups.entry(key).or_default().push(value as f64);
+        }
+    }
+
+    let spreads: HashMap<GroupKey, Spread> = groups
+        .into_iter()
+        .filter(|(_, values)| values.len() >= MIN_GROUP_SIZE)
+        .filter_map(|(key, values)| {
+            let centre = median(&values)?;
+            let deviations: Vec<f64> = values.iter().map(|v| (v - centre).abs()).collect();
+            let mad = median(&deviations)?;
+            Some((
+                key,
+                Spread {
+                    median: centre,
+                    mad,
+                },
+            ))
+        })
+        .filter(|(_, spread)| spread.mad > 0.0)
+        .collect();
+
+    hits.iter()
+        .map(|hit| {
+            let spread = spreads.get(&group_key(hit, measure)?)?;
+            let value = measure.value(hit)?;
+            // The modified z-score (Iglewicz and Hoaglin), where 0.6745 is the 0.75 quantile of the
+            // standard normal distribution.
+            let z = 0.6745 * (value as f64 - spread.median) / spread.mad;
+            if z.abs() <= OUTLIER_THRESHOLD {
+                return None;
+            }
+            Some(format!(
+                "{} of {} {} is unusually {} for a {} {} {} (median {:.0} {})",
+                measure.name(),
+                value,
+                measure.unit(hit),
+                if z < 0.0 { "low" } else { "high" },
+                hit.year,
+                hit.make,
+                hit.model,
+                spread.median,
+                measure.unit(hit),
+            ))
+        })
+        .collect()
+}
+
+impl Action for AnomalyDetection {
+    fn execute(&self, mut hits: Vec<Hit>) -> Vec<Hit> {
+        let price_outliers = outlier_reasons(&hits, Measure::Price);
+        let mileage_outliers = outlier_reasons(&hits, Measure::Mileage);
+
+        for ((hit, price), mileage) in hits.iter_mut().zip(price_outliers).zip(mileage_outliers) {
+            let mut reasons = self.placeholder_reasons(hit);
+            reasons.extend(price);
+            reasons.extend(mileage);
+            hit.anomalies.extend(reasons);
+        }
+
+        match self.mode {
+            AnomalyMode::Flag => hits,
+            AnomalyMode::Remove => hits
+                .into_iter()
+                .filter(|hit| hit.anomalies.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&str> for AnomalyMode {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "flag" => Ok(AnomalyMode::Flag),
+            "remove" => Ok(AnomalyMode::Remove),
+            _ => Err(format!("Unrecognised anomaly mode: {}", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hit(year: u16, kms: i32, price: Price) -> Hit {
+        Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: "Fabia".to_string(),
+            mileage: Mileage::Km(kms),
+            year,
+            price,
+            url: "bla".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn plausible_group() -> Vec<Hit> {
+        vec![
+            hit(2015, 110000, Price::Eur(9000)),
+            hit(2015, 120000, Price::Eur(9500)),
+            hit(2015, 125000, Price::Eur(9200)),
+            hit(2015, 130000, Price::Eur(8800)),
+            hit(2015, 100000, Price::Eur(9900)),
+            hit(2015, 140000, Price::Eur(8500)),
+        ]
+    }
+
+    #[test]
+    fn plausible_hits_are_not_flagged() {
+        let detection = AnomalyDetection::new(AnomalyMode::Flag, 2023);
+        let result = detection.execute(plausible_group());
+
+        assert!(result.iter().all(|h| h.anomalies.is_empty()));
+    }
+
+    #[test]
+    fn placeholder_values_are_flagged() {
+        let detection = AnomalyDetection::new(AnomalyMode::Flag, 2023);
+        let result = detection.execute(vec![
+            hit(2015, 120000, Price::Eur(1)),
+            hit(2009, 1, Price::Eur(3000)),
+            hit(0, 50000, Price::Eur(3000)),
+            hit(2023, 5, Price::Eur(25000)),
+        ]);
+
+        assert_eq!(
+            result[0].anomalies,
+            vec!["price of 1 EUR looks like a placeholder".to_string()]
+        );
+        assert_eq!(
+            result[1].anomalies,
+            vec!["mileage of 1 km is implausible for a 2009 car".to_string()]
+        );
+        assert_eq!(
+            result[2].anomalies,
+            vec!["year of registration is missing".to_string()]
+        );
+        assert!(result[3].anomalies.is_empty());
+    }
+
+    #[test]
+    fn statistical_outliers_are_flagged() {
+        let mut hits = plausible_group();
+        hits.push(hit(2015, 115000, Price::Eur(2500)));
+        hits.push(hit(2015, 900000, Price::Eur(9100)));
+
+        let detection = AnomalyDetection::new(AnomalyMode::Flag, 2023);
+        let result = detection.execute(hits);
+
+        assert!(result[..6].iter().all(|h| h.anomalies.is_empty()));
+        assert_eq!(
+            result[6].anomalies,
+            vec![
+                "price of 2500 EUR is unusually low for a 2015 Skoda Fabia (median 9050 EUR)"
+                    .to_string()
+            ]
+        );
+        assert_eq!(
+            result[7].anomalies,
+            vec![
+                "mileage of 900000 km is unusually high for a 2015 Skoda Fabia (median 122500 km)"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn small_groups_are_not_tested_for_outliers() {
+        let hits = vec![
+            hit(2015, 110000, Price::Eur(9000)),
+            hit(2015, 120000, Price::Eur(9500)),
+            hit(2015, 125000, Price::Eur(900)),
+        ];
+
+        let detection = AnomalyDetection::new(AnomalyMode::Flag, 2023);
+        let result = detection.execute(hits);
+
+        assert!(result.iter().all(|h| h.anomalies.is_empty()));
+    }
+
+    #[test]
+    fn remove_mode_drops_anomalies() {
+        let mut hits = plausible_group();
+        hits.push(hit(2015, 115000, Price::Eur(2500)));
+        hits.push(hit(0, 115000, Price::Eur(9000)));
+
+        let detection = AnomalyDetection::new(AnomalyMode::Remove, 2023);
+        let result = detection.execute(hits);
+
+        assert_eq!(result, plausible_group());
+    }
+}
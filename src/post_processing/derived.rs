@@ -0,0 +1,124 @@
+use crate::hit::{Hit, Price};
+
+use super::Action;
+
+/// The keys of the derived fields, as sorted and filtered on.
+pub const DERIVED_FIELDS: [&str; 3] = ["age", "km_per_year", "price_per_1000_km"];
+
+/// An action which computes metrics that are derived from other fields of each Hit: the age of
+/// the vehicle, the average distance it has been driven per year, and its price per 1,000 km.
+/// Distances are normalised to kilometres.
+pub struct DerivedFields {
+    current_year: u16,
+}
+
+impl DerivedFields {
+    pub fn new(current_year: u16) -> Self {
+        Self { current_year }
+    }
+}
+
+fn per_1000_km(price: &Price, kms: i32) -> Option<Price> {
+    if kms <= 0 {
+        return None;
+    }
+    let per_1000 = |amount: i32| (amount as f64 * 1000.0 / kms as f64).round() as i32;
+    match price {
+        Price::Eur(amount) => Some(Price::Eur(per_1000(*amount))),
+        Price::Usd(amount) => Some(Price::Usd(per_1000(*amount))),
+        Price::Gbp(amount) => Some(Price::Gbp(per_1000(*amount))),
+        Price::Unknown => None,
+    }
+}
+
+impl Action for DerivedFields {
+    fn execute(&self, mut hits: Vec<Hit>) -> Vec<Hit> {
+        for hit in hits.iter_mut() {
+            hit.age = match hit.year {
+                0 => None,
+                year => Some(self.current_year.saturating_sub(year)),
+            };
+
+            let kms = hit.mileage.kms();
+            // A car in its first year of registration has been driven for (up to) a year
+            hit.km_per_year = kms
+                .zip(hit.age)
+                .map(|(kms, age)| kms / i32::from(age.max(1)));
+            hit.price_per_1000_km = kms.and_then(|kms| per_1000_km(&hit.price, kms));
+        }
+        hits
+    }
+
+    fn per_hit(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::Mileage;
+
+    use super::*;
+
+    fn hit(year: u16, mileage: Mileage, price: Price) -> Hit {
+        Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: "Fabia".to_string(),
+            mileage,
+            year,
+            price,
+            url: "bla".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn derives_fields() {
+        let result =
+            DerivedFields::new(2023).execute(vec![hit(2018, Mileage::Km(75000), Price::Eur(9000))]);
+
+        assert_eq!(result[0].age, Some(5));
+        assert_eq!(result[0].km_per_year, Some(15000));
+        assert_eq!(result[0].price_per_1000_km, Some(Price::Eur(120)));
+    }
+
+    #[test]
+    fn normalises_miles() {
+        let result = DerivedFields::new(2023).execute(vec![hit(
+            2021,
+            Mileage::Mi(10000),
+            Price::Gbp(16093),
+        )]);
+
+        assert_eq!(result[0].age, Some(2));
+        assert_eq!(result[0].km_per_year, Some(8046));
+        assert_eq!(result[0].price_per_1000_km, Some(Price::Gbp(1000)));
+    }
+
+    #[test]
+    fn new_cars_count_as_one_year() {
+        let result =
+            DerivedFields::new(2023).execute(vec![hit(2023, Mileage::Km(8000), Price::Eur(25000))]);
+
+        assert_eq!(result[0].age, Some(0));
+        assert_eq!(result[0].km_per_year, Some(8000));
+    }
+
+    #[test]
+    fn unknown_inputs() {
+        let result = DerivedFields::new(2023).execute(vec![
+            hit(0, Mileage::Km(8000), Price::Eur(25000)),
+            hit(2020, Mileage::Unknown, Price::Eur(25000)),
+            hit(2020, Mileage::Km(0), Price::Unknown),
+        ]);
+
+        assert_eq!(result[0].age, None);
+        assert_eq!(result[0].km_per_year, None);
+        assert_eq!(result[0].price_per_1000_km, Some(Price::Eur(3125)));
+        assert_eq!(result[1].km_per_year, None);
+        assert_eq!(result[1].price_per_1000_km, None);
+        assert_eq!(result[2].km_per_year, Some(0));
+        assert_eq!(result[2].price_per_1000_km, None);
+    }
+}
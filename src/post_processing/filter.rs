@@ -0,0 +1,209 @@
+use crate::hit::{Hit, Price};
+
+use super::Action;
+
+/// A filtering action, which keeps only those Hits for which the value identified by the provided
+/// FilterBy satisfies the comparison. Hits for which the value is unknown are removed.
+pub struct Filter {
+    by: FilterBy,
+    comparison: Comparison,
+    value: i64,
+}
+
+pub enum FilterBy {
+    Price,
+    Year,
+    Mileage,
+    DealScore,
+    Age,
+    KmPerYear,
+    PricePer1000Km,
+}
+
+pub enum Comparison {
+    LessThan,
+    AtMost,
+    Equal,
+    AtLeast,
+    GreaterThan,
+}
+
+impl Filter {
+    pub fn new(by: FilterBy, comparison: Comparison, value: i64) -> Self {
+        Self {
+            by,
+            comparison,
+            value,
+        }
+    }
+
+    fn value_of(&self, hit: &Hit) -> Option<i64> {
+        match self.by {
+            FilterBy::Price => hit.price.amount().map(i64::from),
+            FilterBy::Year => Some(hit.year).filter(|y| *y > 0).map(i64::from),
+            FilterBy::Mileage => hit.mileage.kms().map(i64::from),
+            FilterBy::DealScore => hit.deal_score.map(i64::from),
+            FilterBy::Age => hit.age.map(i64::from),
+            FilterBy::KmPerYear => hit.km_per_year.map(i64::from),
+            FilterBy::PricePer1000Km => hit
+                .price_per_1000_km
+                .as_ref()
+                .and_then(Price::amount)
+                .map(i64::from),
+        }
+    }
+}
+
+impl TryFrom<&str> for FilterBy {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "price" => Ok(FilterBy::Price),
+            "year" => Ok(FilterBy::Year),
+            "mileage" => Ok(FilterBy::Mileage),
+            "deal_score" => Ok(FilterBy::DealScore),
+            "age" => Ok(FilterBy::Age),
+            "km_per_year" => Ok(FilterBy::KmPerYear),
+            "price_per_1000_km" => Ok(FilterBy::PricePer1000Km),
+            _ => Err(format!("Unrecognised key for filtering: {}", value)),
+        }
+    }
+}
+
+impl TryFrom<&str> for Filter {
+    type Error = String;
+
+    /// Parse a filter expression such as `deal_score>=10` or `year<2015`.
+    fn try_from(expression: &str) -> Result<Self, Self::Error> {
+        let operators = [
+            ("<=", Comparison::AtMost),
+            (">=", Comparison::AtLeast),
+            ("<", Comparison::LessThan),
+            (">", Comparison::GreaterThan),
+            ("=", Comparison::Equal),
+        ];
+
+        for (operator, comparison) in operators {
+            if let Some((key, value)) = expression.split_once(operator) {
+                let by = FilterBy::try_from(key.trim())?;
+                let value = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid value in filter: {}", expression))?;
+                return Ok(Filter::new(by, comparison, value));
+            }
+        }
+
+        Err(format!("Invalid filter: {}", expression))
+    }
+}
+
+impl Action for Filter {
+    fn execute(&self, hits: Vec<Hit>) -> Vec<Hit> {
+        hits.into_iter()
+            .filter(|hit| match self.value_of(hit) {
+                None => false,
+                Some(v) => match self.comparison {
+                    Comparison::LessThan => v < self.value,
+                    Comparison::AtMost => v <= self.value,
+                    Comparison::Equal => v == self.value,
+                    Comparison::AtLeast => v >= self.value,
+                    Comparison::GreaterThan => v > self.value,
+                },
+            })
+            .collect()
+    }
+
+    fn per_hit(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::Mileage;
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Km(100),
+                year: 2001,
+                price: Price::Eur(100),
+                url: "bla".to_string(),
+                deal_score: Some(15),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Mi(1000),
+                year: 1999,
+                price: Price::Unknown,
+                url: "bla".to_string(),
+                deal_score: None,
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Km(1001),
+                year: 2022,
+                price: Price::Eur(21),
+                url: "bla".to_string(),
+                deal_score: Some(-5),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn filter_deal_score_at_least() {
+        let filter = Filter::try_from("deal_score>=10").unwrap();
+        let result = filter.execute(hits());
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].deal_score, Some(15));
+    }
+
+    #[test]
+    fn filter_year_less_than() {
+        let filter = Filter::try_from("year < 2022").unwrap();
+        let result = filter.execute(hits());
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].year, 2001);
+        assert_eq!(result[1].year, 1999);
+    }
+
+    #[test]
+    fn filter_mileage_normalises_miles() {
+        let filter = Filter::try_from("mileage>1500").unwrap();
+        let result = filter.execute(hits());
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].mileage, Mileage::Mi(1000));
+    }
+
+    #[test]
+    fn filter_drops_unknown_values() {
+        let filter = Filter::try_from("price<=1000").unwrap();
+        let result = filter.execute(hits());
+
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert!(Filter::try_from("colour=red").is_err());
+        assert!(Filter::try_from("price<cheap").is_err());
+        assert!(Filter::try_from("price").is_err());
+    }
+}
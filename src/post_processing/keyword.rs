@@ -0,0 +1,142 @@
+use crate::hit::Hit;
+
+use super::Action;
+
+/// A keyword filtering action, which keeps only those Hits whose text (title, description, make and
+/// model) contains every one of the keywords, and none of the excluded keywords. Keywords may be
+/// phrases such as "full service history", and are matched case-insensitively. Hits without a title
+/// or description cannot be checked for the keywords, and are kept unless they contain any of the
+/// excluded keywords.
+pub struct KeywordFilter {
+    keywords: Vec<String>,
+    excluded: Vec<String>,
+}
+
+impl KeywordFilter {
+    pub fn new(keywords: &[String], excluded: &[String]) -> Self {
+        let lowercase = |words: &[String]| words.iter().map(|w| w.to_lowercase()).collect();
+        Self {
+            keywords: lowercase(keywords),
+            excluded: lowercase(excluded),
+        }
+    }
+
+    fn matches(&self, hit: &Hit) -> bool {
+        let text = [
+            hit.title.as_deref(),
+            hit.description.as_deref(),
+            Some(&hit.make),
+            Some(&hit.model),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+
+        (!has_text(hit) || self.keywords.iter().all(|k| text.contains(k.as_str())))
+            && !self.excluded.iter().any(|k| text.contains(k.as_str()))
+    }
+}
+
+/// Whether a hit has a title or description to check keywords against.
+pub fn has_text(hit: &Hit) -> bool {
+    hit.title.is_some() || hit.description.is_some()
+}
+
+impl Action for KeywordFilter {
+    fn execute(&self, hits: Vec<Hit>) -> Vec<Hit> {
+        hits.into_iter().filter(|hit| self.matches(hit)).collect()
+    }
+
+    fn per_hit(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hit(title: &str, description: Option<&str>) -> Hit {
+        Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: "Octavia Estate".to_string(),
+            mileage: Mileage::Km(100),
+            year: 2001,
+            price: Price::Eur(100),
+            url: "bla".to_string(),
+            title: Some(title.to_string()),
+            description: description.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            hit(
+                "2015 Octavia with towbar",
+                Some("Full Service History, NCT"),
+            ),
+            hit("2016 Octavia", Some("Slightly crashed, spares or repairs")),
+            hit("Octavia TOWBAR", None),
+        ]
+    }
+
+    #[test]
+    fn keywords_match_title_and_description() {
+        let filter = KeywordFilter::new(
+            &["towbar".to_string(), "full service history".to_string()],
+            &[],
+        );
+        let result = filter.execute(hits());
+
+        assert_eq!(result, vec![hits()[0].clone()]);
+    }
+
+    #[test]
+    fn keywords_match_make_and_model() {
+        let filter = KeywordFilter::new(&["estate".to_string()], &[]);
+        let result = filter.execute(hits());
+
+        assert_eq!(result.len(), 3);
+    }
+
+    #[test]
+    fn excluded_keywords_remove_hits() {
+        let filter = KeywordFilter::new(
+            &[],
+            &["crashed".to_string(), "spares or repairs".to_string()],
+        );
+        let result = filter.execute(hits());
+
+        assert_eq!(result, vec![hits()[0].clone(), hits()[2].clone()]);
+    }
+
+    #[test]
+    fn hits_without_text_are_kept_unless_excluded() {
+        let untitled = Hit {
+            title: None,
+            ..hit("", None)
+        };
+        let filter = KeywordFilter::new(&["towbar".to_string()], &[]);
+        assert_eq!(
+            filter.execute(vec![untitled.clone()]),
+            vec![untitled.clone()]
+        );
+
+        let filter = KeywordFilter::new(&[], &["octavia".to_string()]);
+        assert!(filter.execute(vec![untitled]).is_empty());
+    }
+
+    #[test]
+    fn no_keywords_keeps_everything() {
+        let filter = KeywordFilter::new(&[], &[]);
+        let result = filter.execute(hits());
+
+        assert_eq!(result, hits());
+    }
+}
@@ -36,6 +36,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -45,6 +46,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -54,6 +56,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -70,6 +73,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },]
         );
     }
@@ -85,6 +89,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -94,6 +99,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -103,6 +109,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -120,6 +127,7 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -129,6 +137,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -138,6 +147,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ]
         );
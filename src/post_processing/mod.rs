@@ -1,11 +1,25 @@
 use crate::hit::Hit;
 
+pub mod anomaly;
+pub mod derived;
+pub mod filter;
+pub mod keyword;
 pub mod limit;
+pub mod offset;
 pub mod sort;
+pub mod spec;
+pub mod top_per_group;
+pub mod valuation;
 
 /// A trait for any post-processing action that takes hits and process them
-pub trait Action {
+pub trait Action: Send + Sync {
     fn execute(&self, hits: Vec<Hit>) -> Vec<Hit>;
+
+    /// Whether this action treats every hit on its own, so that it can be applied to hits as they
+    /// arrive rather than to all of them at once. Actions such as sorting cannot.
+    fn per_hit(&self) -> bool {
+        false
+    }
 }
 
 /// This struct expresses numerous actions which will be performed in-order, to process a vector of
@@ -26,4 +40,32 @@ impl Action for Pipeline {
     fn execute(&self, hits: Vec<Hit>) -> Vec<Hit> {
         self.actions.iter().fold(hits, |acc, cur| cur.execute(acc))
     }
+
+    fn per_hit(&self) -> bool {
+        self.actions.iter().all(|action| action.per_hit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::derived::DerivedFields;
+    use super::filter::Filter;
+    use super::sort::{Sort, SortBy, SortOrder};
+    use super::*;
+
+    #[test]
+    fn pipelines_are_per_hit_if_all_their_actions_are() {
+        let per_hit = || -> Vec<Box<dyn Action>> {
+            vec![
+                Box::new(DerivedFields::new(2024)),
+                Box::new(Filter::try_from("year>=2018").unwrap()),
+            ]
+        };
+        let mut sorted = per_hit();
+        sorted.push(Box::new(Sort::new(SortBy::Price, SortOrder::Asc)));
+
+        assert!(Pipeline::from(vec![]).per_hit());
+        assert!(Pipeline::from(per_hit()).per_hit());
+        assert!(!Pipeline::from(sorted).per_hit());
+    }
 }
@@ -0,0 +1,91 @@
+use crate::hit::Hit;
+
+use super::Action;
+
+/// An offset action, which skips the first given number of Hits. Combined with Limit, this can be
+/// used to page through a large set of Hits.
+pub struct Offset {
+    offset: usize,
+}
+
+impl Offset {
+    pub fn new(offset: usize) -> Self {
+        Self { offset }
+    }
+}
+
+impl Action for Offset {
+    fn execute(&self, hits: Vec<Hit>) -> Vec<Hit> {
+        hits.into_iter().skip(self.offset).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Km(100),
+                year: 2001,
+                price: Price::Eur(100),
+                url: "bla".to_string(),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Km(1000),
+                year: 1999,
+                price: Price::Eur(101),
+                url: "bla".to_string(),
+                ..Default::default()
+            },
+            Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Km(1001),
+                year: 2022,
+                price: Price::Eur(21),
+                url: "bla".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn offset_two() {
+        let offset = Offset::new(2);
+        let result = offset.execute(hits());
+
+        assert_eq!(
+            result,
+            vec![Hit {
+                search_engine: "foo".to_string(),
+                make: "Skoda".to_string(),
+                model: "Fabia".to_string(),
+                mileage: Mileage::Km(1001),
+                year: 2022,
+                price: Price::Eur(21),
+                url: "bla".to_string(),
+                ..Default::default()
+            },]
+        );
+    }
+
+    #[test]
+    fn offset_exceeding_length() {
+        let offset = Offset::new(10);
+        let result = offset.execute(hits());
+
+        assert_eq!(result, vec![]);
+    }
+}
@@ -1,6 +1,6 @@
 use std::cmp::Reverse;
 
-use crate::hit::Hit;
+use crate::hit::{Hit, Price};
 
 use super::Action;
 
@@ -15,6 +15,10 @@ pub enum SortBy {
     Price,
     Year,
     Mileage,
+    DealScore,
+    Age,
+    KmPerYear,
+    PricePer1000Km,
 }
 
 pub enum SortOrder {
@@ -28,26 +32,113 @@ impl Sort {
     }
 }
 
+impl TryFrom<&str> for SortBy {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "price" => Ok(SortBy::Price),
+            "mileage" => Ok(SortBy::Mileage),
+            "year" => Ok(SortBy::Year),
+            "deal_score" => Ok(SortBy::DealScore),
+            "age" => Ok(SortBy::Age),
+            "km_per_year" => Ok(SortBy::KmPerYear),
+            "price_per_1000_km" => Ok(SortBy::PricePer1000Km),
+            _ => Err(format!("Unrecognised key for sorting: {}", value)),
+        }
+    }
+}
+
+impl TryFrom<&str> for SortOrder {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_uppercase().as_str() {
+            "ASC" => Ok(SortOrder::Asc),
+            "DESC" => Ok(SortOrder::Desc),
+            _ => Err(format!("Unrecognised sort order: {}", value)),
+        }
+    }
+}
+
+/// A key that orders prices by currency (EUR, GBP, USD), as prices in different currencies cannot
+/// be compared, and by their amount, as given, within a currency. Unknown prices are last.
+fn by_currency<T: Ord>(
+    price: &Price,
+    amount: impl Fn(i32) -> T,
+) -> (bool, Option<&'static str>, Option<T>) {
+    (
+        price.currency().is_none(),
+        price.currency(),
+        price.amount().map(amount),
+    )
+}
+
+/// A key that orders values as they are, with unknown values last. Values are wrapped in
+/// `Reverse` to sort in descending order, which keeps unknown values last.
+fn unknown_last<T: Ord>(value: Option<T>) -> (bool, Option<T>) {
+    (value.is_none(), value)
+}
+
+/// The year of a hit, unless it is unknown, which search engines report as 0.
+fn known_year(hit: &Hit) -> Option<u16> {
+    Some(hit.year).filter(|year| *year > 0)
+}
+
 impl Action for Sort {
     fn execute(&self, mut hits: Vec<Hit>) -> Vec<Hit> {
         match (&self.by, &self.order) {
             (SortBy::Price, SortOrder::Asc) => {
-                hits.sort_by_key(|x| x.price.clone());
+                hits.sort_by_key(|x| by_currency(&x.price, |amount| amount));
             }
             (SortBy::Price, SortOrder::Desc) => {
-                hits.sort_by_key(|x| Reverse(x.price.clone()));
+                hits.sort_by_key(|x| by_currency(&x.price, Reverse));
             }
             (SortBy::Year, SortOrder::Asc) => {
-                hits.sort_by_key(|x| x.year);
+                hits.sort_by_key(|x| unknown_last(known_year(x)));
             }
             (SortBy::Year, SortOrder::Desc) => {
-                hits.sort_by_key(|x| Reverse(x.year));
+                hits.sort_by_key(|x| unknown_last(known_year(x).map(Reverse)));
             }
             (SortBy::Mileage, SortOrder::Asc) => {
-                hits.sort_by_key(|x| x.mileage.clone());
+                hits.sort_by_key(|x| unknown_last(x.mileage.kms()));
             }
             (SortBy::Mileage, SortOrder::Desc) => {
-                hits.sort_by_key(|x| Reverse(x.mileage.clone()));
+                hits.sort_by_key(|x| unknown_last(x.mileage.kms().map(Reverse)));
+            }
+            (SortBy::DealScore, SortOrder::Asc) => {
+                hits.sort_by_key(|x| unknown_last(x.deal_score));
+            }
+            (SortBy::DealScore, SortOrder::Desc) => {
+                hits.sort_by_key(|x| unknown_last(x.deal_score.map(Reverse)));
+            }
+            (SortBy::Age, SortOrder::Asc) => {
+                hits.sort_by_key(|x| unknown_last(x.age));
+            }
+            (SortBy::Age, SortOrder::Desc) => {
+                hits.sort_by_key(|x| unknown_last(x.age.map(Reverse)));
+            }
+            (SortBy::KmPerYear, SortOrder::Asc) => {
+                hits.sort_by_key(|x| unknown_last(x.km_per_year));
+            }
+            (SortBy::KmPerYear, SortOrder::Desc) => {
+                hits.sort_by_key(|x| unknown_last(x.km_per_year.map(Reverse)));
+            }
+            (SortBy::PricePer1000Km, SortOrder::Asc) => {
+                hits.sort_by_key(|x| {
+                    by_currency(
+                        x.price_per_1000_km.as_ref().unwrap_or(&Price::Unknown),
+                        |amount| amount,
+                    )
+                });
+            }
+            (SortBy::PricePer1000Km, SortOrder::Desc) => {
+                hits.sort_by_key(|x| {
+                    by_currency(
+                        x.price_per_1000_km.as_ref().unwrap_or(&Price::Unknown),
+                        Reverse,
+                    )
+                });
             }
         };
         hits
@@ -71,6 +162,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -80,6 +172,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -89,6 +182,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -106,6 +200,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -115,6 +210,7 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -124,6 +220,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ],
         );
@@ -140,6 +237,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -149,6 +247,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -158,6 +257,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -175,6 +275,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -184,6 +285,7 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -193,6 +295,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ],
         );
@@ -209,6 +312,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -218,6 +322,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -227,6 +332,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -244,6 +350,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -253,6 +360,7 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -262,6 +370,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ],
         );
@@ -278,6 +387,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -287,6 +397,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -296,6 +407,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -313,6 +425,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -322,6 +435,7 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -331,6 +445,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ],
         );
@@ -347,6 +462,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -356,6 +472,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -365,6 +482,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -382,6 +500,7 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -391,6 +510,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -400,6 +520,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ],
         );
@@ -416,6 +537,7 @@ mod tests {
                 year: 2001,
                 price: Price::Eur(100),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -425,6 +547,7 @@ mod tests {
                 year: 1999,
                 price: Price::Eur(101),
                 url: "bla".to_string(),
+                ..Default::default()
             },
             Hit {
                 search_engine: "foo".to_string(),
@@ -434,6 +557,7 @@ mod tests {
                 year: 2022,
                 price: Price::Eur(21),
                 url: "bla".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -451,6 +575,7 @@ mod tests {
                     year: 2022,
                     price: Price::Eur(21),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -460,6 +585,7 @@ mod tests {
                     year: 1999,
                     price: Price::Eur(101),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
                 Hit {
                     search_engine: "foo".to_string(),
@@ -469,8 +595,141 @@ mod tests {
                     year: 2001,
                     price: Price::Eur(100),
                     url: "bla".to_string(),
+                    ..Default::default()
                 },
             ],
         );
     }
+
+    #[test]
+    fn sort_mileage_across_units() {
+        let hit = |mileage: Mileage| Hit {
+            mileage,
+            ..Default::default()
+        };
+        let hits = vec![
+            hit(Mileage::Km(50000)),
+            hit(Mileage::Unknown),
+            hit(Mileage::Mi(20000)),
+            hit(Mileage::Km(20000)),
+        ];
+
+        let sorter = Sort::new(SortBy::Mileage, SortOrder::Asc);
+        let result: Vec<Mileage> = sorter
+            .execute(hits)
+            .into_iter()
+            .map(|hit| hit.mileage)
+            .collect();
+
+        // 20,000 miles is about 32,187 km
+        assert_eq!(
+            result,
+            vec![
+                Mileage::Km(20000),
+                Mileage::Mi(20000),
+                Mileage::Km(50000),
+                Mileage::Unknown,
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_values_sort_last_in_either_order() {
+        let hit = |deal_score: Option<i32>, age: Option<u16>| Hit {
+            deal_score,
+            age,
+            ..Default::default()
+        };
+        let hits = vec![
+            hit(None, None),
+            hit(Some(-5), Some(3)),
+            hit(Some(12), Some(7)),
+        ];
+        let sorted = |by: SortBy, order: SortOrder| -> Vec<Hit> {
+            Sort::new(by, order).execute(hits.clone())
+        };
+
+        assert_eq!(
+            sorted(SortBy::DealScore, SortOrder::Asc),
+            vec![hits[1].clone(), hits[2].clone(), hits[0].clone()]
+        );
+        assert_eq!(
+            sorted(SortBy::DealScore, SortOrder::Desc),
+            vec![hits[2].clone(), hits[1].clone(), hits[0].clone()]
+        );
+        assert_eq!(
+            sorted(SortBy::Age, SortOrder::Asc),
+            vec![hits[1].clone(), hits[2].clone(), hits[0].clone()]
+        );
+        assert_eq!(
+            sorted(SortBy::Age, SortOrder::Desc),
+            vec![hits[2].clone(), hits[1].clone(), hits[0].clone()]
+        );
+    }
+
+    #[test]
+    fn unknown_years_sort_last_in_either_order() {
+        let hit = |year: u16| Hit {
+            year,
+            ..Default::default()
+        };
+        let hits = vec![hit(0), hit(2015), hit(2020)];
+        let sorted =
+            |order: SortOrder| -> Vec<Hit> { Sort::new(SortBy::Year, order).execute(hits.clone()) };
+
+        assert_eq!(
+            sorted(SortOrder::Asc),
+            vec![hits[1].clone(), hits[2].clone(), hits[0].clone()]
+        );
+        assert_eq!(
+            sorted(SortOrder::Desc),
+            vec![hits[2].clone(), hits[1].clone(), hits[0].clone()]
+        );
+    }
+
+    #[test]
+    fn sort_price_by_currency_then_amount() {
+        let hit = |price: Price| Hit {
+            price,
+            ..Default::default()
+        };
+        let hits = vec![
+            hit(Price::Usd(9000)),
+            hit(Price::Unknown),
+            hit(Price::Gbp(12000)),
+            hit(Price::Eur(15000)),
+            hit(Price::Gbp(8000)),
+            hit(Price::Eur(11000)),
+        ];
+        let sorted = |order: SortOrder| -> Vec<Price> {
+            Sort::new(SortBy::Price, order)
+                .execute(hits.clone())
+                .into_iter()
+                .map(|hit| hit.price)
+                .collect()
+        };
+
+        assert_eq!(
+            sorted(SortOrder::Asc),
+            vec![
+                Price::Eur(11000),
+                Price::Eur(15000),
+                Price::Gbp(8000),
+                Price::Gbp(12000),
+                Price::Usd(9000),
+                Price::Unknown,
+            ]
+        );
+        assert_eq!(
+            sorted(SortOrder::Desc),
+            vec![
+                Price::Eur(15000),
+                Price::Eur(11000),
+                Price::Gbp(12000),
+                Price::Gbp(8000),
+                Price::Usd(9000),
+                Price::Unknown,
+            ]
+        );
+    }
 }
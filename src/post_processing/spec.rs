@@ -0,0 +1,369 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::aggregate::GroupKey;
+use crate::calendar::current_year;
+use crate::hit::Hit;
+
+use super::{
+    anomaly::{AnomalyDetection, AnomalyMode},
+    derived::{DerivedFields, DERIVED_FIELDS},
+    filter::Filter,
+    limit::Limit,
+    offset::Offset,
+    sort::{Sort, SortBy, SortOrder},
+    top_per_group::TopPerGroup,
+    valuation::Valuation,
+    Action, Pipeline,
+};
+
+/// The steps that can make up a pipeline, along with a description of their arguments.
+pub const STEPS: [(&str, &str); 8] = [
+    ("anomalies", "<flag|remove>"),
+    ("derive", "none"),
+    ("valuation", "[reference file]"),
+    ("filter", "<expression>"),
+    ("sort", "<key>[:<asc|desc>]"),
+    ("offset", "<n>"),
+    ("top_per_group", "<keys>:<n>"),
+    ("limit", "<n>"),
+];
+
+/// The specification of a single pipeline step: the name of the step, and its arguments.
+/// On the command line, a step is written as its name followed by its arguments, separated by
+/// colons, e.g. `sort:price:desc`. In a pipeline file, a step is a `[[pipeline]]` table, e.g.
+///
+/// ```toml
+/// [[pipeline]]
+/// step = "sort"
+/// args = ["price", "desc"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StepSpec {
+    #[serde(rename = "step")]
+    pub name: String,
+    #[serde(default, deserialize_with = "deserialize_args")]
+    pub args: Vec<String>,
+}
+
+/// A pipeline file, consisting of an ordered list of steps.
+#[derive(Debug, Deserialize)]
+pub struct PipelineFile {
+    pub pipeline: Vec<StepSpec>,
+}
+
+/// Accept strings, numbers and booleans as step arguments, so that e.g. `args = [10]` is valid.
+fn deserialize_args<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Arg {
+        String(String),
+        Integer(i64),
+        Float(f64),
+        Boolean(bool),
+    }
+
+    let args = Vec::<Arg>::deserialize(deserializer)?;
+    Ok(args
+        .into_iter()
+        .map(|arg| match arg {
+            Arg::String(s) => s,
+            Arg::Integer(i) => i.to_string(),
+            Arg::Float(f) => f.to_string(),
+            Arg::Boolean(b) => b.to_string(),
+        })
+        .collect())
+}
+
+impl FromStr for StepSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':').map(str::trim);
+        match parts.next() {
+            Some(name) if !name.is_empty() => Ok(StepSpec {
+                name: name.to_string(),
+                args: parts.map(String::from).collect(),
+            }),
+            _ => Err(format!("Empty pipeline step: '{}'", s)),
+        }
+    }
+}
+
+/// An error in the specification of a pipeline step.
+#[derive(Debug, PartialEq)]
+pub struct SpecError {
+    /// The position of the step in the pipeline, starting at 1
+    pub position: usize,
+    pub step: String,
+    pub reason: String,
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid pipeline step {} ({}): {}",
+            self.position, self.step, self.reason
+        )
+    }
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a non-negative number, got '{}'", value))
+}
+
+impl StepSpec {
+    fn expect_args(&self, min: usize, max: usize) -> Result<(), String> {
+        let usage = STEPS
+            .iter()
+            .find(|(name, _)| *name == self.name)
+            .map_or("", |(_, usage)| usage);
+        if self.args.len() < min || self.args.len() > max {
+            return Err(format!(
+                "expected arguments {}, got {} argument(s)",
+                usage,
+                self.args.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether this step sorts or filters on one of the derived fields, which need to be derived
+    /// before it.
+    fn uses_derived_fields(&self) -> bool {
+        match (self.name.as_str(), self.args.first()) {
+            ("sort", Some(key)) => DERIVED_FIELDS.contains(&key.trim()),
+            ("filter", Some(expression)) => DERIVED_FIELDS
+                .iter()
+                .any(|key| expression.trim_start().starts_with(key)),
+            _ => false,
+        }
+    }
+
+    /// Build the action that this step specifies, validating its arguments.
+    pub fn build(&self) -> Result<Box<dyn Action>, String> {
+        match self.name.as_str() {
+            "anomalies" => {
+                self.expect_args(1, 1)?;
+                let mode = AnomalyMode::try_from(self.args[0].as_str())?;
+                Ok(Box::new(AnomalyDetection::new(mode, current_year())))
+            }
+            "derive" => {
+                self.expect_args(0, 0)?;
+                Ok(Box::new(DerivedFields::new(current_year())))
+            }
+            "valuation" => {
+                self.expect_args(0, 1)?;
+                match self.args.first() {
+                    None => Ok(Box::new(Valuation::new())),
+                    Some(path) => {
+                        let contents = std::fs::read_to_string(path)
+                            .map_err(|e| format!("unable to read {}: {}", path, e))?;
+                        let reference: Vec<Hit> = serde_json::from_str(&contents)
+                            .map_err(|e| format!("unable to parse {}: {}", path, e))?;
+                        Ok(Box::new(Valuation::with_reference(reference)))
+                    }
+                }
+            }
+            "filter" => {
+                // Filter expressions may themselves contain colons, so rejoin them
+                self.expect_args(1, usize::MAX)?;
+                Ok(Box::new(Filter::try_from(self.args.join(":").as_str())?))
+            }
+            "sort" => {
+                self.expect_args(1, 2)?;
+                let by = SortBy::try_from(self.args[0].as_str())?;
+                let order = match self.args.get(1) {
+                    None => SortOrder::Asc,
+                    Some(order) => SortOrder::try_from(order.as_str())?,
+                };
+                Ok(Box::new(Sort::new(by, order)))
+            }
+            "offset" => {
+                self.expect_args(1, 1)?;
+                Ok(Box::new(Offset::new(parse_count(&self.args[0])?)))
+            }
+            "top_per_group" => {
+                self.expect_args(2, 2)?;
+                let group_by = self.args[0]
+                    .split(',')
+                    .map(|key| GroupKey::try_from(key.trim()))
+                    .collect::<Result<Vec<GroupKey>, String>>()?;
+                Ok(Box::new(TopPerGroup::new(
+                    group_by,
+                    parse_count(&self.args[1])?,
+                )))
+            }
+            "limit" => {
+                self.expect_args(1, 1)?;
+                Ok(Box::new(Limit::new(parse_count(&self.args[0])?)))
+            }
+            _ => Err(format!(
+                "unknown step. Options are: {}",
+                STEPS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl TryFrom<&[StepSpec]> for Pipeline {
+    type Error = Vec<SpecError>;
+
+    /// Build a pipeline from the specified steps, in order. All steps are validated, and every
+    /// invalid step is reported. The derived fields are derived before the first step that sorts
+    /// or filters on them, unless a derive step already precedes it.
+    fn try_from(specs: &[StepSpec]) -> Result<Self, Self::Error> {
+        let mut actions: Vec<Box<dyn Action>> = vec![];
+        let mut errors = vec![];
+        let mut derived = false;
+
+        for (i, spec) in specs.iter().enumerate() {
+            if spec.uses_derived_fields() && !derived {
+                actions.push(Box::new(DerivedFields::new(current_year())));
+            }
+            derived |= spec.name == "derive" || spec.uses_derived_fields();
+            match spec.build() {
+                Ok(action) => actions.push(action),
+                Err(reason) => errors.push(SpecError {
+                    position: i + 1,
+                    step: spec.name.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Pipeline::from(actions))
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    fn hits() -> Vec<Hit> {
+        [
+            ("Fabia", 9000),
+            ("Octavia", 15000),
+            ("Fabia", 8000),
+            ("Octavia", 14000),
+        ]
+        .into_iter()
+        .map(|(model, price)| Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: model.to_string(),
+            mileage: Mileage::Km(1000),
+            year: 2018,
+            price: Price::Eur(price),
+            url: "bla".to_string(),
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    fn specs(steps: &[&str]) -> Vec<StepSpec> {
+        steps.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn parse_step_from_string() {
+        assert_eq!(
+            "top_per_group:model,year:3".parse::<StepSpec>(),
+            Ok(StepSpec {
+                name: "top_per_group".to_string(),
+                args: vec!["model,year".to_string(), "3".to_string()],
+            })
+        );
+        assert!("".parse::<StepSpec>().is_err());
+    }
+
+    #[test]
+    fn parse_pipeline_file() {
+        let file: PipelineFile = toml::from_str(
+            r#"
+            [[pipeline]]
+            step = "sort"
+            args = ["price", "desc"]
+
+            [[pipeline]]
+            step = "limit"
+            args = [2]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(file.pipeline, specs(&["sort:price:desc", "limit:2"]));
+    }
+
+    #[test]
+    fn steps_are_executed_in_order() {
+        let pipeline = Pipeline::try_from(specs(&["sort:price", "limit:1"]).as_slice()).unwrap();
+        let result = pipeline.execute(hits());
+        assert_eq!(result[0].price, Price::Eur(8000));
+
+        let pipeline = Pipeline::try_from(specs(&["limit:1", "sort:price"]).as_slice()).unwrap();
+        let result = pipeline.execute(hits());
+        assert_eq!(result[0].price, Price::Eur(9000));
+    }
+
+    #[test]
+    fn filter_and_top_per_group_steps() {
+        let pipeline = Pipeline::try_from(
+            specs(&[
+                "filter:price>8000",
+                "sort:price:DESC",
+                "top_per_group:model:1",
+            ])
+            .as_slice(),
+        )
+        .unwrap();
+        let result: Vec<Price> = pipeline
+            .execute(hits())
+            .into_iter()
+            .map(|h| h.price)
+            .collect();
+
+        assert_eq!(result, vec![Price::Eur(15000), Price::Eur(9000)]);
+    }
+
+    #[test]
+    fn steps_on_derived_fields_derive_them() {
+        let pipeline =
+            Pipeline::try_from(specs(&["filter:km_per_year>0", "sort:age"]).as_slice()).unwrap();
+        let result = pipeline.execute(hits());
+
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().all(|h| h.age.is_some()));
+    }
+
+    #[test]
+    fn every_invalid_step_is_reported() {
+        let errors = Pipeline::try_from(
+            specs(&["sort:colour", "limit:1", "limit", "offset:-1", "shuffle"]).as_slice(),
+        )
+        .err()
+        .unwrap();
+
+        let positions: Vec<usize> = errors.iter().map(|e| e.position).collect();
+        assert_eq!(positions, vec![1, 3, 4, 5]);
+        assert_eq!(
+            errors[1].to_string(),
+            "Invalid pipeline step 3 (limit): expected arguments <n>, got 0 argument(s)"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::aggregate::GroupKey;
+use crate::hit::Hit;
+
+use super::Action;
+
+/// An action which keeps only the first given number of Hits within each group, as identified by
+/// one or more grouping keys. The relative order of the Hits is preserved, so sorting beforehand
+/// determines which Hits are kept (e.g. the cheapest three of every model year).
+pub struct TopPerGroup {
+    group_by: Vec<GroupKey>,
+    n: usize,
+}
+
+impl TopPerGroup {
+    pub fn new(group_by: Vec<GroupKey>, n: usize) -> Self {
+        Self { group_by, n }
+    }
+}
+
+impl Action for TopPerGroup {
+    fn execute(&self, hits: Vec<Hit>) -> Vec<Hit> {
+        let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+        hits.into_iter()
+            .filter(|hit| {
+                let key = self.group_by.iter().map(|k| k.value(hit)).collect();
+                let count = seen.entry(key).or_insert(0);
+                *count += 1;
+                *count <= self.n
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Mileage, Price};
+    use crate::post_processing::sort::{Sort, SortBy, SortOrder};
+
+    use super::*;
+
+    fn hit(model: &str, year: u16, price: i32) -> Hit {
+        Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: model.to_string(),
+            mileage: Mileage::Km(1000),
+            year,
+            price: Price::Eur(price),
+            url: "bla".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn hits() -> Vec<Hit> {
+        vec![
+            hit("Fabia", 2018, 9000),
+            hit("Octavia", 2018, 15000),
+            hit("Fabia", 2018, 8000),
+            hit("Fabia", 2019, 10000),
+            hit("Fabia", 2018, 8500),
+            hit("Octavia", 2018, 14000),
+        ]
+    }
+
+    #[test]
+    fn keeps_first_n_per_group_in_order() {
+        let top = TopPerGroup::new(vec![GroupKey::Model], 2);
+        let result = top.execute(hits());
+
+        assert_eq!(
+            result,
+            vec![
+                hit("Fabia", 2018, 9000),
+                hit("Octavia", 2018, 15000),
+                hit("Fabia", 2018, 8000),
+                hit("Octavia", 2018, 14000),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_then_top_per_model_year() {
+        let sort = Sort::new(SortBy::Price, SortOrder::Asc);
+        let top = TopPerGroup::new(vec![GroupKey::Model, GroupKey::Year], 1);
+        let result = top.execute(sort.execute(hits()));
+
+        assert_eq!(
+            result,
+            vec![
+                hit("Fabia", 2018, 8000),
+                hit("Fabia", 2019, 10000),
+                hit("Octavia", 2018, 14000),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_per_model_then_sort() {
+        let top = TopPerGroup::new(vec![GroupKey::Model], 1);
+        let sort = Sort::new(SortBy::Price, SortOrder::Asc);
+        let result = sort.execute(top.execute(hits()));
+
+        assert_eq!(
+            result,
+            vec![hit("Fabia", 2018, 9000), hit("Octavia", 2018, 15000)]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use crate::hit::{Hit, Price};
+
+use super::Action;
+
+/// The minimum number of comparable hits needed before an expected price is estimated.
+const MIN_SAMPLES: usize = 3;
+
+/// A valuation action, which estimates a fair price for every Hit and scores how good a deal it
+/// is. A price model is fitted per make, model and currency, using the year of registration and
+/// mileage as predictors. The model is fitted on a reference set of hits if one is provided, and
+/// on the hits being processed otherwise.
+#[derive(Default)]
+pub struct Valuation {
+    reference: Option<Vec<Hit>>,
+}
+
+impl Valuation {
+    /// Fit the price models on the hits that are being processed.
+    pub fn new() -> Self {
+        Self { reference: None }
+    }
+
+    /// Fit the price models on the provided hits, e.g. previously stored search results.
+    pub fn with_reference(reference: Vec<Hit>) -> Self {
+        Self {
+            reference: Some(reference),
+        }
+    }
+}
+
+type ModelKey = (String, String, &'static str);
+
+fn model_key(hit: &Hit) -> Option<ModelKey> {
+    hit.price
+        .currency()
+        .map(|currency| (hit.make.to_lowercase(), hit.model.to_lowercase(), currency))
+}
+
+fn predictors(hit: &Hit) -> Option<[f64; 2]> {
+    match (hit.year, hit.mileage.kms()) {
+        (0, _) | (_, None) => None,
+        (year, Some(kms)) => Some([year as f64, kms as f64]),
+    }
+}
+
+/// A linear price model: `price = intercept + sum(slope_i * (x_i - mean_i))`, where each x_i is
+/// one of the predictors selected by `columns`.
+#[derive(Debug)]
+struct PriceModel {
+    columns: Vec<usize>,
+    means: Vec<f64>,
+    intercept: f64,
+    slopes: Vec<f64>,
+}
+
+impl PriceModel {
+    /// Fit a model on (predictors, price) observations. The full model using both year and mileage
+    /// is preferred; if it cannot be fitted (e.g. because every observation has the same year), a
+    /// model using a single predictor is tried, falling back to the mean price.
+    fn fit(observations: &[([f64; 2], f64)]) -> Option<Self> {
+        if observations.len() < MIN_SAMPLES {
+            return None;
+        }
+
+        [vec![0, 1], vec![1], vec![0], vec![]]
+            .into_iter()
+            .filter(|columns| observations.len() > columns.len() + 1)
+            .find_map(|columns| Self::fit_columns(observations, columns))
+    }
+
+    fn fit_columns(observations: &[([f64; 2], f64)], columns: Vec<usize>) -> Option<Self> {
+        let n = observations.len() as f64;
+        let means: Vec<f64> = columns
+            .iter()
+            .map(|&c| observations.iter().map(|(x, _)| x[c]).sum::<f64>() / n)
+            .collect();
+        let intercept = observations.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+        // Normal equations on centred predictors: (X'X) b = X'y
+        let k = columns.len();
+        let mut xtx = vec![vec![0.0; k]; k];
+        let mut xty = vec![0.0; k];
+        for (x, y) in observations {
+            let centred: Vec<f64> = columns.iter().zip(&means).map(|(&c, m)| x[c] - m).collect();
+            for i in 0..k {
+                xty[i] += centred[i] * (y - intercept);
+                for j in 0..k {
+                    xtx[i][j] += centred[i] * centred[j];
+                }
+            }
+        }
+
+        let slopes = solve(xtx, xty)?;
+        Some(Self {
+            columns,
+            means,
+            intercept,
+            slopes,
+        })
+    }
+
+    fn predict(&self, x: &[f64; 2]) -> f64 {
+        self.columns
+            .iter()
+            .zip(&self.means)
+            .zip(&self.slopes)
+            .fold(self.intercept, |acc, ((&c, mean), slope)| {
+                acc + slope * (x[c] - mean)
+            })
+    }
+}
+
+/// Solve a linear system using Gaussian elimination with partial pivoting. Returns None if the
+/// system is singular.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let k = b.len();
+    for col in 0..k {
+        let pivot = (col..k).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot_value;
+            }
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; k];
+    for row in (0..k).rev() {
+        let sum: f64 = (row + 1..k).map(|c| a[row][c] * x[c]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+fn with_amount(price: &Price, amount: i32) -> Price {
+    match price {
+        Price::Eur(_) => Price::Eur(amount),
+        Price::Usd(_) => Price::Usd(amount),
+        Price::Gbp(_) => Price::Gbp(amount),
+        Price::Unknown => Price::Unknown,
+    }
+}
+
+impl Action for Valuation {
+    fn execute(&self, mut hits: Vec<Hit>) -> Vec<Hit> {
+        let reference = self.reference.as_ref().unwrap_or(&hits);
+
+        let mut observations: HashMap<ModelKey, Vec<([f64; 2], f64)>> = HashMap::new();
+        for hit in reference {
+            if let (Some(key), Some(x), Some(price)) =
+                (model_key(hit), predictors(hit), hit.price.amount())
+            {
+                observations.entry(key).or_default().push((x, price as f64));
+            }
+        }
+
+        let models: HashMap<ModelKey, PriceModel> = observations
+            .into_iter()
+            .filter_map(|(key, obs)| PriceModel::fit(&obs).map(|model| (key, model)))
+            .collect();
+
+        for hit in hits.iter_mut() {
+            let estimate = model_key(hit)
+                .and_then(|key| models.get(&key))
+                .zip(predictors(hit))
+                .map(|(model, x)| model.predict(&x).round() as i32)
+                .filter(|expected| *expected > 0);
+
+            if let (Some(expected), Some(price)) = (estimate, hit.price.amount()) {
+                hit.expected_price = Some(with_amount(&hit.price, expected));
+                hit.residual = Some(price - expected);
+                hit.deal_score =
+                    Some(((expected - price) as f64 / expected as f64 * 100.0).round() as i32);
+            }
+        }
+
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::Mileage;
+
+    use super::*;
+
+    /// A synthetic market where every car loses 1000 per year of age and 5 per 100 km.
+    fn market_price(year: u16, kms: i32) -> i32 {
+        20000 - 1000 * (2023 - year as i32) - kms / 20
+    }
+
+    fn hit(model: &str, year: u16, kms: i32, price: Price) -> Hit {
+        Hit {
+            search_engine: "foo".to_string(),
+            make: "Skoda".to_string(),
+            model: model.to_string(),
+            mileage: Mileage::Km(kms),
+            year,
+            price,
+            url: "bla".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn synthetic_market() -> Vec<Hit> {
+        vec![
+            hit(
+                "Fabia",
+                2015,
+                120000,
+                Price::Eur(market_price(2015, 120000)),
+            ),
+            hit("Fabia", 2017, 60000, Price::Eur(market_price(2017, 60000))),
+            hit("Fabia", 2018, 90000, Price::Eur(market_price(2018, 90000))),
+            hit("Fabia", 2020, 30000, Price::Eur(market_price(2020, 30000))),
+            hit("Fabia", 2021, 45000, Price::Eur(market_price(2021, 45000))),
+        ]
+    }
+
+    #[test]
+    fn exact_market_has_zero_residuals() {
+        let result = Valuation::new().execute(synthetic_market());
+
+        for hit in result {
+            let price = hit.price.amount().unwrap();
+            assert_eq!(hit.expected_price, Some(Price::Eur(price)));
+            assert_eq!(hit.residual, Some(0));
+            assert_eq!(hit.deal_score, Some(0));
+        }
+    }
+
+    #[test]
+    fn reference_identifies_underpriced_car() {
+        let candidates = vec![
+            hit("Fabia", 2019, 50000, Price::Eur(10000)),
+            hit("Fabia", 2019, 50000, Price::Eur(18000)),
+        ];
+        let result = Valuation::with_reference(synthetic_market()).execute(candidates);
+
+        let expected = market_price(2019, 50000);
+        assert_eq!(result[0].expected_price, Some(Price::Eur(expected)));
+        assert_eq!(result[0].residual, Some(10000 - expected));
+        assert_eq!(result[0].deal_score, Some(26));
+        assert_eq!(result[1].residual, Some(18000 - expected));
+        assert_eq!(result[1].deal_score, Some(-33));
+    }
+
+    #[test]
+    fn underpriced_car_within_result_set() {
+        let mut hits = synthetic_market();
+        hits.extend([
+            hit(
+                "Fabia",
+                2016,
+                100000,
+                Price::Eur(market_price(2016, 100000)),
+            ),
+            hit("Fabia", 2019, 40000, Price::Eur(market_price(2019, 40000))),
+            hit("Fabia", 2019, 40000, Price::Eur(6000)),
+        ]);
+        let result = Valuation::new().execute(hits);
+
+        let best = result.iter().max_by_key(|h| h.deal_score).unwrap();
+        assert_eq!(best.price, Price::Eur(6000));
+        assert!(best.deal_score.unwrap() > 40);
+    }
+
+    #[test]
+    fn models_are_fitted_per_make_and_model() {
+        let mut hits = synthetic_market();
+        hits.extend([
+            hit("Octavia", 2019, 40000, Price::Eur(30000)),
+            hit("Octavia", 2019, 40000, Price::Eur(31000)),
+            hit("Octavia", 2019, 40000, Price::Eur(32000)),
+        ]);
+        let result = Valuation::new().execute(hits);
+
+        // All Octavias share the same year and mileage, so only a mean price can be fitted.
+        for hit in result.iter().filter(|h| h.model == "Octavia") {
+            assert_eq!(hit.expected_price, Some(Price::Eur(31000)));
+        }
+        for hit in result.iter().filter(|h| h.model == "Fabia") {
+            assert_eq!(hit.residual, Some(0));
+        }
+    }
+
+    #[test]
+    fn too_few_samples_leaves_hits_unscored() {
+        let hits = vec![
+            hit("Fabia", 2019, 40000, Price::Eur(12000)),
+            hit("Fabia", 2018, 50000, Price::Eur(11000)),
+        ];
+        let result = Valuation::new().execute(hits);
+
+        for hit in result {
+            assert_eq!(hit.expected_price, None);
+            assert_eq!(hit.residual, None);
+            assert_eq!(hit.deal_score, None);
+        }
+    }
+
+    #[test]
+    fn currencies_are_not_mixed() {
+        let mut hits = synthetic_market();
+        hits.push(hit("Fabia", 2019, 50000, Price::Gbp(9000)));
+        hits.push(hit("Fabia", 2019, 50000, Price::Unknown));
+        let result = Valuation::new().execute(hits);
+
+        assert_eq!(result[5].expected_price, None);
+        assert_eq!(result[6].expected_price, None);
+    }
+}
@@ -1,45 +1,166 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
 use crate::args::Args;
+use crate::hit::Hit;
+
+/// A make and (optionally) a model of car to search for.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MakeModel {
+    pub make: Option<String>,
+    pub model: Option<String>,
+}
+
+impl MakeModel {
+    /// Whether the hit is of this make and model. Comparison is case-insensitive; a missing make
+    /// or model matches any.
+    pub fn matches(&self, hit: &Hit) -> bool {
+        let matches = |wanted: &Option<String>, actual: &str| {
+            wanted
+                .as_ref()
+                .is_none_or(|w| w.to_lowercase() == actual.to_lowercase())
+        };
+        matches(&self.make, &hit.make) && matches(&self.model, &hit.model)
+    }
+
+    /// A label for this make and model, with which matching hits are tagged.
+    pub fn label(&self) -> String {
+        [self.make.as_deref(), self.model.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+impl FromStr for MakeModel {
+    type Err = String;
+
+    /// Parse a make and optional model separated by a colon, e.g. `Volkswagen:Golf` or `Ford`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (make, model) = match s.split_once(':') {
+            Some((make, model)) => (make.trim(), Some(model.trim())),
+            None => (s.trim(), None),
+        };
+        if make.is_empty() {
+            return Err(format!("Missing make in '{}'", s));
+        }
+        Ok(MakeModel {
+            make: Some(make.to_string()),
+            model: model.filter(|m| !m.is_empty()).map(String::from),
+        })
+    }
+}
 
 /// The query object that is the input to the root engine, and to each of the individual
 /// search engines.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Query {
-    pub make: Option<String>,
-    pub model: Option<String>,
+    /// The makes and models to search for; any make and model if empty
+    pub make_models: Vec<MakeModel>,
     pub min_price: Option<String>,
     pub max_price: Option<String>,
     pub min_year: Option<String>,
     pub max_year: Option<String>,
     pub min_kms: Option<String>,
     pub max_kms: Option<String>,
+    /// The postcode (or ZIP code) to search around, for search engines that search by distance
+    pub postcode: Option<String>,
+    /// The distance in km around the postcode to search within
+    pub radius: Option<String>,
+    /// Keywords (or phrases) that must all appear in the title or description of a listing
+    pub keywords: Vec<String>,
+    /// Keywords (or phrases) that must not appear in the title or description of a listing
+    pub exclude_keywords: Vec<String>,
+    /// The currency of the listings to keep, as an ISO code; any currency if None
+    pub currency: Option<String>,
 }
 
-impl Default for Query {
-    fn default() -> Self {
-        Self {
-            make: None,
-            model: None,
-            min_price: None,
-            max_price: None,
-            min_year: None,
-            max_year: None,
-            min_kms: None,
-            max_kms: None,
-        }
+impl Query {
+    /// The label of the first make and model in the query that the hit matches, if any.
+    pub fn matched_label(&self, hit: &Hit) -> Option<String> {
+        self.make_models
+            .iter()
+            .find(|make_model| make_model.matches(hit))
+            .map(MakeModel::label)
     }
 }
 
 impl From<&Args> for Query {
     fn from(args: &Args) -> Self {
+        let make_models = match &args.vehicle {
+            Some(vehicles) => vehicles.clone(),
+            None if args.make.is_some() || args.model.is_some() => vec![MakeModel {
+                make: args.make.clone(),
+                model: args.model.clone(),
+            }],
+            None => vec![],
+        };
+
         Self {
-            make: args.make.clone(),
-            model: args.model.clone(),
+            make_models,
             min_price: args.min_price.clone(),
             max_price: args.max_price.clone(),
             min_year: args.min_year.clone(),
             max_year: args.max_year.clone(),
             min_kms: args.min_kms.clone(),
             max_kms: args.max_kms.clone(),
+            postcode: args.postcode.clone(),
+            radius: args.radius.clone(),
+            keywords: args.keyword.clone().unwrap_or_default(),
+            exclude_keywords: args.exclude_keyword.clone().unwrap_or_default(),
+            currency: args.currency.as_ref().map(|c| c.to_uppercase()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(make: &str, model: &str) -> Hit {
+        Hit {
+            make: make.to_string(),
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_make_model() {
+        assert_eq!(
+            "Volkswagen:Golf".parse(),
+            Ok(MakeModel {
+                make: Some("Volkswagen".to_string()),
+                model: Some("Golf".to_string()),
+            })
+        );
+        assert_eq!(
+            "Ford".parse(),
+            Ok(MakeModel {
+                make: Some("Ford".to_string()),
+                model: None,
+            })
+        );
+        assert!(":Golf".parse::<MakeModel>().is_err());
+    }
+
+    #[test]
+    fn matched_label() {
+        let query = Query {
+            make_models: vec!["Volkswagen:Golf".parse().unwrap(), "ford".parse().unwrap()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.matched_label(&hit("VOLKSWAGEN", "golf")),
+            Some("Volkswagen Golf".to_string())
+        );
+        assert_eq!(
+            query.matched_label(&hit("Ford", "Focus")),
+            Some("ford".to_string())
+        );
+        assert_eq!(query.matched_label(&hit("Volkswagen", "Polo")), None);
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::Error;
+
+use reqwest::Url;
+
+use super::scraping::{Listing, Rules, Site};
+use super::slug;
+use crate::query::{MakeModel, Query};
+
+const ROOT: &str = "https://www.adverts.ie";
+
+/// Adverts, a classifieds site on which cars are mostly sold by private sellers in Ireland. Its
+/// search results are scraped; prices are in EUR, and mileages in the unit the seller listed them
+/// in.
+pub struct AdvertsIE {
+    root: Url,
+    rules: Rules,
+}
+
+impl AdvertsIE {
+    pub fn new() -> Self {
+        Self::at(ROOT)
+    }
+
+    /// Adverts, served from the provided root URL.
+    fn at(root: &str) -> Self {
+        Self {
+            root: Url::parse(root).expect("invalid root URL"),
+            rules: Rules::new("div.sr-grid-cell", "EUR")
+                .text("title", ".title a")
+                .attribute("url", ".title a", "href")
+                .text("make", ".info .make")
+                .text("model", ".info .model")
+                .text("year", ".info .year")
+                .text("mileage", ".info .mileage")
+                .text("fuel", ".info .fuel")
+                .text("price", ".price")
+                .next(".pagination a.next"),
+        }
+    }
+}
+
+impl Default for AdvertsIE {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Site for AdvertsIE {
+    fn name(&self) -> &str {
+        "adverts_ie"
+    }
+
+    fn search_engine(&self) -> &str {
+        "adverts.ie"
+    }
+
+    fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+        let path = match (&make_model.make, &make_model.model) {
+            (Some(make), Some(model)) => format!("/car/{}/{}/", slug(make), slug(model)),
+            (Some(make), None) => format!("/car/{}/", slug(make)),
+            (None, _) => "/car/".to_string(),
+        };
+        let keywords = query.keywords.join(" ");
+        let params = [
+            ("price_min", query.min_price.clone()),
+            ("price_max", query.max_price.clone()),
+            ("year_min", query.min_year.clone()),
+            ("year_max", query.max_year.clone()),
+            ("mileage_min", query.min_kms.clone()),
+            ("mileage_max", query.max_kms.clone()),
+            ("q", Some(keywords).filter(|k| !k.is_empty())),
+        ];
+        let params: Vec<(&str, &String)> = params
+            .iter()
+            .filter_map(|(name, value)| Some((*name, value.as_ref()?)))
+            .collect();
+
+        let mut url = self.root.join(&path).map_err(Error::other)?;
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        Ok(url)
+    }
+
+    fn supports_keywords(&self) -> bool {
+        true
+    }
+
+    /// Adverts places spotlight ads of other makes at the top of the search results, which are
+    /// left out.
+    fn make_model(&self, listing: &Listing, searched: &MakeModel) -> Option<(String, String)> {
+        listing.of_make(searched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Hit, Mileage, Price};
+    use crate::search::scraping::Scraper;
+    use crate::search::testing::{serve, Received};
+    use crate::search::Searcher;
+
+    use super::*;
+
+    fn page(request: &Received) -> Option<String> {
+        match request.target.split_once("page=") {
+            None => Some(include_str!("fixtures/adverts_ie/page_1.html")),
+            Some((_, "2")) => Some(include_str!("fixtures/adverts_ie/page_2.html")),
+            Some(_) => None,
+        }
+        .map(String::from)
+    }
+
+    #[test]
+    fn maps_the_query_to_search_parameters() {
+        let site = AdvertsIE::new();
+        let query = Query {
+            max_price: Some("8000".to_string()),
+            min_year: Some("2012".to_string()),
+            min_kms: Some("50000".to_string()),
+            keywords: vec!["nct".to_string()],
+            ..Default::default()
+        };
+        let make_model = "Land Rover:Range Rover Sport".parse().unwrap();
+
+        assert_eq!(
+            site.search_url(&query, &make_model).unwrap().as_str(),
+            "https://www.adverts.ie/car/land-rover/range-rover-sport/?price_max=8000\
+             &year_min=2012&mileage_min=50000&q=nct"
+        );
+        assert_eq!(
+            site.search_url(&Query::default(), &MakeModel::default())
+                .unwrap()
+                .as_str(),
+            "https://www.adverts.ie/car/"
+        );
+    }
+
+    #[tokio::test]
+    async fn searches_every_page_keeping_the_make_searched_for() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(AdvertsIE::at(&base));
+        let query = Query {
+            make_models: vec!["Ford:Focus".parse().unwrap()],
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(targets, vec!["/car/ford/focus/", "/car/ford/focus/?page=2"]);
+        // A spotlight ad for a BMW on the first page is not what was searched for
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "adverts.ie".to_string(),
+                make: "Ford".to_string(),
+                model: "Focus".to_string(),
+                mileage: Mileage::Km(112000),
+                year: 2016,
+                price: Price::Eur(8450),
+                url: format!("{}/car/ford/focus/1-0-ecoboost-zetec/28451234", base),
+                fuel: Some("Petrol".to_string()),
+                title: Some("Ford Focus 1.0 EcoBoost Zetec, NCT 06/25".to_string()),
+                matched: Some("Ford Focus".to_string()),
+                ..Default::default()
+            }
+        );
+        // Sellers list mileages in the unit of their choice
+        assert_eq!(hits[1].mileage, Mileage::Mi(74500));
+        assert_eq!(hits[2].price, Price::Unknown);
+    }
+}
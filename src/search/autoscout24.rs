@@ -0,0 +1,313 @@
+use std::io::Error;
+
+use reqwest::Url;
+
+use super::scraping::{covering, Listing, Rules, Site};
+use super::slug;
+use crate::query::{MakeModel, Query};
+use crate::taxonomy::split_prefix;
+
+/// The distances (in km) that AutoScout24 searches within; anything further is nationwide.
+const RADIUSES: [i32; 8] = [10, 20, 50, 100, 150, 200, 300, 500];
+
+/// A country that AutoScout24 has a site for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Country {
+    /// The ISO 3166 code of the country
+    pub code: &'static str,
+    pub name: &'static str,
+    /// The top-level domain of the country's site
+    domain: &'static str,
+    /// The code by which AutoScout24 filters on the country of listings
+    filter: &'static str,
+}
+
+/// The countries whose sites are searched, each as a search engine of its own.
+pub const COUNTRIES: [Country; 8] = [
+    Country {
+        code: "AT",
+        name: "Austria",
+        domain: "at",
+        filter: "A",
+    },
+    Country {
+        code: "BE",
+        name: "Belgium",
+        domain: "be",
+        filter: "B",
+    },
+    Country {
+        code: "DE",
+        name: "Germany",
+        domain: "de",
+        filter: "D",
+    },
+    Country {
+        code: "ES",
+        name: "Spain",
+        domain: "es",
+        filter: "E",
+    },
+    Country {
+        code: "FR",
+        name: "France",
+        domain: "fr",
+        filter: "F",
+    },
+    Country {
+        code: "IT",
+        name: "Italy",
+        domain: "it",
+        filter: "I",
+    },
+    Country {
+        code: "LU",
+        name: "Luxembourg",
+        domain: "lu",
+        filter: "L",
+    },
+    Country {
+        code: "NL",
+        name: "Netherlands",
+        domain: "nl",
+        filter: "NL",
+    },
+];
+
+/// AutoScout24, a marketplace for used cars across Europe with a site per country, which is
+/// searched for the listings in that country. Its search results are scraped; prices are in EUR.
+///
+/// AutoScout24 identifies makes and models by slugs such as `land-rover` or `3er-(alle)`. These
+/// are derived from their names, unless the taxonomy has one (as `autoscout24`).
+pub struct AutoScout24 {
+    name: String,
+    search_engine: String,
+    country: Country,
+    root: Url,
+    rules: Rules,
+}
+
+impl AutoScout24 {
+    pub fn new(country: Country) -> Self {
+        Self::at(
+            country,
+            &format!("https://www.autoscout24.{}", country.domain),
+        )
+    }
+
+    /// AutoScout24 for a country, served from the provided root URL.
+    fn at(country: Country, root: &str) -> Self {
+        Self {
+            name: format!("autoscout24_{}", country.code.to_lowercase()),
+            search_engine: format!("autoscout24.{}", country.domain),
+            country,
+            root: Url::parse(root).expect("invalid root URL"),
+            // Numbers are kept in attributes, which unlike the text do not depend on the language
+            // of the site
+            rules: Rules::new("article[data-guid]", "EUR")
+                .listing_attribute("make", "data-make")
+                .listing_attribute("model", "data-model")
+                .listing_attribute("price", "data-price")
+                .listing_attribute("mileage", "data-mileage")
+                .listing_attribute("year", "data-first-registration")
+                .text("title", "h2")
+                .attribute("url", "a[href]", "href")
+                .text("fuel", r#"[data-testid="VehicleDetails-gas_pump"]"#),
+        }
+    }
+}
+
+/// The part of a title that spells a slug, and the rest, if the title starts with it.
+fn spelled<'a>(title: &'a str, slug: &str) -> Option<(&'a str, &'a str)> {
+    split_prefix(title, slug).or_else(|| split_prefix(title, &slug.replace('-', " ")))
+}
+
+impl Site for AutoScout24 {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn search_engine(&self) -> &str {
+        &self.search_engine
+    }
+
+    fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+        let path = match (&make_model.make, &make_model.model) {
+            (Some(make), Some(model)) => format!("/lst/{}/{}", slug(make), slug(model)),
+            (Some(make), None) => format!("/lst/{}", slug(make)),
+            (None, _) => "/lst".to_string(),
+        };
+        let radius = query
+            .radius
+            .as_deref()
+            .and_then(|kms| covering(&RADIUSES, kms.trim().parse().ok()?));
+        let params = [
+            ("atype", Some("C".to_string())),
+            ("cy", Some(self.country.filter.to_string())),
+            ("ustate", Some("N,U".to_string())),
+            ("pricefrom", query.min_price.clone()),
+            ("priceto", query.max_price.clone()),
+            ("fregfrom", query.min_year.clone()),
+            ("fregto", query.max_year.clone()),
+            ("kmfrom", query.min_kms.clone()),
+            ("kmto", query.max_kms.clone()),
+            ("zip", query.postcode.clone()),
+            ("zipr", radius.map(|radius| radius.to_string())),
+        ];
+
+        let mut url = self.root.join(&path).map_err(Error::other)?;
+        url.query_pairs_mut().extend_pairs(
+            params
+                .iter()
+                .filter_map(|(name, value)| Some((name, value.as_ref()?))),
+        );
+        Ok(url)
+    }
+
+    /// Listings have the slugs of their make and model, which are spelled as in the title where
+    /// it has them.
+    fn make_model(&self, listing: &Listing, _searched: &MakeModel) -> Option<(String, String)> {
+        let (make, model) = (listing.get("make")?, listing.get("model")?);
+        let title = listing.get("title").unwrap_or_default();
+        let (make, rest) = spelled(title, make).unwrap_or((make, ""));
+        let model = spelled(rest, model).map_or(model, |(model, _)| model);
+        Some((make.to_string(), model.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Hit, Mileage, Price};
+    use crate::search::scraping::Scraper;
+    use crate::search::testing::{serve, Received};
+    use crate::search::Searcher;
+    use crate::taxonomy::Taxonomy;
+
+    use super::*;
+
+    fn country(code: &str) -> Country {
+        COUNTRIES.into_iter().find(|c| c.code == code).unwrap()
+    }
+
+    fn page(request: &Received) -> Option<String> {
+        match request.target.split_once("&page=") {
+            None => Some(include_str!("fixtures/autoscout24/page_1.html")),
+            Some((_, "2")) => Some(include_str!("fixtures/autoscout24/page_2.html")),
+            Some(_) => None,
+        }
+        .map(String::from)
+    }
+
+    #[test]
+    fn maps_the_query_to_search_parameters_of_the_country() {
+        let site = AutoScout24::new(country("IT"));
+        let query = Query {
+            make_models: vec!["BMW:3er".parse().unwrap()],
+            min_price: Some("10000".to_string()),
+            max_kms: Some("120000".to_string()),
+            postcode: Some("20121".to_string()),
+            radius: Some("40".to_string()),
+            ..Default::default()
+        };
+        let query = Taxonomy::bundled().query_for(site.name(), &query);
+
+        assert_eq!(site.name(), "autoscout24_it");
+        assert_eq!(site.search_engine(), "autoscout24.it");
+        assert_eq!(
+            site.search_url(&query, &query.make_models[0])
+                .unwrap()
+                .as_str(),
+            "https://www.autoscout24.it/lst/bmw/3er-(alle)?atype=C&cy=I&ustate=N%2CU\
+             &pricefrom=10000&kmto=120000&zip=20121&zipr=50"
+        );
+        assert_eq!(
+            site.search_url(&Query::default(), &"Land Rover".parse().unwrap())
+                .unwrap()
+                .path(),
+            "/lst/land-rover"
+        );
+    }
+
+    #[test]
+    fn spells_makes_and_models_as_the_title_does() {
+        let site = AutoScout24::new(country("DE"));
+        let listing = |make: &str, model: &str, title: &str| Listing {
+            fields: [("make", make), ("model", model), ("title", title)]
+                .into_iter()
+                .map(|(field, value)| (field, value.to_string()))
+                .collect(),
+        };
+        let make_model = |listing: &Listing| site.make_model(listing, &MakeModel::default());
+        let pair = |make: &str, model: &str| Some((make.to_string(), model.to_string()));
+
+        assert_eq!(
+            make_model(&listing(
+                "land-rover",
+                "range-rover-sport",
+                "Land Rover Range Rover Sport HSE"
+            )),
+            pair("Land Rover", "Range Rover Sport")
+        );
+        assert_eq!(
+            make_model(&listing(
+                "mercedes-benz",
+                "c-220",
+                "Mercedes-Benz C 220 d T-Modell"
+            )),
+            pair("Mercedes-Benz", "C 220")
+        );
+        // Without a title that spells them, slugs are kept
+        assert_eq!(make_model(&listing("bmw", "320", "")), pair("bmw", "320"));
+    }
+
+    #[tokio::test]
+    async fn searches_every_page() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(AutoScout24::at(country("DE"), &base));
+        let query = Query {
+            make_models: vec!["Volkswagen:Golf".parse().unwrap()],
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/lst/volkswagen/golf?atype=C&cy=D&ustate=N%2CU",
+                "/lst/volkswagen/golf?atype=C&cy=D&ustate=N%2CU&page=2",
+            ]
+        );
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "autoscout24.de".to_string(),
+                make: "Volkswagen".to_string(),
+                model: "Golf".to_string(),
+                mileage: Mileage::Km(62000),
+                year: 2019,
+                price: Price::Eur(18490),
+                url: format!(
+                    "{}/angebote/volkswagen-golf-1-5-tsi-highline-benzin-8a3c1f20",
+                    base
+                ),
+                fuel: Some("Benzin".to_string()),
+                title: Some("Volkswagen Golf 1.5 TSI Highline".to_string()),
+                matched: Some("Volkswagen Golf".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(hits[2].price, Price::Eur(9990));
+    }
+}
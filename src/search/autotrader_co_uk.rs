@@ -0,0 +1,254 @@
+use std::io::Error;
+
+use reqwest::Url;
+
+use super::parsing::{miles, MileageUnit};
+use super::scraping::{covering, split_title, Listing, Rules, Site};
+use crate::query::{MakeModel, Query};
+
+const ROOT: &str = "https://www.autotrader.co.uk";
+
+/// The distances (in miles) that Autotrader searches within; anything further is national.
+const RADIUSES: [i32; 18] = [
+    1, 5, 10, 15, 20, 25, 30, 35, 40, 45, 50, 55, 60, 70, 80, 90, 100, 200,
+];
+
+/// Autotrader, the largest marketplace for used cars in the UK. Its search results are scraped;
+/// prices are in GBP and mileages in miles.
+pub struct AutotraderUK {
+    root: Url,
+    rules: Rules,
+}
+
+impl AutotraderUK {
+    pub fn new() -> Self {
+        Self::at(ROOT)
+    }
+
+    /// Autotrader, served from the provided root URL.
+    fn at(root: &str) -> Self {
+        let spec = |n: usize| {
+            format!(
+                r#"ul[data-testid="search-listing-specs"] li:nth-child({})"#,
+                n
+            )
+        };
+        Self {
+            root: Url::parse(root).expect("invalid root URL"),
+            rules: Rules::new(r#"li[data-testid="search-listing"]"#, "GBP")
+                .text("title", r#"a[data-testid="search-listing-title"] h3"#)
+                .text("description", r#"[data-testid="search-listing-subtitle"]"#)
+                .attribute("url", r#"a[data-testid="search-listing-title"]"#, "href")
+                .text("price", r#"[data-testid="search-listing-price"]"#)
+                .text("year", &spec(1))
+                .text("mileage", &spec(3))
+                .text("fuel", &spec(6))
+                .next(r#"a[data-testid="pagination-next"]"#)
+                .mileage_unit(MileageUnit::Mi),
+        }
+    }
+}
+
+impl Default for AutotraderUK {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The smallest of Autotrader's distances that covers the radius in km, if any.
+fn radius(kms: &str) -> Option<i32> {
+    covering(&RADIUSES, miles(kms)? as f64)
+}
+
+impl Site for AutotraderUK {
+    fn name(&self) -> &str {
+        "autotrader_co_uk"
+    }
+
+    fn search_engine(&self) -> &str {
+        "autotrader.co.uk"
+    }
+
+    fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+        let mut url = self.root.join("/car-search").map_err(Error::other)?;
+        let keywords = query.keywords.join(" ");
+        let radius = query.radius.as_deref().and_then(radius);
+        let params = [
+            ("postcode", query.postcode.clone()),
+            ("radius", radius.map(|radius| radius.to_string())),
+            ("make", make_model.make.clone()),
+            ("model", make_model.model.clone()),
+            ("price-from", query.min_price.clone()),
+            ("price-to", query.max_price.clone()),
+            ("year-from", query.min_year.clone()),
+            ("year-to", query.max_year.clone()),
+            (
+                "minimum-mileage",
+                query
+                    .min_kms
+                    .as_deref()
+                    .and_then(miles)
+                    .map(|m| m.to_string()),
+            ),
+            (
+                "maximum-mileage",
+                query
+                    .max_kms
+                    .as_deref()
+                    .and_then(miles)
+                    .map(|m| m.to_string()),
+            ),
+            ("keywords", Some(keywords).filter(|k| !k.is_empty())),
+        ];
+        let params: Vec<(&str, &String)> = params
+            .iter()
+            .filter_map(|(name, value)| Some((*name, value.as_ref()?)))
+            .collect();
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        Ok(url)
+    }
+
+    fn supports_keywords(&self) -> bool {
+        true
+    }
+
+    /// Autotrader only lists the make and model together, as the title of a listing.
+    fn make_model(&self, listing: &Listing, searched: &MakeModel) -> Option<(String, String)> {
+        let (make, model) = split_title(listing.get("title")?, searched.make.as_deref())?;
+        Some((make.to_string(), model.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Hit, Mileage, Price};
+    use crate::search::scraping::Scraper;
+    use crate::search::testing::{serve, Received};
+    use crate::search::Searcher;
+
+    use super::*;
+
+    fn page(request: &Received) -> Option<String> {
+        match request.target.split_once("&page=") {
+            None => Some(include_str!("fixtures/autotrader_co_uk/page_1.html")),
+            Some((_, "2")) => Some(include_str!("fixtures/autotrader_co_uk/page_2.html")),
+            Some(_) => None,
+        }
+        .map(String::from)
+    }
+
+    #[test]
+    fn maps_the_query_to_search_parameters() {
+        let site = AutotraderUK::new();
+        let query = Query {
+            min_price: Some("2000".to_string()),
+            max_price: Some("10000".to_string()),
+            min_year: Some("2015".to_string()),
+            max_kms: Some("100000".to_string()),
+            postcode: Some("LS1 4AP".to_string()),
+            radius: Some("50".to_string()),
+            keywords: vec!["full service history".to_string()],
+            ..Default::default()
+        };
+        let make_model = "Land Rover:Discovery Sport".parse().unwrap();
+
+        assert_eq!(
+            site.search_url(&query, &make_model).unwrap().as_str(),
+            "https://www.autotrader.co.uk/car-search?postcode=LS1+4AP&radius=35&make=Land+Rover\
+             &model=Discovery+Sport&price-from=2000&price-to=10000&year-from=2015\
+             &maximum-mileage=62137&keywords=full+service+history"
+        );
+        assert_eq!(
+            site.search_url(&Query::default(), &MakeModel::default())
+                .unwrap()
+                .as_str(),
+            "https://www.autotrader.co.uk/car-search"
+        );
+    }
+
+    #[test]
+    fn radiuses_are_rounded_up_to_those_autotrader_offers() {
+        assert_eq!(radius("1"), Some(1));
+        assert_eq!(radius("30"), Some(20));
+        assert_eq!(radius("300"), Some(200));
+        // Further than Autotrader's largest distance, which is national
+        assert_eq!(radius("500"), None);
+        assert_eq!(radius("far"), None);
+    }
+
+    #[test]
+    fn splits_titles_into_make_and_model() {
+        let site = AutotraderUK::new();
+        let listing = |title: &str| Listing {
+            fields: [("title", title.to_string())].into_iter().collect(),
+        };
+        let split = |title: &str, searched: &str| {
+            site.make_model(&listing(title), &searched.parse().unwrap_or_default())
+        };
+        let pair = |make: &str, model: &str| Some((make.to_string(), model.to_string()));
+
+        assert_eq!(split("Ford Focus", "Ford"), pair("Ford", "Focus"));
+        assert_eq!(
+            split("Land Rover Range Rover Evoque", "land rover"),
+            pair("Land Rover", "Range Rover Evoque")
+        );
+        assert_eq!(split("Volkswagen Golf", ""), pair("Volkswagen", "Golf"));
+        assert_eq!(split("Alfa Romeo Giulia", ""), pair("Alfa Romeo", "Giulia"));
+        // Sponsored listings of other makes
+        assert_eq!(split("Vauxhall Astra", "Volvo"), pair("Vauxhall", "Astra"));
+        assert_eq!(split("Volvo", "Volvo"), pair("Volvo", ""));
+    }
+
+    #[tokio::test]
+    async fn searches_every_page() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(AutotraderUK::at(&base));
+        let query = Query {
+            make_models: vec!["Land Rover:Defender".parse().unwrap()],
+            postcode: Some("BT1 1AA".to_string()),
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/car-search?postcode=BT1+1AA&make=Land+Rover&model=Defender",
+                "/car-search?postcode=BT1+1AA&make=Land+Rover&model=Defender&page=2",
+            ]
+        );
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "autotrader.co.uk".to_string(),
+                make: "Land Rover".to_string(),
+                model: "Defender".to_string(),
+                mileage: Mileage::Mi(38214),
+                year: 2020,
+                price: Price::Gbp(42995),
+                url: format!("{}/car-details/202409183345611", base),
+                fuel: Some("Diesel".to_string()),
+                title: Some("Land Rover Defender".to_string()),
+                description: Some("3.0 D250 MHEV X-Dynamic SE 110 Auto 4WD Euro 6".to_string()),
+                matched: Some("Land Rover Defender".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(hits[1].price, Price::Unknown);
+        assert_eq!(hits[2].mileage, Mileage::Mi(71560));
+    }
+}
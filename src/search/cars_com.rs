@@ -0,0 +1,264 @@
+use std::io::Error;
+
+use reqwest::Url;
+
+use super::parsing::{miles, MileageUnit};
+use super::scraping::{covering, split_title, Listing, Rules, Site};
+use super::slug;
+use crate::query::{MakeModel, Query};
+use crate::taxonomy::split_prefix;
+
+const ROOT: &str = "https://www.cars.com";
+
+/// The distances (in miles) that cars.com searches within; anything further is nationwide.
+const RADIUSES: [i32; 11] = [10, 20, 30, 40, 50, 75, 100, 150, 200, 250, 500];
+
+/// cars.com, a marketplace for used cars from dealers across the US. Its search results are
+/// scraped; prices are in USD and mileages in miles. cars.com has no minimum mileage, so
+/// `min_kms` is not searched on.
+pub struct CarsCom {
+    root: Url,
+    rules: Rules,
+}
+
+impl CarsCom {
+    pub fn new() -> Self {
+        Self::at(ROOT)
+    }
+
+    /// cars.com, served from the provided root URL.
+    fn at(root: &str) -> Self {
+        Self {
+            root: Url::parse(root).expect("invalid root URL"),
+            rules: Rules::new("div.vehicle-card", "USD")
+                .text("title", "h2.title")
+                .attribute("url", "a.vehicle-card-link", "href")
+                .text("year", "h2.title")
+                .text("price", ".primary-price")
+                .text("mileage", ".mileage")
+                .next("a#next_paginate")
+                .mileage_unit(MileageUnit::Mi),
+        }
+    }
+}
+
+impl Default for CarsCom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Site for CarsCom {
+    fn name(&self) -> &str {
+        "cars_com"
+    }
+
+    fn search_engine(&self) -> &str {
+        "cars.com"
+    }
+
+    fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+        // cars.com separates the words of its slugs with underscores, e.g. `land_rover` or `f_150`.
+        let underscored = |name: &str| slug(name).replace('-', "_");
+        let make = make_model.make.as_deref().map(underscored);
+        let model = make_model.model.as_deref().map(underscored);
+        let keywords = query.keywords.join(" ");
+        let radius = query.radius.as_deref().and_then(|kms| {
+            let radius = covering(&RADIUSES, miles(kms)? as f64);
+            Some(radius.map_or("all".to_string(), |radius| radius.to_string()))
+        });
+        let params = [
+            ("stock_type", Some("used".to_string())),
+            ("makes[]", make.clone()),
+            (
+                "models[]",
+                make.zip(model)
+                    .map(|(make, model)| format!("{}-{}", make, model)),
+            ),
+            ("list_price_min", query.min_price.clone()),
+            ("list_price_max", query.max_price.clone()),
+            ("year_min", query.min_year.clone()),
+            ("year_max", query.max_year.clone()),
+            (
+                "mileage_max",
+                query
+                    .max_kms
+                    .as_deref()
+                    .and_then(miles)
+                    .map(|m| m.to_string()),
+            ),
+            ("zip", query.postcode.clone()),
+            ("maximum_distance", radius),
+            ("keyword", Some(keywords).filter(|k| !k.is_empty())),
+        ];
+
+        let mut url = self.root.join("/shopping/results/").map_err(Error::other)?;
+        url.query_pairs_mut().extend_pairs(
+            params
+                .iter()
+                .filter_map(|(name, value)| Some((name, value.as_ref()?))),
+        );
+        Ok(url)
+    }
+
+    fn supports_keywords(&self) -> bool {
+        true
+    }
+
+    /// cars.com titles a listing with its condition, year, make, model and trim, e.g.
+    /// `Used 2019 Ford F-150 XLT`. The model is the one that was searched for if the title has it,
+    /// or else the word after the make.
+    fn make_model(&self, listing: &Listing, searched: &MakeModel) -> Option<(String, String)> {
+        let title = listing.get("title")?;
+        let year = title
+            .split(' ')
+            .position(|word| word.len() == 4 && word.chars().all(|c| c.is_ascii_digit()))?;
+        let vehicle = title.splitn(year + 2, ' ').last()?;
+        let (make, rest) = split_title(vehicle, searched.make.as_deref())?;
+        let model = searched
+            .model
+            .as_deref()
+            .and_then(|model| split_prefix(rest, model))
+            .map_or_else(
+                || rest.split(' ').next().unwrap_or_default(),
+                |(model, _)| model,
+            );
+        Some((make.to_string(), model.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Hit, Mileage, Price};
+    use crate::search::scraping::Scraper;
+    use crate::search::testing::{serve, Received};
+    use crate::search::Searcher;
+
+    use super::*;
+
+    fn page(request: &Received) -> Option<String> {
+        match request.target.split_once("&page=") {
+            None => Some(include_str!("fixtures/cars_com/page_1.html")),
+            Some((_, "2")) => Some(include_str!("fixtures/cars_com/page_2.html")),
+            Some(_) => None,
+        }
+        .map(String::from)
+    }
+
+    #[test]
+    fn maps_the_query_to_search_parameters() {
+        let site = CarsCom::new();
+        let query = Query {
+            max_price: Some("35000".to_string()),
+            min_year: Some("2018".to_string()),
+            min_kms: Some("10000".to_string()),
+            max_kms: Some("80000".to_string()),
+            postcode: Some("60606".to_string()),
+            radius: Some("100".to_string()),
+            ..Default::default()
+        };
+        let make_model = "Land Rover:Range Rover Sport".parse().unwrap();
+
+        assert_eq!(
+            site.search_url(&query, &make_model).unwrap().as_str(),
+            "https://www.cars.com/shopping/results/?stock_type=used&makes%5B%5D=land_rover\
+             &models%5B%5D=land_rover-range_rover_sport&list_price_max=35000&year_min=2018\
+             &mileage_max=49710&zip=60606&maximum_distance=75"
+        );
+
+        // Further than cars.com's largest distance
+        let query = Query {
+            postcode: Some("60606".to_string()),
+            radius: Some("1000".to_string()),
+            ..Default::default()
+        };
+        assert!(site
+            .search_url(&query, &MakeModel::default())
+            .unwrap()
+            .as_str()
+            .ends_with("&zip=60606&maximum_distance=all"));
+    }
+
+    #[test]
+    fn splits_titles_into_make_and_model() {
+        let site = CarsCom::new();
+        let listing = |title: &str| Listing {
+            fields: [("title", title.to_string())].into_iter().collect(),
+        };
+        let split = |title: &str, searched: &str| {
+            site.make_model(&listing(title), &searched.parse().unwrap_or_default())
+        };
+        let pair = |make: &str, model: &str| Some((make.to_string(), model.to_string()));
+
+        assert_eq!(
+            split("Used 2019 Ford F-150 XLT", "Ford"),
+            pair("Ford", "F-150")
+        );
+        assert_eq!(
+            split(
+                "Certified 2020 Jeep Grand Cherokee Limited",
+                "Jeep:Grand Cherokee"
+            ),
+            pair("Jeep", "Grand Cherokee")
+        );
+        assert_eq!(
+            split("2017 Land Rover Discovery HSE", ""),
+            pair("Land Rover", "Discovery")
+        );
+        assert_eq!(split("Used Ford F-150", "Ford"), None);
+    }
+
+    #[tokio::test]
+    async fn searches_every_page() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(CarsCom::at(&base));
+        let query = Query {
+            make_models: vec!["Ford:F-150".parse().unwrap()],
+            postcode: Some("60606".to_string()),
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/shopping/results/?stock_type=used&makes%5B%5D=ford&models%5B%5D=ford-f_150\
+                 &zip=60606",
+                "/shopping/results/?stock_type=used&makes%5B%5D=ford&models%5B%5D=ford-f_150\
+                 &zip=60606&page=2",
+            ]
+        );
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "cars.com".to_string(),
+                make: "Ford".to_string(),
+                model: "F-150".to_string(),
+                mileage: Mileage::Mi(42118),
+                year: 2019,
+                price: Price::Usd(32995),
+                url: format!(
+                    "{}/vehicledetail/6b3f2a9e-11d4-4c1e-9d0a-2f5c8e7b1a44/",
+                    base
+                ),
+                title: Some("Used 2019 Ford F-150 XLT".to_string()),
+                matched: Some("Ford F-150".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(hits[1].price, Price::Unknown);
+        assert_eq!(hits[2].mileage, Mileage::Mi(87450));
+    }
+}
@@ -0,0 +1,170 @@
+use std::io::Error;
+
+use reqwest::Url;
+
+use super::scraping::{Listing, Rules, Site};
+use crate::query::{MakeModel, Query};
+
+const ROOT: &str = "https://www.carsireland.ie";
+
+/// CarsIreland, a marketplace for used cars from dealers in Ireland. Its search results are
+/// scraped; prices are in EUR.
+pub struct CarsIrelandIE {
+    root: Url,
+    rules: Rules,
+}
+
+impl CarsIrelandIE {
+    pub fn new() -> Self {
+        Self::at(ROOT)
+    }
+
+    /// CarsIreland, served from the provided root URL.
+    fn at(root: &str) -> Self {
+        Self {
+            root: Url::parse(root).expect("invalid root URL"),
+            rules: Rules::new("div.vehicle-card[data-vehicle-id]", "EUR")
+                .listing_attribute("make", "data-make")
+                .listing_attribute("model", "data-model")
+                .text("title", ".vehicle-card__title")
+                .text("description", ".vehicle-card__variant")
+                .attribute("url", "a.vehicle-card__link", "href")
+                .text("price", ".vehicle-card__price")
+                .text("year", ".vehicle-card__year")
+                .text("mileage", ".vehicle-card__mileage")
+                .text("fuel", ".vehicle-card__fuel"),
+        }
+    }
+}
+
+impl Default for CarsIrelandIE {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Site for CarsIrelandIE {
+    fn name(&self) -> &str {
+        "carsireland_ie"
+    }
+
+    fn search_engine(&self) -> &str {
+        "carsireland.ie"
+    }
+
+    fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+        let params = [
+            ("make", make_model.make.clone()),
+            ("model", make_model.model.clone()),
+            ("price_from", query.min_price.clone()),
+            ("price_to", query.max_price.clone()),
+            ("year_from", query.min_year.clone()),
+            ("year_to", query.max_year.clone()),
+            ("mileage_from", query.min_kms.clone()),
+            ("mileage_to", query.max_kms.clone()),
+        ];
+
+        let mut url = self.root.join("/used-cars").map_err(Error::other)?;
+        url.query_pairs_mut().extend_pairs(
+            params
+                .iter()
+                .filter_map(|(name, value)| Some((name, value.as_ref()?))),
+        );
+        Ok(url)
+    }
+
+    /// CarsIreland places featured listings of other makes among the search results, which are
+    /// left out.
+    fn make_model(&self, listing: &Listing, searched: &MakeModel) -> Option<(String, String)> {
+        listing.of_make(searched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Hit, Mileage, Price};
+    use crate::search::scraping::Scraper;
+    use crate::search::testing::{serve, Received};
+    use crate::search::Searcher;
+
+    use super::*;
+
+    fn page(request: &Received) -> Option<String> {
+        match request.target.split_once("&page=") {
+            None => Some(include_str!("fixtures/carsireland_ie/page_1.html")),
+            Some((_, "2")) => Some(include_str!("fixtures/carsireland_ie/page_2.html")),
+            Some(_) => None,
+        }
+        .map(String::from)
+    }
+
+    #[test]
+    fn maps_the_query_to_search_parameters() {
+        let site = CarsIrelandIE::new();
+        let query = Query {
+            min_price: Some("5000".to_string()),
+            max_price: Some("15000".to_string()),
+            min_year: Some("2015".to_string()),
+            max_kms: Some("120000".to_string()),
+            ..Default::default()
+        };
+        let make_model = "Volkswagen:Golf".parse().unwrap();
+
+        assert_eq!(
+            site.search_url(&query, &make_model).unwrap().as_str(),
+            "https://www.carsireland.ie/used-cars?make=Volkswagen&model=Golf&price_from=5000\
+             &price_to=15000&year_from=2015&mileage_to=120000"
+        );
+    }
+
+    #[tokio::test]
+    async fn searches_every_page_keeping_the_make_searched_for() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(CarsIrelandIE::at(&base));
+        let query = Query {
+            make_models: vec!["Volkswagen:Golf".parse().unwrap()],
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/used-cars?make=Volkswagen&model=Golf",
+                "/used-cars?make=Volkswagen&model=Golf&page=2",
+            ]
+        );
+        // A featured Toyota on the first page is not what was searched for
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "carsireland.ie".to_string(),
+                make: "Volkswagen".to_string(),
+                model: "Golf".to_string(),
+                mileage: Mileage::Km(68000),
+                year: 2019,
+                price: Price::Eur(19950),
+                url: format!("{}/used-cars/volkswagen/golf/3317620", base),
+                fuel: Some("Petrol".to_string()),
+                title: Some("2019 Volkswagen Golf".to_string()),
+                description: Some("1.5 TSI Highline 5DR".to_string()),
+                matched: Some("Volkswagen Golf".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(hits[1].price, Price::Unknown);
+        assert_eq!(hits[2].mileage, Mileage::Km(141250));
+    }
+}
@@ -1,12 +1,13 @@
 #![allow(non_snake_case)]
 
 use serde::{Deserialize, Serialize};
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
-use super::{SearchResult, Searcher};
+use super::{search_make_models, SearchResult, Searcher};
 use crate::{
     hit::{Hit, Mileage, Price},
-    query::Query,
+    http,
+    query::{MakeModel, Query},
 };
 
 const API_ROOT: &str = "https://www.carzone.ie/rest/1.0/Car/stock";
@@ -49,22 +50,27 @@ struct VehicleMileage {
 struct Vehicle {
     mileage: VehicleMileage,
     registrationYear: u16,
+    fuelType: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct MakeModel {
+struct Mmv {
     cleanMake: String,
     cleanModel: String,
 }
 
 #[derive(Deserialize)]
 struct SearchDetailSummary {
-    mmv: MakeModel,
+    mmv: Mmv,
 }
 
 #[derive(Deserialize)]
 struct Summary {
     publicReference: String,
+    #[serde(default)]
+    headline: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
     priceDetail: PriceDetail,
     vehicle: Vehicle,
     searchDetailSummary: SearchDetailSummary,
@@ -88,10 +94,10 @@ struct CarzoneResponse {
 
 pub struct CarZoneIE {}
 
-fn params_from_query(query: &Query, page: u16) -> CarzoneQueryParams {
+fn params_from_query(query: &Query, make_model: &MakeModel, page: u16) -> CarzoneQueryParams {
     CarzoneQueryParams {
-        make: query.make.clone(),
-        model: query.model.clone(),
+        make: make_model.make.clone(),
+        model: make_model.model.clone(),
         minPrice: query.min_price.clone(),
         maxPrice: query.max_price.clone(),
         minYear: query.min_year.clone(),
@@ -108,45 +114,62 @@ fn params_from_query(query: &Query, page: u16) -> CarzoneQueryParams {
 async fn recursive_fetch(
     client: &reqwest::Client,
     query: &Query,
+    make_model: &MakeModel,
     page: u16,
     mut collected: Vec<CarzoneAd>,
 ) -> Result<Vec<CarzoneAd>, Error> {
-    let params = params_from_query(query, page);
-    let res = client
+    let params = params_from_query(query, make_model, page);
+    let request = client
         .get(API_ROOT)
         .query(&params)
-        .send()
-        .await
-        .map_err(|error| Error::new(ErrorKind::Other, error))?
-        .json::<CarzoneResponse>()
-        .await
-        .map_err(|error| Error::new(ErrorKind::Other, error))?;
+        .build()
+        .map_err(Error::other)?;
+    let res: CarzoneResponse =
+        serde_json::from_str(&http::fetch(client, request).await?).map_err(Error::other)?;
     let mut current_ads = res.results.into_iter().flat_map(|r| r.items).collect();
     collected.append(&mut current_ads);
     if page < res.totalPages {
-        Ok(recursive_fetch(client, query, page + 1, collected).await?)
+        Ok(recursive_fetch(client, query, make_model, page + 1, collected).await?)
     } else {
         Ok(collected)
     }
 }
 
+/// Search for a single make and model.
+async fn search_make_model(
+    client: &reqwest::Client,
+    query: &Query,
+    make_model: &MakeModel,
+) -> SearchResult {
+    let ads = recursive_fetch(client, query, make_model, 1, vec![]).await?;
+    let mapped = ads
+        .iter()
+        // Note: Carzone places premium ads of different makes in the search returns.
+        // Let's make sure we filter those out.
+        .filter(|x| {
+            make_model.make.is_none()
+                || x.summary.searchDetailSummary.mmv.cleanMake.to_lowercase()
+                    == make_model.make.as_ref().unwrap().to_lowercase()
+        })
+        .map(Hit::from)
+        .collect();
+    Ok(mapped)
+}
+
 #[async_trait::async_trait]
 impl Searcher for CarZoneIE {
+    fn name(&self) -> &str {
+        "carzone_ie"
+    }
+
+    /// Carzone only supports a single make and model per search, so a search is performed for
+    /// every make and model in the query.
     async fn search(&self, query: &Query) -> SearchResult {
-        let client = reqwest::Client::new();
-        let ads = recursive_fetch(&client, query, 1, vec![]).await?;
-        let mapped = ads
-            .iter()
-            // Note: Carzone places premium ads of different makes in the search returns.
-            // Let's make sure we filter those out.
-            .filter(|x| {
-                query.make.is_none()
-                    || x.summary.searchDetailSummary.mmv.cleanMake.to_lowercase()
-                        == query.make.as_ref().unwrap().to_lowercase()
-            })
-            .map(Hit::from)
-            .collect();
-        Ok(mapped)
+        let client = &http::client();
+        search_make_models(query, |make_model| async move {
+            search_make_model(client, query, &make_model).await
+        })
+        .await
     }
 }
 
@@ -187,6 +210,10 @@ impl From<&CarzoneAd> for Hit {
             model,
             price: ad.into(),
             url,
+            fuel: ad.summary.vehicle.fuelType.clone(),
+            title: ad.summary.headline.clone(),
+            description: ad.summary.description.clone(),
+            ..Default::default()
         }
     }
 }
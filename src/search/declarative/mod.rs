@@ -0,0 +1,811 @@
+use std::collections::BTreeMap;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::hit::{Hit, Price};
+use crate::http;
+use crate::query::{MakeModel, Query};
+
+use super::parsing::{mileage, number, year, MileageUnit};
+use super::{search_make_models, Registration, SearchResult, Searcher};
+
+mod path;
+mod template;
+
+pub use path::JsonPath;
+pub use template::Template;
+
+/// A value of the query, or of the page being requested, that a request can be templated with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    Make,
+    Model,
+    MinPrice,
+    MaxPrice,
+    MinYear,
+    MaxYear,
+    MinKms,
+    MaxKms,
+    Postcode,
+    /// The distance in km around the postcode
+    Radius,
+    /// The keywords of the query, separated by spaces
+    Keywords,
+    Currency,
+    /// The number of the page, for page pagination
+    Page,
+    /// The offset of the page, for offset pagination
+    Offset,
+    /// The cursor of the page, for cursor pagination
+    Cursor,
+    /// The number of listings per page
+    Size,
+}
+
+impl FromStr for Variable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "make" => Variable::Make,
+            "model" => Variable::Model,
+            "min_price" => Variable::MinPrice,
+            "max_price" => Variable::MaxPrice,
+            "min_year" => Variable::MinYear,
+            "max_year" => Variable::MaxYear,
+            "min_kms" => Variable::MinKms,
+            "max_kms" => Variable::MaxKms,
+            "postcode" => Variable::Postcode,
+            "radius" => Variable::Radius,
+            "keywords" => Variable::Keywords,
+            "currency" => Variable::Currency,
+            "page" => Variable::Page,
+            "offset" => Variable::Offset,
+            "cursor" => Variable::Cursor,
+            "size" => Variable::Size,
+            _ => return Err(format!("unknown variable {{{}}}", s)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Method {
+    #[default]
+    #[serde(alias = "get")]
+    Get,
+    #[serde(alias = "post")]
+    Post,
+}
+
+/// A JSON body in which every string is a template. A string that is nothing but a placeholder
+/// becomes a number if its value is one. Values whose template has no value are left out.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "Value")]
+pub enum Body {
+    Template(Template<Variable>),
+    Array(Vec<Body>),
+    Object(Vec<(String, Body)>),
+    Literal(Value),
+}
+
+impl TryFrom<Value> for Body {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::String(s) => Body::Template(s.parse()?),
+            Value::Array(items) => Body::Array(
+                items
+                    .into_iter()
+                    .map(Body::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(fields) => Body::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| Ok((name, Body::try_from(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            value => Body::Literal(value),
+        })
+    }
+}
+
+impl Body {
+    fn render(&self, value: &impl Fn(&Variable) -> Option<String>) -> Option<Value> {
+        match self {
+            Body::Template(template) => {
+                let text = template.render(value)?;
+                match (template.only_placeholder(), text.parse::<i64>()) {
+                    (Some(_), Ok(number)) => Some(Value::from(number)),
+                    _ => Some(Value::String(text)),
+                }
+            }
+            Body::Array(items) => Some(Value::Array(
+                items.iter().filter_map(|item| item.render(value)).collect(),
+            )),
+            Body::Object(fields) => Some(Value::Object(
+                fields
+                    .iter()
+                    .filter_map(|(name, field)| Some((name.clone(), field.render(value)?)))
+                    .collect(),
+            )),
+            Body::Literal(literal) => Some(literal.clone()),
+        }
+    }
+
+    fn placeholders(&self) -> Vec<Variable> {
+        match self {
+            Body::Template(template) => template.placeholders().copied().collect(),
+            Body::Array(items) => items.iter().flat_map(Body::placeholders).collect(),
+            Body::Object(fields) => fields.iter().flat_map(|(_, f)| f.placeholders()).collect(),
+            Body::Literal(_) => vec![],
+        }
+    }
+}
+
+/// How to request a page of listings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestSpec {
+    pub url: Template<Variable>,
+    #[serde(default)]
+    pub method: Method,
+    /// The parameters of the URL; parameters whose template has no value are left out
+    #[serde(default)]
+    pub params: BTreeMap<String, Template<Variable>>,
+    #[serde(default)]
+    pub body: Option<Body>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn first_page() -> u32 {
+    1
+}
+
+fn max_pages() -> u32 {
+    50
+}
+
+/// How to page through the listings. There is no next page after a page without listings, nor
+/// after `max_pages` pages.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "style", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Pagination {
+    /// All listings are in a single response
+    #[default]
+    None,
+    /// Pages numbered from `start`, up to the number of pages at `total_pages` in the response
+    Page {
+        #[serde(default = "first_page")]
+        start: u32,
+        size: Option<u32>,
+        total_pages: Option<JsonPath>,
+        #[serde(default = "max_pages")]
+        max_pages: u32,
+    },
+    /// Pages of `size` listings from an offset, up to the number of listings at `total` in the
+    /// response. A page of fewer than `size` listings is the last.
+    Offset {
+        #[serde(default)]
+        start: u32,
+        size: u32,
+        total: Option<JsonPath>,
+        #[serde(default = "max_pages")]
+        max_pages: u32,
+    },
+    /// Pages identified by a cursor, which for the next page is at `next` in the response. There
+    /// is no next page if the cursor is missing, empty or 0.
+    Cursor {
+        start: Option<String>,
+        size: Option<u32>,
+        next: JsonPath,
+        #[serde(default = "max_pages")]
+        max_pages: u32,
+    },
+}
+
+/// The page being requested.
+#[derive(Debug, Clone, PartialEq)]
+enum Position {
+    Single,
+    Page(u32),
+    Offset(u32),
+    Cursor(Option<String>),
+}
+
+fn number_at(path: &Option<JsonPath>, response: &Value) -> Option<u32> {
+    path.as_ref()?.text(response)?.parse().ok()
+}
+
+impl Pagination {
+    fn first(&self) -> Position {
+        match self {
+            Pagination::None => Position::Single,
+            Pagination::Page { start, .. } => Position::Page(*start),
+            Pagination::Offset { start, .. } => Position::Offset(*start),
+            Pagination::Cursor { start, .. } => Position::Cursor(start.clone()),
+        }
+    }
+
+    fn max_pages(&self) -> u32 {
+        match self {
+            Pagination::None => 1,
+            Pagination::Page { max_pages, .. }
+            | Pagination::Offset { max_pages, .. }
+            | Pagination::Cursor { max_pages, .. } => *max_pages,
+        }
+    }
+
+    fn size(&self) -> Option<u32> {
+        match self {
+            Pagination::None => None,
+            Pagination::Offset { size, .. } => Some(*size),
+            Pagination::Page { size, .. } | Pagination::Cursor { size, .. } => *size,
+        }
+    }
+
+    /// The page after the provided one, which had the provided response and number of listings.
+    fn next(&self, position: &Position, response: &Value, listings: usize) -> Option<Position> {
+        if listings == 0 {
+            return None;
+        }
+        match (self, position) {
+            (
+                Pagination::Page {
+                    start, total_pages, ..
+                },
+                Position::Page(page),
+            ) => match number_at(total_pages, response) {
+                Some(total) if page + 1 - start >= total => None,
+                _ => Some(Position::Page(page + 1)),
+            },
+            (Pagination::Offset { size, total, .. }, Position::Offset(offset)) => {
+                let next = offset + size;
+                match number_at(total, response) {
+                    _ if listings < *size as usize => None,
+                    Some(total) if next >= total => None,
+                    _ => Some(Position::Offset(next)),
+                }
+            }
+            (Pagination::Cursor { next, .. }, Position::Cursor(cursor)) => next
+                .text(response)
+                .filter(|next| !next.is_empty() && next != "0")
+                .filter(|next| cursor.as_ref() != Some(next))
+                .map(|next| Position::Cursor(Some(next))),
+            _ => None,
+        }
+    }
+}
+
+/// A value of a hit: either a path into the listing, e.g. `$.vehicle.year`, or a template of
+/// paths into the listing, e.g. `https://example.com/cars/{$.id}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Field {
+    Path(JsonPath),
+    Template(Template<JsonPath>),
+}
+
+impl TryFrom<String> for Field {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.starts_with('$') {
+            true => s.parse().map(Field::Path),
+            false => s.parse().map(Field::Template),
+        }
+    }
+}
+
+impl Field {
+    fn text(&self, listing: &Value) -> Option<String> {
+        match self {
+            Field::Path(path) => path.text(listing),
+            Field::Template(template) => template.render(|path| path.text(listing)),
+        }
+    }
+}
+
+/// How to extract hits from a response. Listings without a make, model or URL are skipped.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Extract {
+    /// The listings in a response
+    pub listings: JsonPath,
+    pub make: Field,
+    pub model: Field,
+    pub url: Field,
+    pub year: Option<Field>,
+    pub price: Option<Field>,
+    pub mileage: Option<Field>,
+    pub fuel: Option<Field>,
+    pub title: Option<Field>,
+    pub description: Option<Field>,
+    /// The currency of the price of a listing, if it is not always that of the search engine
+    pub currency: Option<Field>,
+    /// The unit of mileages that do not mention one
+    #[serde(default)]
+    pub mileage_unit: MileageUnit,
+}
+
+/// A search engine with a JSON API, described by a spec in TOML or YAML rather than in code: how
+/// to request pages of listings, and how to extract hits from them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub country: String,
+    /// The currency of the prices of listings
+    pub currency: String,
+    pub request: RequestSpec,
+    #[serde(default)]
+    pub pagination: Pagination,
+    pub extract: Extract,
+}
+
+impl Spec {
+    /// Read a spec from a TOML (`.toml`) or YAML (`.yaml` or `.yml`) file.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&text).map_err(|e| e.to_string()),
+            Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+            _ => Err("unsupported format, expected .toml, .yaml or .yml".to_string()),
+        }
+    }
+
+    fn placeholders(&self) -> Vec<Variable> {
+        let request = &self.request;
+        request
+            .url
+            .placeholders()
+            .chain(request.params.values().flat_map(Template::placeholders))
+            .copied()
+            .chain(request.body.iter().flat_map(Body::placeholders))
+            .collect()
+    }
+
+    fn variable(
+        &self,
+        variable: &Variable,
+        query: &Query,
+        make_model: &MakeModel,
+        position: &Position,
+    ) -> Option<String> {
+        match (variable, position) {
+            (Variable::Make, _) => make_model.make.clone(),
+            (Variable::Model, _) => make_model.model.clone(),
+            (Variable::MinPrice, _) => query.min_price.clone(),
+            (Variable::MaxPrice, _) => query.max_price.clone(),
+            (Variable::MinYear, _) => query.min_year.clone(),
+            (Variable::MaxYear, _) => query.max_year.clone(),
+            (Variable::MinKms, _) => query.min_kms.clone(),
+            (Variable::MaxKms, _) => query.max_kms.clone(),
+            (Variable::Postcode, _) => query.postcode.clone(),
+            (Variable::Radius, _) => query.radius.clone(),
+            (Variable::Keywords, _) => {
+                Some(query.keywords.join(" ")).filter(|keywords| !keywords.is_empty())
+            }
+            (Variable::Currency, _) => query.currency.clone(),
+            (Variable::Page, Position::Page(page)) => Some(page.to_string()),
+            (Variable::Offset, Position::Offset(offset)) => Some(offset.to_string()),
+            (Variable::Cursor, Position::Cursor(cursor)) => cursor.clone(),
+            (Variable::Size, _) => self.pagination.size().map(|size| size.to_string()),
+            _ => None,
+        }
+    }
+
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        query: &Query,
+        make_model: &MakeModel,
+        position: &Position,
+    ) -> Result<reqwest::Request, Error> {
+        let value = |variable: &Variable| self.variable(variable, query, make_model, position);
+        let url = self
+            .request
+            .url
+            .render(value)
+            .ok_or_else(|| Error::other("the URL has placeholders without a value"))?;
+        let params: Vec<(&String, String)> = self
+            .request
+            .params
+            .iter()
+            .filter_map(|(name, template)| Some((name, template.render(value)?)))
+            .collect();
+
+        let mut builder = match self.request.method {
+            Method::Get => client.get(url),
+            Method::Post => client.post(url),
+        }
+        .query(&params);
+        for (name, header) in &self.request.headers {
+            builder = builder.header(name, header);
+        }
+        if let Some(body) = &self.request.body {
+            builder = builder.json(&body.render(&value));
+        }
+        builder.build().map_err(Error::other)
+    }
+
+    /// The hit of a listing, if it has a make, model and URL.
+    fn hit(&self, listing: &Value) -> Option<Hit> {
+        let extract = &self.extract;
+        let text = |field: &Option<Field>| field.as_ref()?.text(listing);
+        let currency = text(&extract.currency).unwrap_or_else(|| self.currency.clone());
+
+        Some(Hit {
+            search_engine: self.name.clone(),
+            make: extract.make.text(listing)?,
+            model: extract.model.text(listing)?,
+            url: extract.url.text(listing)?,
+            year: text(&extract.year).as_deref().and_then(year).unwrap_or(0),
+            price: text(&extract.price)
+                .as_deref()
+                .and_then(number)
+                .map_or(Price::Unknown, |amount| {
+                    Price::in_currency(&currency, amount)
+                }),
+            mileage: text(&extract.mileage)
+                .and_then(|text| mileage(&text, extract.mileage_unit))
+                .unwrap_or_default(),
+            fuel: text(&extract.fuel),
+            title: text(&extract.title),
+            description: text(&extract.description),
+            ..Default::default()
+        })
+    }
+}
+
+/// A searcher for a search engine described by a [`Spec`].
+#[derive(Debug, Clone)]
+pub struct DeclarativeSearcher {
+    spec: Arc<Spec>,
+}
+
+impl DeclarativeSearcher {
+    pub fn new(spec: Spec) -> Self {
+        Self {
+            spec: Arc::new(spec),
+        }
+    }
+
+    /// The registration of the search engine that the spec describes.
+    pub fn registration(&self) -> Registration {
+        let searcher = self.clone();
+        Registration::new(
+            &self.spec.name,
+            &self.spec.description,
+            &self.spec.country,
+            &self.spec.currency,
+            move || Box::new(searcher.clone()),
+        )
+    }
+
+    /// Search for a single make and model.
+    async fn search_make_model(
+        &self,
+        client: &reqwest::Client,
+        query: &Query,
+        make_model: &MakeModel,
+    ) -> SearchResult {
+        let spec = &self.spec;
+        let mut hits = vec![];
+        let mut position = spec.pagination.first();
+        for _ in 0..spec.pagination.max_pages() {
+            let request = spec.request(client, query, make_model, &position)?;
+            let response: Value =
+                serde_json::from_str(&http::fetch(client, request).await?).map_err(Error::other)?;
+            let listings = spec.extract.listings.select(&response);
+            hits.extend(listings.iter().filter_map(|listing| spec.hit(listing)));
+            match spec.pagination.next(&position, &response, listings.len()) {
+                Some(next) => position = next,
+                None => break,
+            }
+        }
+
+        Ok(hits)
+    }
+}
+
+#[async_trait::async_trait]
+impl Searcher for DeclarativeSearcher {
+    fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    /// A search is performed for every make and model in the query.
+    async fn search(&self, query: &Query) -> SearchResult {
+        let client = &http::client();
+        search_make_models(query, |make_model| async move {
+            self.search_make_model(client, query, &make_model).await
+        })
+        .await
+    }
+
+    /// Whether the spec sends the keywords of the query to the search engine.
+    fn supports_keywords(&self) -> bool {
+        self.spec.placeholders().contains(&Variable::Keywords)
+    }
+}
+
+/// Read every spec (`.toml`, `.yaml` or `.yml`) in the provided directory, in alphabetical order.
+/// Invalid specs are skipped with a warning; a missing directory has no specs.
+pub fn discover_specs(dir: &Path) -> Vec<DeclarativeSearcher> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|extension| extension.to_str()),
+                Some("toml" | "yaml" | "yml")
+            )
+        })
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| match Spec::from_file(path) {
+            Ok(spec) => Some(DeclarativeSearcher::new(spec)),
+            Err(e) => {
+                eprintln!("Ignoring spec {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::hit::Mileage;
+    use crate::search::testing::{serve, Received};
+
+    use super::*;
+
+    const CARZONE_SPEC: &str = include_str!("../fixtures/declarative/carzone.toml");
+    const CARZONE_PAGES: [&str; 2] = [
+        include_str!("../fixtures/declarative/carzone_page_1.json"),
+        include_str!("../fixtures/declarative/carzone_page_2.json"),
+    ];
+    const DONEDEAL_SPEC: &str = include_str!("../fixtures/declarative/donedeal.yaml");
+    const DONEDEAL_PAGES: [&str; 2] = [
+        include_str!("../fixtures/declarative/donedeal_from_0.json"),
+        include_str!("../fixtures/declarative/donedeal_from_2.json"),
+    ];
+
+    /// The spec, with its endpoint moved to the provided base URL.
+    fn local(mut spec: Spec, base: &str) -> DeclarativeSearcher {
+        spec.request.url = format!("{}/search", base).parse().unwrap();
+        DeclarativeSearcher::new(spec)
+    }
+
+    fn parameter<'a>(request: &'a Received, name: &str) -> Option<&'a str> {
+        let (_, query) = request.target.split_once('?')?;
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        let spec = CARZONE_SPEC.replace("{min_price}", "{lowest_price}");
+        assert!(toml::from_str::<Spec>(&spec)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("unknown variable {lowest_price}"));
+
+        let spec = CARZONE_SPEC.replace("$.summary.publicReference", "summary.publicReference");
+        assert!(toml::from_str::<Spec>(&spec).is_err());
+    }
+
+    #[test]
+    fn renders_bodies() {
+        let body: Body = serde_json::from_value(json!({
+            "paging": { "from": "{cursor}", "pageSize": 40 },
+            "makeModelFilters": [{ "make": "{make}", "model": "{model}" }],
+            "words": "{keywords}",
+            "label": "cars from {make}"
+        }))
+        .unwrap();
+        let value = |variable: &Variable| match variable {
+            Variable::Cursor => Some("40".to_string()),
+            Variable::Make => Some("Toyota".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            body.render(&value).unwrap(),
+            json!({
+                "paging": { "from": 40, "pageSize": 40 },
+                "makeModelFilters": [{ "make": "Toyota" }],
+                "label": "cars from Toyota"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_through_a_json_api() {
+        let (base, received) = serve(|request| {
+            let page: usize = parameter(request, "page")?.parse().ok()?;
+            CARZONE_PAGES.get(page - 1).map(|page| page.to_string())
+        })
+        .await;
+        let searcher = local(toml::from_str(CARZONE_SPEC).unwrap(), &base);
+        let query = Query {
+            make_models: vec!["Toyota:Corolla".parse().unwrap()],
+            min_price: Some("5000".to_string()),
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(parameter(&received[0], "make"), Some("Toyota"));
+        assert_eq!(parameter(&received[0], "minPrice"), Some("5000"));
+        assert_eq!(parameter(&received[0], "maxPrice"), None);
+        assert_eq!(parameter(&received[0], "size"), Some("2"));
+        assert_eq!(parameter(&received[1], "page"), Some("2"));
+
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "carzone_spec".to_string(),
+                make: "Toyota".to_string(),
+                model: "Corolla".to_string(),
+                mileage: Mileage::Km(52000),
+                year: 2019,
+                price: Price::Eur(16950),
+                url: "https://www.carzone.ie/used-cars/Toyota/Corolla/fpa/201901".to_string(),
+                fuel: Some("Hybrid".to_string()),
+                matched: Some("Toyota Corolla".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(hits[1].price, Price::Eur(14500));
+        // A price on application
+        assert_eq!(hits[2].price, Price::Unknown);
+        assert!(!searcher.supports_keywords());
+    }
+
+    #[tokio::test]
+    async fn follows_cursors_with_a_json_body() {
+        let (base, received) = serve(|request| {
+            let body: Value = serde_json::from_str(&request.body).ok()?;
+            match body["paging"]["from"].as_i64()? {
+                0 => Some(DONEDEAL_PAGES[0].to_string()),
+                2 => Some(DONEDEAL_PAGES[1].to_string()),
+                _ => None,
+            }
+        })
+        .await;
+        let searcher = local(serde_yaml::from_str(DONEDEAL_SPEC).unwrap(), &base);
+        let query = Query {
+            keywords: vec!["full".to_string(), "history".to_string()],
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert!(received.iter().all(|request| request.method == "POST"));
+        let body: Value = serde_json::from_str(&received[1].body).unwrap();
+        assert_eq!(body["paging"], json!({ "from": 2, "pageSize": 2 }));
+        assert_eq!(body["words"], json!("full history"));
+        assert_eq!(body["sections"], json!(["cars"]));
+
+        let summary: Vec<(&str, &str, u16, &Mileage)> = hits
+            .iter()
+            .map(|hit| {
+                (
+                    hit.make.as_str(),
+                    hit.model.as_str(),
+                    hit.year,
+                    &hit.mileage,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Volkswagen", "Golf", 2017, &Mileage::Km(88000)),
+                ("Ford", "Focus", 2015, &Mileage::Mi(61000)),
+                ("Skoda", "Octavia", 2020, &Mileage::Unknown),
+            ]
+        );
+        assert_eq!(
+            hits[0].title,
+            Some("2017 VW Golf, full history".to_string())
+        );
+        assert_eq!(hits[0].price, Price::Eur(14950));
+        assert_eq!(hits[1].price, Price::Gbp(7500));
+        assert!(searcher.supports_keywords());
+    }
+
+    #[tokio::test]
+    async fn pages_by_offset_until_a_short_page() {
+        let (base, received) = serve(|request| {
+            let offset: usize = parameter(request, "from")?.parse().ok()?;
+            let cars: Vec<Value> = (offset..(offset + 2).min(5))
+                .map(|i| json!({ "make": "Mazda", "model": "MX-5", "id": i }))
+                .collect();
+            Some(json!({ "cars": cars }).to_string())
+        })
+        .await;
+        let spec: Spec = toml::from_str(
+            r#"
+            name = "offsets"
+            currency = "EUR"
+
+            [request]
+            url = "https://example.com/search"
+            params = { from = "{offset}", count = "{size}" }
+
+            [pagination]
+            style = "offset"
+            size = 2
+
+            [extract]
+            listings = "$.cars[*]"
+            make = "$.make"
+            model = "$.model"
+            url = "https://example.com/cars/{$.id}"
+            "#,
+        )
+        .unwrap();
+
+        let hits = local(spec, &base).search(&Query::default()).await.unwrap();
+
+        assert_eq!(received.lock().unwrap().len(), 3);
+        assert_eq!(hits.len(), 5);
+        assert_eq!(hits[4].url, "https://example.com/cars/4");
+        assert_eq!(hits[4].year, 0);
+    }
+
+    #[tokio::test]
+    async fn errors_are_propagated() {
+        let (base, _) = serve(|_| None).await;
+        let searcher = local(toml::from_str(CARZONE_SPEC).unwrap(), &base);
+
+        assert!(searcher.search(&Query::default()).await.is_err());
+    }
+
+    #[test]
+    fn discovers_specs() {
+        let dir = std::env::temp_dir().join(format!("findcar-specs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("carzone.toml"), CARZONE_SPEC).unwrap();
+        std::fs::write(dir.join("donedeal.yml"), DONEDEAL_SPEC).unwrap();
+        std::fs::write(dir.join("broken.yaml"), "name: broken").unwrap();
+        std::fs::write(dir.join("README.md"), "not a spec").unwrap();
+
+        let searchers = discover_specs(&dir);
+        let names: Vec<&str> = searchers.iter().map(|s| s.name()).collect();
+
+        assert_eq!(names, vec!["carzone_spec", "donedeal_spec"]);
+        assert!(discover_specs(&dir.join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    /// Every element of an array, or every value of an object
+    Wildcard,
+    /// Every element of an array that has the value at the key
+    Filter {
+        key: String,
+        value: String,
+    },
+}
+
+/// A JSONPath-like expression, selecting values in a JSON document: `$` is the document, followed
+/// by any number of `.key`, `['key']`, `[index]`, `[*]` (or `.*`) and `[?(@.key=='value')]`
+/// segments, e.g. `$.results[*].items[*]` or `$.attributes[?(@.name=='year')].value`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct JsonPath {
+    expression: String,
+    segments: Vec<Segment>,
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid path {}: {}", s, reason);
+        let mut rest = s
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("must start with $"))?;
+        let mut segments = vec![];
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let key = &after[..end];
+                segments.push(match key {
+                    "" => return Err(invalid("empty key")),
+                    "*" => Segment::Wildcard,
+                    key => Segment::Key(key.to_string()),
+                });
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed ["))?;
+                let inner = &after[..end];
+                segments.push(if inner == "*" {
+                    Segment::Wildcard
+                } else if let Some(filter) = inner
+                    .strip_prefix("?(@.")
+                    .and_then(|filter| filter.strip_suffix(')'))
+                {
+                    let (key, value) = filter.split_once("==").ok_or_else(|| {
+                        invalid("expected a filter of the form ?(@.key=='value')")
+                    })?;
+                    let value = value.trim();
+                    let unquoted = value
+                        .strip_prefix('\'')
+                        .and_then(|value| value.strip_suffix('\''))
+                        .or_else(|| {
+                            value
+                                .strip_prefix('"')
+                                .and_then(|value| value.strip_suffix('"'))
+                        })
+                        .unwrap_or(value);
+                    Segment::Filter {
+                        key: key.trim().to_string(),
+                        value: unquoted.to_string(),
+                    }
+                } else if let Some(key) = inner
+                    .strip_prefix('\'')
+                    .and_then(|key| key.strip_suffix('\''))
+                {
+                    Segment::Key(key.to_string())
+                } else {
+                    Segment::Index(inner.parse().map_err(|_| invalid("invalid index"))?)
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected . or ["));
+            }
+        }
+        Ok(Self {
+            expression: s.to_string(),
+            segments,
+        })
+    }
+}
+
+impl TryFrom<String> for JsonPath {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+impl JsonPath {
+    /// Every value in the document that the path selects, in document order.
+    pub fn select<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        self.segments
+            .iter()
+            .fold(vec![document], |values, segment| {
+                values
+                    .into_iter()
+                    .flat_map(|value| -> Vec<&'a Value> {
+                        match (segment, value) {
+                            (Segment::Key(key), Value::Object(map)) => {
+                                map.get(key).into_iter().collect()
+                            }
+                            (Segment::Index(i), Value::Array(items)) => {
+                                items.get(*i).into_iter().collect()
+                            }
+                            (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                            (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                            (Segment::Filter { key, value }, Value::Array(items)) => items
+                                .iter()
+                                .filter(|item| scalar(&item[key.as_str()]).as_ref() == Some(value))
+                                .collect(),
+                            _ => vec![],
+                        }
+                    })
+                    .collect()
+            })
+    }
+
+    /// The first value that the path selects, as text, unless it is null or not a scalar.
+    pub fn text(&self, document: &Value) -> Option<String> {
+        self.select(document).into_iter().find_map(scalar)
+    }
+}
+
+/// A scalar value as text.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn select(path: &str, document: &Value) -> Vec<Value> {
+        path.parse::<JsonPath>()
+            .unwrap()
+            .select(document)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn selects_keys_indices_and_wildcards() {
+        let document = json!({
+            "results": [
+                { "items": [{ "id": 1 }, { "id": 2 }] },
+                { "items": [{ "id": 3 }] }
+            ],
+            "paging": { "next.from": 40 }
+        });
+
+        assert_eq!(select("$", &document), vec![document.clone()]);
+        assert_eq!(
+            select("$.results[1].items[0].id", &document),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            select("$.results[*].items[*].id", &document),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(select("$.paging.*", &document), vec![json!(40)]);
+        assert_eq!(select("$.paging['next.from']", &document), vec![json!(40)]);
+        assert_eq!(
+            select("$.results[*].items[?(@.id=='2')]", &document),
+            vec![json!({ "id": 2 })]
+        );
+        assert_eq!(
+            select("$.results[0].items[?(@.id == \"3\")]", &document),
+            Vec::<Value>::new()
+        );
+        assert_eq!(select("$.results[5].items", &document), Vec::<Value>::new());
+        assert_eq!(select("$.missing.id", &document), Vec::<Value>::new());
+    }
+
+    #[test]
+    fn text_of_scalars() {
+        let document = json!({ "make": "Toyota", "year": 2018, "sold": false, "price": null });
+        let text = |path: &str| path.parse::<JsonPath>().unwrap().text(&document);
+
+        assert_eq!(text("$.make"), Some("Toyota".to_string()));
+        assert_eq!(text("$.year"), Some("2018".to_string()));
+        assert_eq!(text("$.sold"), Some("false".to_string()));
+        assert_eq!(text("$.price"), None);
+        assert_eq!(text("$"), None);
+    }
+
+    #[test]
+    fn invalid_paths_are_rejected() {
+        assert_eq!(
+            "results".parse::<JsonPath>().err().unwrap(),
+            "invalid path results: must start with $"
+        );
+        assert!("$.results[".parse::<JsonPath>().is_err());
+        assert!("$.results[x]".parse::<JsonPath>().is_err());
+        assert!("$..results".parse::<JsonPath>().is_err());
+        assert!("$.results[?(@.id)]".parse::<JsonPath>().is_err());
+    }
+}
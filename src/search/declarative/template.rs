@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part<P> {
+    Literal(String),
+    Placeholder(P),
+}
+
+/// A string with `{...}` placeholders, e.g. `https://example.com/cars/{$.id}` or `{min_price}`.
+/// What a placeholder may contain depends on `P`, which it is parsed as.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String", bound(deserialize = "P: FromStr<Err = String>"))]
+pub struct Template<P> {
+    parts: Vec<Part<P>>,
+}
+
+impl<P: FromStr<Err = String>> FromStr for Template<P> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![];
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("invalid template {}: unclosed {{", s))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            parts.push(Part::Placeholder(rest[start + 1..end].parse()?));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl<P: FromStr<Err = String>> TryFrom<String> for Template<P> {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl<P> Template<P> {
+    /// The template with every placeholder replaced by its value, or None if any placeholder has
+    /// no value.
+    pub fn render(&self, value: impl Fn(&P) -> Option<String>) -> Option<String> {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(literal) => Some(literal.clone()),
+                Part::Placeholder(placeholder) => value(placeholder),
+            })
+            .collect()
+    }
+
+    /// The placeholder, if the template consists of nothing but a single placeholder.
+    pub fn only_placeholder(&self) -> Option<&P> {
+        match self.parts.as_slice() {
+            [Part::Placeholder(placeholder)] => Some(placeholder),
+            _ => None,
+        }
+    }
+
+    pub fn placeholders(&self) -> impl Iterator<Item = &P> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Placeholder(placeholder) => Some(placeholder),
+            Part::Literal(_) => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Upper(String);
+
+    impl FromStr for Upper {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.chars().all(|c| c.is_ascii_uppercase()) {
+                true => Ok(Upper(s.to_string())),
+                false => Err(format!("not upper case: {}", s)),
+            }
+        }
+    }
+
+    fn value(placeholder: &Upper) -> Option<String> {
+        Some(placeholder.0.to_lowercase()).filter(|v| v != "missing")
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let template: Template<Upper> = "https://{HOST}/cars/{ID}.json".parse().unwrap();
+
+        assert_eq!(
+            template.render(value),
+            Some("https://host/cars/id.json".to_string())
+        );
+        assert_eq!(
+            template.placeholders().collect::<Vec<&Upper>>(),
+            vec![&Upper("HOST".to_string()), &Upper("ID".to_string())]
+        );
+        assert_eq!(template.only_placeholder(), None);
+    }
+
+    #[test]
+    fn missing_values_render_nothing() {
+        let template: Template<Upper> = "{MISSING}".parse().unwrap();
+
+        assert_eq!(template.render(value), None);
+        assert_eq!(
+            template.only_placeholder(),
+            Some(&Upper("MISSING".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert_eq!(
+            "{ID".parse::<Template<Upper>>().err().unwrap(),
+            "invalid template {ID: unclosed {"
+        );
+        assert_eq!(
+            "{id}".parse::<Template<Upper>>().err().unwrap(),
+            "not upper case: id"
+        );
+    }
+}
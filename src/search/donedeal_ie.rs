@@ -1,11 +1,12 @@
 #![allow(non_snake_case)]
 
 use serde::{Deserialize, Serialize};
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 use super::{SearchResult, Searcher};
 use crate::{
     hit::{Hit, Mileage, Price},
+    http,
     query::Query,
 };
 
@@ -19,6 +20,8 @@ struct DisplayAttribute {
 
 #[derive(Deserialize)]
 struct DonedealAd {
+    header: Option<String>,
+    description: Option<String>,
     currency: String,
     price: Option<String>,
     displayAttributes: Vec<DisplayAttribute>,
@@ -68,6 +71,8 @@ struct DonedealRequestBody {
     filters: Vec<Filter>,
     ranges: Vec<Range>,
     sections: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<String>,
 }
 
 pub struct DoneDealIE {}
@@ -102,16 +107,32 @@ fn ranges_from_query(query: &Query) -> Vec<Range> {
     ranges
 }
 
+fn make_model_filters(query: &Query) -> Vec<MakeModelFilter> {
+    if query.make_models.is_empty() {
+        return vec![MakeModelFilter {
+            make: "".to_string(),
+            model: "".to_string(),
+        }];
+    }
+
+    query
+        .make_models
+        .iter()
+        .map(|make_model| MakeModelFilter {
+            make: make_model.make.clone().unwrap_or("".to_string()),
+            model: make_model.model.clone().unwrap_or("".to_string()),
+        })
+        .collect()
+}
+
 fn request_from_query(query: &Query, from: u32) -> DonedealRequestBody {
     DonedealRequestBody {
-        makeModelFilters: vec![MakeModelFilter {
-            make: query.make.clone().unwrap_or("".to_string()),
-            model: query.model.clone().unwrap_or("".to_string()),
-        }],
+        makeModelFilters: make_model_filters(query),
         paging: Paging { from, pageSize: 40 },
         filters: vec![],
         ranges: ranges_from_query(query),
         sections: vec!["cars".to_string()],
+        words: Some(query.keywords.join(" ")).filter(|words| !words.is_empty()),
     }
 }
 
@@ -123,15 +144,13 @@ async fn recursive_fetch(
     mut collected: Vec<DonedealAd>,
 ) -> Result<Vec<DonedealAd>, Error> {
     let req = request_from_query(query, n);
-    let res = client
+    let request = client
         .post(API_ROOT)
         .json(&req)
-        .send()
-        .await
-        .map_err(|error| Error::new(ErrorKind::Other, error))?
-        .json::<DonedealResponse>()
-        .await
-        .map_err(|error| Error::new(ErrorKind::Other, error))?;
+        .build()
+        .map_err(Error::other)?;
+    let res: DonedealResponse =
+        serde_json::from_str(&http::fetch(client, request).await?).map_err(Error::other)?;
     let mut current_ads = res.ads.unwrap_or(vec![]);
     collected.append(&mut current_ads);
     if res.paging.nextFrom > 0 {
@@ -143,10 +162,25 @@ async fn recursive_fetch(
 
 #[async_trait::async_trait]
 impl Searcher for DoneDealIE {
+    fn name(&self) -> &str {
+        "donedeal_ie"
+    }
+
     async fn search(&self, query: &Query) -> SearchResult {
-        let client = reqwest::Client::new();
+        let client = http::client();
         let ads = recursive_fetch(&client, query, 0, vec![]).await?;
-        Ok(ads.iter().map(Hit::from).collect())
+        Ok(ads
+            .iter()
+            .map(Hit::from)
+            .map(|hit| Hit {
+                matched: query.matched_label(&hit),
+                ..hit
+            })
+            .collect())
+    }
+
+    fn supports_keywords(&self) -> bool {
+        true
     }
 }
 
@@ -158,12 +192,7 @@ impl From<&DonedealAd> for Price {
 
         let unwrapped = ad.price.as_ref().unwrap();
         let parsed: i32 = unwrapped.replace(",", "").parse().unwrap();
-        match ad.currency.to_uppercase().as_str() {
-            "EUR" => Price::Eur(parsed),
-            "GBP" => Price::Gbp(parsed),
-            "USD" => Price::Usd(parsed),
-            _ => Price::Unknown,
-        }
+        Price::in_currency(&ad.currency, parsed)
     }
 }
 
@@ -214,6 +243,12 @@ impl From<&DonedealAd> for Hit {
             .find(|attr| attr.name == "year")
             .map_or(0, |year| year.value.parse().unwrap_or(0));
 
+        let fuel = ad
+            .displayAttributes
+            .iter()
+            .find(|attr| attr.name == "fuelType")
+            .map(|fuel| fuel.value.to_string());
+
         Hit {
             mileage: ad.into(),
             year,
@@ -222,6 +257,10 @@ impl From<&DonedealAd> for Hit {
             model: model.to_string(),
             price: ad.into(),
             url: ad.friendlyUrl.to_string(),
+            fuel,
+            title: ad.header.clone(),
+            description: ad.description.clone(),
+            ..Default::default()
         }
     }
 }
@@ -0,0 +1,274 @@
+use std::io::{Error, ErrorKind};
+
+use reqwest::Url;
+
+use super::scraping::{covering, split_title, Listing, Rules, Site};
+use crate::query::{MakeModel, Query};
+use crate::taxonomy::key;
+
+const ROOT: &str = "https://suchen.mobile.de";
+
+/// The distances (in km) that mobile.de searches within; anything further is nationwide.
+const RADIUSES: [i32; 8] = [10, 20, 50, 100, 150, 200, 250, 500];
+
+/// mobile.de, the largest marketplace for used cars in Germany. Its search results are scraped, in
+/// English; prices are in EUR.
+///
+/// mobile.de identifies makes and models by number, so they are searched by the identifiers in
+/// the taxonomy (as `mobile_de`). A model without one is searched by its make, keeping the
+/// listings of a model of that name; variants such as the Golf Sportsvan are models of their own.
+pub struct MobileDE {
+    root: Url,
+    rules: Rules,
+}
+
+impl MobileDE {
+    pub fn new() -> Self {
+        Self::at(ROOT)
+    }
+
+    /// mobile.de, served from the provided root URL.
+    fn at(root: &str) -> Self {
+        Self {
+            root: Url::parse(root).expect("invalid root URL"),
+            rules: Rules::new(r#"article[data-testid^="result-listing-"]"#, "EUR")
+                .text("title", r#"[data-testid="result-listing-headline"]"#)
+                .text("description", r#"[data-testid="result-listing-subline"]"#)
+                .attribute("url", r#"a[data-testid="result-listing-link"]"#, "href")
+                .text("price", r#"[data-testid="price-label"]"#)
+                .text("year", r#"[data-testid="first-registration"]"#)
+                .text("mileage", r#"[data-testid="mileage"]"#)
+                .text("fuel", r#"[data-testid="fuel"]"#)
+                .next(r#"a[data-testid="pagination:next"]"#),
+        }
+    }
+}
+
+impl Default for MobileDE {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a make or model is a mobile.de identifier rather than a name.
+fn is_id(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_digit())
+}
+
+/// A range as mobile.de expects it, e.g. `1000:15000` or `:15000`.
+fn range(min: &Option<String>, max: &Option<String>) -> Option<String> {
+    (min.is_some() || max.is_some()).then(|| {
+        format!(
+            "{}:{}",
+            min.as_deref().unwrap_or_default(),
+            max.as_deref().unwrap_or_default()
+        )
+    })
+}
+
+impl Site for MobileDE {
+    fn name(&self) -> &str {
+        "mobile_de"
+    }
+
+    fn search_engine(&self) -> &str {
+        "mobile.de"
+    }
+
+    fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+        let makes = match make_model.make.as_deref() {
+            None => None,
+            Some(make) if is_id(make) => {
+                let model = make_model.model.as_deref().filter(|model| is_id(model));
+                Some(format!("{};{};;", make, model.unwrap_or_default()))
+            }
+            Some(make) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "mobile.de has no identifier for the make {}; add one to the taxonomy \
+                         as engines.mobile_de",
+                        make
+                    ),
+                ))
+            }
+        };
+        let postcode = query.postcode.as_ref();
+        let radius = query
+            .radius
+            .as_deref()
+            .and_then(|kms| covering(&RADIUSES, kms.trim().parse().ok()?));
+        let params = [
+            ("isSearchRequest", Some("true".to_string())),
+            ("s", Some("Car".to_string())),
+            ("vc", Some("Car".to_string())),
+            ("ms", makes),
+            ("p", range(&query.min_price, &query.max_price)),
+            ("fr", range(&query.min_year, &query.max_year)),
+            ("ml", range(&query.min_kms, &query.max_kms)),
+            ("zip", postcode.cloned()),
+            ("zipc", postcode.map(|_| "DE".to_string())),
+            ("zipr", radius.map(|radius| radius.to_string())),
+            ("lang", Some("en".to_string())),
+        ];
+
+        let mut url = self
+            .root
+            .join("/fahrzeuge/search.html")
+            .map_err(Error::other)?;
+        url.query_pairs_mut().extend_pairs(
+            params
+                .iter()
+                .filter_map(|(name, value)| Some((name, value.as_ref()?))),
+        );
+        Ok(url)
+    }
+
+    /// mobile.de only lists the make and model together, as the headline of a listing. When a
+    /// model was searched for by name, only listings of exactly that model are kept.
+    fn make_model(&self, listing: &Listing, searched: &MakeModel) -> Option<(String, String)> {
+        // The make that was searched for is an identifier, which the headline does not mention
+        let (make, model) = split_title(listing.get("title")?, None)?;
+        let by_name = searched.model.as_deref().filter(|model| !is_id(model));
+        if by_name.is_some_and(|wanted| key(model) != key(wanted)) {
+            return None;
+        }
+        Some((make.to_string(), model.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::{Hit, Mileage, Price};
+    use crate::search::scraping::Scraper;
+    use crate::search::testing::{serve, Received};
+    use crate::search::Searcher;
+    use crate::taxonomy::Taxonomy;
+
+    use super::*;
+
+    fn page(request: &Received) -> Option<String> {
+        match request.target.split_once("&pageNumber=") {
+            None => Some(include_str!("fixtures/mobile_de/page_1.html")),
+            Some((_, "2")) => Some(include_str!("fixtures/mobile_de/page_2.html")),
+            Some(_) => None,
+        }
+        .map(String::from)
+    }
+
+    fn query(vehicle: &str) -> Query {
+        let query = Query {
+            make_models: vec![vehicle.parse().unwrap()],
+            ..Default::default()
+        };
+        Taxonomy::bundled().query_for("mobile_de", &query)
+    }
+
+    #[test]
+    fn maps_the_query_to_search_parameters() {
+        let site = MobileDE::new();
+        let query = Query {
+            max_price: Some("15000".to_string()),
+            min_year: Some("2016".to_string()),
+            max_year: Some("2019".to_string()),
+            postcode: Some("10115".to_string()),
+            radius: Some("75".to_string()),
+            ..query("BMW:3 Series")
+        };
+
+        assert_eq!(query.make_models[0].make, Some("3500".to_string()));
+        assert_eq!(
+            site.search_url(&query, &query.make_models[0])
+                .unwrap()
+                .as_str(),
+            "https://suchen.mobile.de/fahrzeuge/search.html?isSearchRequest=true&s=Car&vc=Car\
+             &ms=3500%3B%3B%3B&p=%3A15000&fr=2016%3A2019&zip=10115&zipc=DE&zipr=100&lang=en"
+        );
+
+        let by_id = "3500:20".parse().unwrap();
+        assert!(site
+            .search_url(&Query::default(), &by_id)
+            .unwrap()
+            .as_str()
+            .contains("&ms=3500%3B20%3B%3B&"));
+    }
+
+    #[test]
+    fn models_searched_by_name_match_exactly() {
+        let site = MobileDE::new();
+        let listing = |title: &str| Listing {
+            fields: [("title", title.to_string())].into_iter().collect(),
+        };
+        let searched = "25200:Golf".parse().unwrap();
+        let model = |title: &str| {
+            site.make_model(&listing(title), &searched)
+                .map(|(_, model)| model)
+        };
+
+        assert_eq!(model("Volkswagen Golf"), Some("Golf".to_string()));
+        assert_eq!(model("Volkswagen GOLF"), Some("GOLF".to_string()));
+        assert_eq!(model("Volkswagen Golf Sportsvan"), None);
+        assert_eq!(model("Volkswagen Golf Plus"), None);
+    }
+
+    #[test]
+    fn makes_without_an_identifier_are_rejected() {
+        let site = MobileDE::new();
+        let query = query("Trabant:601");
+
+        assert_eq!(
+            site.search_url(&query, &query.make_models[0])
+                .unwrap_err()
+                .to_string(),
+            "mobile.de has no identifier for the make Trabant; add one to the taxonomy as \
+             engines.mobile_de"
+        );
+    }
+
+    #[tokio::test]
+    async fn searches_every_page_keeping_the_model_searched_for() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(MobileDE::at(&base));
+
+        let hits = searcher.search(&query("VW:Golf")).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/fahrzeuge/search.html?isSearchRequest=true&s=Car&vc=Car&ms=25200%3B%3B%3B&lang=en",
+                "/fahrzeuge/search.html?isSearchRequest=true&s=Car&vc=Car&ms=25200%3B%3B%3B&lang=en&pageNumber=2",
+            ]
+        );
+        // A Polo and a Golf Variant on the first page are not what was searched for
+        assert_eq!(hits.len(), 2);
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "mobile.de".to_string(),
+                make: "Volkswagen".to_string(),
+                model: "Golf".to_string(),
+                mileage: Mileage::Km(87500),
+                year: 2019,
+                price: Price::Eur(17490),
+                url: format!("{}/fahrzeuge/details.html?id=389012345&lang=en", base),
+                fuel: Some("Petrol".to_string()),
+                title: Some("Volkswagen Golf".to_string()),
+                description: Some("1.5 TSI ACT Highline DSG LED Navi".to_string()),
+                // The engine tags hits with the canonical make instead
+                matched: Some("25200 Golf".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(hits[1].price, Price::Eur(9950));
+    }
+}
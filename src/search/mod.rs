@@ -1,16 +1,177 @@
-use crate::{hit::Hit, query::Query};
+use std::future::Future;
 
+use crate::{
+    hit::Hit,
+    query::{MakeModel, Query},
+};
+
+#[cfg(feature = "adverts_ie")]
+mod adverts_ie;
+#[cfg(feature = "adverts_ie")]
+pub use adverts_ie::AdvertsIE;
+
+#[cfg(feature = "autotrader_co_uk")]
+mod autotrader_co_uk;
+#[cfg(feature = "autotrader_co_uk")]
+pub use autotrader_co_uk::AutotraderUK;
+
+#[cfg(feature = "donedeal_ie")]
 mod donedeal_ie;
+#[cfg(feature = "donedeal_ie")]
 pub use donedeal_ie::DoneDealIE;
 
+#[cfg(feature = "autoscout24")]
+pub mod autoscout24;
+#[cfg(feature = "autoscout24")]
+pub use autoscout24::AutoScout24;
+
+#[cfg(feature = "cars_com")]
+mod cars_com;
+#[cfg(feature = "cars_com")]
+pub use cars_com::CarsCom;
+
+#[cfg(feature = "carsireland_ie")]
+mod carsireland_ie;
+#[cfg(feature = "carsireland_ie")]
+pub use carsireland_ie::CarsIrelandIE;
+
+#[cfg(feature = "carzone_ie")]
 mod carzone_ie;
+#[cfg(feature = "carzone_ie")]
 pub use carzone_ie::CarZoneIE;
 
+#[cfg(feature = "mobile_de")]
+mod mobile_de;
+#[cfg(feature = "mobile_de")]
+pub use mobile_de::MobileDE;
+
+pub mod declarative;
+pub use declarative::{discover_specs, DeclarativeSearcher, Spec};
+
+pub mod parsing;
+
+mod plugin;
+pub use plugin::{discover_plugins, Capabilities, Handshake, PluginSearcher};
+
+mod registry;
+pub use registry::{Registration, Registry};
+
+pub mod scraping;
+pub use scraping::{Rules, Scraper, Site};
+
+#[cfg(test)]
+mod testing;
+
 pub type SearchResult = Result<Vec<Hit>, std::io::Error>;
 
 /// A trait that defines a single operation that any search engine must implement.
 /// The root engine will be able to use the results of any struct that implements this trait.
 #[async_trait::async_trait]
-pub trait Searcher {
+pub trait Searcher: Send + Sync {
     async fn search(&self, query: &Query) -> SearchResult;
+
+    /// The name by which this search engine is identified, e.g. on the command line and in the
+    /// taxonomy of makes and models.
+    fn name(&self) -> &str;
+
+    /// Whether this search engine filters on the query's keywords itself. If it does not, the
+    /// root engine filters the results on the keywords instead. Excluded keywords are always
+    /// applied by the root engine.
+    fn supports_keywords(&self) -> bool {
+        false
+    }
+
+    /// The makes that this search engine knows of or, if a make is provided, the models of that
+    /// make, as listed by the search engine itself. Search engines that cannot list their makes
+    /// and models return None, in which case the bundled taxonomy is used instead.
+    async fn taxonomy(&self, _make: Option<&str>) -> Option<Result<Vec<String>, std::io::Error>> {
+        None
+    }
+}
+
+/// Search for every make and model of the query at once, for search engines that only support a
+/// single make and model per search, tagging every hit with the make and model it was found for.
+/// A query without makes and models is searched for any make and model. Makes and models that
+/// cannot be searched are reported on stderr, and the hits of the others are kept; the search
+/// only fails if none of them can be searched.
+pub async fn search_make_models<F, Fut>(query: &Query, search: F) -> SearchResult
+where
+    F: Fn(MakeModel) -> Fut,
+    Fut: Future<Output = SearchResult>,
+{
+    let make_models = match query.make_models.as_slice() {
+        [] => vec![MakeModel::default()],
+        make_models => make_models.to_vec(),
+    };
+
+    let labels: Vec<String> = make_models.iter().map(MakeModel::label).collect();
+    let results = futures::future::join_all(make_models.into_iter().map(|make_model| {
+        let matched = Some(make_model.label()).filter(|label| !label.is_empty());
+        let hits = search(make_model);
+        async move {
+            hits.await.map(|hits| {
+                hits.into_iter()
+                    .map(|hit| Hit {
+                        matched: matched.clone(),
+                        ..hit
+                    })
+                    .collect::<Vec<Hit>>()
+            })
+        }
+    }))
+    .await;
+
+    if results.iter().all(Result::is_err) {
+        return results.into_iter().next().unwrap_or(Ok(vec![]));
+    }
+    let mut hits = vec![];
+    for (label, result) in labels.iter().zip(results) {
+        match result {
+            Ok(found) => hits.extend(found),
+            Err(error) => eprintln!(
+                "Unable to search for {}, results unavailable.\nReason: {}",
+                label, error
+            ),
+        }
+    }
+    Ok(hits)
+}
+
+/// The slug by which many sites identify a make or model in their paths, e.g. `land-rover` for
+/// `Land Rover`. Slugs are left as they are.
+pub fn slug(name: &str) -> String {
+    name.trim().to_lowercase().replace(' ', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Error;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn makes_and_models_that_fail_are_left_out() {
+        let search = |make_model: MakeModel| async move {
+            match make_model.make.as_deref() {
+                Some("Ford") => Err(Error::other("no connection")),
+                _ => Ok(vec![Hit::default()]),
+            }
+        };
+        let query = |vehicles: &[&str]| Query {
+            make_models: vehicles.iter().map(|v| v.parse().unwrap()).collect(),
+            ..Default::default()
+        };
+
+        let hits = search_make_models(&query(&["VW:Golf", "Ford:Focus"]), search)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].matched, Some("VW Golf".to_string()));
+
+        let error = search_make_models(&query(&["Ford:Focus", "Ford:Fiesta"]), search)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "no connection");
+    }
 }
@@ -0,0 +1,160 @@
+use serde::Deserialize;
+
+use crate::hit::Mileage;
+
+/// The unit of a mileage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MileageUnit {
+    #[default]
+    Km,
+    Mi,
+}
+
+/// Whether the character separates groups of thousands in numbers such as `14,950` or `14 950`.
+fn is_separator(c: char) -> bool {
+    matches!(c, ',' | '.' | ' ' | '\u{a0}' | '\u{202f}' | '\'')
+}
+
+/// The first number in text as listings have them, e.g. `14950`, `14950.0`, `€14,950` or
+/// `52.000 km`. Decimals are rounded; separators are only taken for groups of thousands.
+pub fn number(text: &str) -> Option<i32> {
+    if let Ok(number) = text.trim().parse::<f64>() {
+        return Some(number.round() as i32);
+    }
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let chars: Vec<char> = text[start..].chars().collect();
+    let mut digits = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_digit() {
+            digits.push(*c);
+            continue;
+        }
+        let group = chars.get(i + 1..i + 4);
+        let thousands = is_separator(*c)
+            && group.is_some_and(|group| group.iter().all(char::is_ascii_digit))
+            && !chars.get(i + 4).is_some_and(char::is_ascii_digit);
+        if !thousands {
+            break;
+        }
+    }
+    digits.parse().ok()
+}
+
+/// The unit of the word that directly follows the first number in text, e.g. `mi` in
+/// `61,000 mi`, if it is one.
+fn unit_after_number(text: &str) -> Option<MileageUnit> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let rest = text[start..].trim_start_matches(|c: char| c.is_ascii_digit() || is_separator(c));
+    let word = rest
+        .chars()
+        .take_while(|c| c.is_alphabetic())
+        .collect::<String>()
+        .to_lowercase();
+    match word.as_str() {
+        "km" | "kms" | "kilometre" | "kilometres" | "kilometer" | "kilometers" => {
+            Some(MileageUnit::Km)
+        }
+        "mi" | "mile" | "miles" => Some(MileageUnit::Mi),
+        _ => None,
+    }
+}
+
+/// A mileage as listings have them, e.g. `52000` or `61,000 mi`, in the unit that follows the
+/// number if any and in the provided unit otherwise.
+pub fn mileage(text: &str, unit: MileageUnit) -> Option<Mileage> {
+    let distance = number(text)?;
+    Some(match unit_after_number(text).unwrap_or(unit) {
+        MileageUnit::Km => Mileage::Km(distance),
+        MileageUnit::Mi => Mileage::Mi(distance),
+    })
+}
+
+/// A distance or mileage in km as a query has them, e.g. `--max-kms 100000`, in miles, for sites
+/// that search in miles.
+pub fn miles(kms: &str) -> Option<i32> {
+    kms.trim()
+        .parse()
+        .ok()
+        .and_then(|kms| Mileage::Km(kms).miles())
+}
+
+/// The year in a number or date, e.g. `2018`, `2018-05-01` or `05/2018`.
+pub fn year(text: &str) -> Option<u16> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(number("14950"), Some(14950));
+        assert_eq!(number("14950.4"), Some(14950));
+        assert_eq!(number("€14,950"), Some(14950));
+        assert_eq!(number("€14,950.00"), Some(14950));
+        assert_eq!(number("£ 9 995"), Some(9995));
+        assert_eq!(number("52.000 km"), Some(52000));
+        assert_eq!(number("1,234,567"), Some(1234567));
+        assert_eq!(number("Reg 2018, 52,000 km"), Some(2018));
+        assert_eq!(number("POA"), None);
+    }
+
+    #[test]
+    fn parses_mileages_in_their_unit() {
+        assert_eq!(mileage("52000", MileageUnit::Km), Some(Mileage::Km(52000)));
+        assert_eq!(mileage("52000", MileageUnit::Mi), Some(Mileage::Mi(52000)));
+        assert_eq!(
+            mileage("61,000 mi", MileageUnit::Km),
+            Some(Mileage::Mi(61000))
+        );
+        assert_eq!(
+            mileage("61,000 miles", MileageUnit::Km),
+            Some(Mileage::Mi(61000))
+        );
+        assert_eq!(
+            mileage("88,000 km", MileageUnit::Mi),
+            Some(Mileage::Km(88000))
+        );
+        assert_eq!(
+            mileage("61000mi", MileageUnit::Km),
+            Some(Mileage::Mi(61000))
+        );
+        assert_eq!(mileage("unknown", MileageUnit::Km), None);
+    }
+
+    #[test]
+    fn only_a_unit_next_to_the_number_counts() {
+        assert_eq!(
+            mileage("Premium, 50,000", MileageUnit::Km),
+            Some(Mileage::Km(50000))
+        );
+        assert_eq!(
+            mileage("mileage unknown 50,000", MileageUnit::Km),
+            Some(Mileage::Km(50000))
+        );
+        assert_eq!(
+            mileage("50,000 (Kmart)", MileageUnit::Mi),
+            Some(Mileage::Mi(50000))
+        );
+    }
+
+    #[test]
+    fn parses_years() {
+        assert_eq!(year("2018"), Some(2018));
+        assert_eq!(year("2018-05-01"), Some(2018));
+        assert_eq!(year("05/2018"), Some(2018));
+        assert_eq!(year("18"), None);
+    }
+
+    #[test]
+    fn converts_kms_to_miles() {
+        assert_eq!(miles("100000"), Some(62137));
+        assert_eq!(miles(" 80 "), Some(50));
+        assert_eq!(miles("far"), None);
+    }
+}
@@ -0,0 +1,404 @@
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::hit::Hit;
+use crate::query::Query;
+
+use super::{Registration, SearchResult, Searcher};
+
+/// The version of the protocol between findcar and its plugins.
+const PROTOCOL: u32 = 1;
+
+/// What a plugin can do besides searching.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Capabilities {
+    /// Whether the plugin filters on the query's keywords itself
+    pub keywords: bool,
+    /// Whether the plugin can list the makes and models it supports
+    pub taxonomy: bool,
+}
+
+/// The plugin's reply to the handshake, describing the search engine it implements.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Handshake {
+    pub protocol: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub currency: String,
+    #[serde(default)]
+    pub capabilities: Capabilities,
+}
+
+/// A search engine implemented by an external executable. The executable is run once per
+/// operation, with the operation as its first argument:
+///
+/// * `handshake`: print a single JSON object describing the search engine (see [`Handshake`])
+/// * `search`: read the query as JSON on stdin, and print the hits as JSON, one per line
+/// * `taxonomy [MAKE]`: print the makes or, if a make is given, the models of that make as a JSON
+///   array of names, if the plugin has the `taxonomy` capability
+///
+/// A plugin that exits with a non-zero status fails the operation, with what it printed to stderr
+/// as the error. Operations that do not finish in time are killed.
+#[derive(Debug, Clone)]
+pub struct PluginSearcher {
+    path: PathBuf,
+    handshake: Handshake,
+    timeout: Duration,
+}
+
+/// Run the plugin with the provided arguments and input, returning what it printed to stdout.
+async fn invoke(
+    path: &Path,
+    arguments: &[&str],
+    input: Option<String>,
+    timeout: Duration,
+) -> Result<String, Error> {
+    let mut child = Command::new(path)
+        .args(arguments)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // The input is written while the output is read, so that neither can block the other; the
+    // input is closed once written.
+    let stdin = child.stdin.take();
+    let write = async move {
+        match (input, stdin) {
+            (Some(input), Some(mut stdin)) => stdin.write_all(input.as_bytes()).await,
+            _ => Ok(()),
+        }
+    };
+    let (written, output) = tokio::time::timeout(
+        timeout,
+        futures::future::join(write, child.wait_with_output()),
+    )
+    .await
+    .map_err(|_| {
+        Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("timed out after {} seconds", timeout.as_secs_f64()),
+        )
+    })?;
+    let output = output?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::other(format!(
+            "exited with {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    written.map_err(|e| Error::new(e.kind(), format!("unable to send the query: {}", e)))?;
+    String::from_utf8(output.stdout).map_err(Error::other)
+}
+
+/// Parse the hits a plugin printed, one JSON object per line. Hits are attributed to the plugin's
+/// search engine if they do not name one themselves.
+fn parse_hits(name: &str, output: &str) -> SearchResult {
+    output
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let mut value: serde_json::Value = serde_json::from_str(line)
+                .map_err(|e| Error::other(format!("invalid hit on line {}: {}", i + 1, e)))?;
+            if let Some(hit) = value.as_object_mut() {
+                hit.entry("search_engine")
+                    .or_insert_with(|| serde_json::Value::String(name.to_string()));
+            }
+            serde_json::from_value::<Hit>(value)
+                .map_err(|e| Error::other(format!("invalid hit on line {}: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+impl PluginSearcher {
+    /// Perform the handshake with the executable at the provided path.
+    pub async fn connect(path: &Path, timeout: Duration) -> Result<Self, Error> {
+        let output = invoke(path, &["handshake"], None, timeout).await?;
+        let handshake: Handshake = serde_json::from_str(output.trim())
+            .map_err(|e| Error::other(format!("invalid handshake: {}", e)))?;
+        if handshake.protocol != PROTOCOL {
+            return Err(Error::other(format!(
+                "unsupported protocol version {}, expected {}",
+                handshake.protocol, PROTOCOL
+            )));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            handshake,
+            timeout,
+        })
+    }
+
+    /// The registration of the search engine that this plugin implements.
+    pub fn registration(&self) -> Registration {
+        let searcher = self.clone();
+        Registration::new(
+            &self.handshake.name,
+            &self.handshake.description,
+            &self.handshake.country,
+            &self.handshake.currency,
+            move || Box::new(searcher.clone()),
+        )
+    }
+
+    fn error(&self, e: Error) -> Error {
+        Error::new(e.kind(), format!("plugin {}: {}", self.handshake.name, e))
+    }
+}
+
+#[async_trait::async_trait]
+impl Searcher for PluginSearcher {
+    async fn search(&self, query: &Query) -> SearchResult {
+        let query = serde_json::to_string(query).map_err(Error::other)?;
+        invoke(&self.path, &["search"], Some(query), self.timeout)
+            .await
+            .and_then(|output| parse_hits(&self.handshake.name, &output))
+            .map_err(|e| self.error(e))
+    }
+
+    fn name(&self) -> &str {
+        &self.handshake.name
+    }
+
+    fn supports_keywords(&self) -> bool {
+        self.handshake.capabilities.keywords
+    }
+
+    async fn taxonomy(&self, make: Option<&str>) -> Option<Result<Vec<String>, Error>> {
+        if !self.handshake.capabilities.taxonomy {
+            return None;
+        }
+        let arguments: Vec<&str> = std::iter::once("taxonomy").chain(make).collect();
+        Some(
+            invoke(&self.path, &arguments, None, self.timeout)
+                .await
+                .and_then(|output| serde_json::from_str(&output).map_err(Error::other))
+                .map_err(|e| self.error(e)),
+        )
+    }
+}
+
+/// Whether the file at the provided path can be run as a plugin.
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Connect to every plugin in the provided directory, in alphabetical order. Plugins that fail the
+/// handshake are skipped with a warning; a missing directory has no plugins.
+pub async fn discover_plugins(dir: &Path, timeout: Duration) -> Vec<PluginSearcher> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_executable(path))
+        .collect();
+    paths.sort();
+
+    let connections = futures::future::join_all(
+        paths
+            .iter()
+            .map(|path| PluginSearcher::connect(path, timeout)),
+    )
+    .await;
+
+    paths
+        .iter()
+        .zip(connections)
+        .filter_map(|(path, connection)| match connection {
+            Ok(plugin) => Some(plugin),
+            Err(e) => {
+                eprintln!("Ignoring plugin {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::hit::{Mileage, Price};
+
+    use super::*;
+
+    const HANDSHAKE: &str = r#"{"protocol": 1, "name": "dealer", "country": "IE", "currency": "EUR", "capabilities": {"taxonomy": true}}"#;
+
+    /// A directory of plugins for a single test, created afresh.
+    fn plugin_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("findcar-plugins-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a shell script plugin that replies to the handshake, and otherwise runs `body`.
+    fn plugin(dir: &Path, name: &str, handshake: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        let script = format!(
+            "#!/bin/sh\nif [ \"$1\" = handshake ]; then echo '{}'; exit 0; fi\n{}\n",
+            handshake, body
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn searches_through_the_plugin() {
+        let dir = plugin_dir("search");
+        // Echo the make of the query back, to show that the query is sent on stdin
+        let path = plugin(
+            &dir,
+            "dealer",
+            HANDSHAKE,
+            r#"make=$(sed 's/.*"make":"\([^"]*\)".*/\1/')
+echo "{\"make\": \"$make\", \"model\": \"Corolla\", \"mileage\": {\"Km\": 1000}, \"year\": 2018, \"price\": {\"Eur\": 9000}, \"url\": \"https://dealer.ie/1\"}"
+echo
+echo "{\"search_engine\": \"other\", \"make\": \"Honda\", \"model\": \"Civic\", \"mileage\": \"Unknown\", \"year\": 2015, \"price\": \"Unknown\", \"url\": \"https://dealer.ie/2\"}""#,
+        );
+        let plugin = PluginSearcher::connect(&path, Duration::from_secs(5))
+            .await
+            .unwrap();
+        let query = Query {
+            make_models: vec!["Toyota".parse().unwrap()],
+            ..Default::default()
+        };
+        let hits = plugin.search(&query).await.unwrap();
+
+        assert_eq!(plugin.name(), "dealer");
+        assert!(!plugin.supports_keywords());
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].search_engine, "dealer");
+        assert_eq!(hits[0].make, "Toyota");
+        assert_eq!(hits[0].mileage, Mileage::Km(1000));
+        assert_eq!(hits[0].price, Price::Eur(9000));
+        assert_eq!(hits[1].search_engine, "other");
+    }
+
+    #[tokio::test]
+    async fn lists_the_taxonomy_through_the_plugin() {
+        let dir = plugin_dir("taxonomy");
+        let path = plugin(&dir, "dealer", HANDSHAKE, r#"echo "[\"$2\", \"Yaris\"]""#);
+        let plugin = PluginSearcher::connect(&path, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            plugin.taxonomy(Some("Corolla")).await.unwrap().unwrap(),
+            vec!["Corolla", "Yaris"]
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_are_propagated() {
+        let dir = plugin_dir("errors");
+        let failing = plugin(
+            &dir,
+            "failing",
+            HANDSHAKE,
+            "echo 'no connection' >&2; exit 3",
+        );
+        let garbled = plugin(&dir, "garbled", HANDSHAKE, "echo 'not json'");
+        let slow = plugin(&dir, "slow", HANDSHAKE, "sleep 5");
+        let query = Query::default();
+
+        let connect = |path: PathBuf| async move {
+            PluginSearcher::connect(&path, Duration::from_millis(500))
+                .await
+                .unwrap()
+        };
+        let error = connect(failing).await.search(&query).await.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "plugin dealer: exited with exit status: 3: no connection"
+        );
+        let error = connect(garbled).await.search(&query).await.err().unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("plugin dealer: invalid hit on line 1"));
+        let error = connect(slow).await.search(&query).await.err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn plugins_that_do_not_read_the_query_fail() {
+        let dir = plugin_dir("unread");
+        let deaf = plugin(&dir, "deaf", HANDSHAKE, "sleep 5");
+        let hasty = plugin(&dir, "hasty", HANDSHAKE, "exit 0");
+        // A query that does not fit in the buffer of a pipe
+        let query = Query {
+            keywords: vec!["towbar".repeat(100_000)],
+            ..Default::default()
+        };
+
+        let connect = |path: PathBuf| async move {
+            PluginSearcher::connect(&path, Duration::from_millis(500))
+                .await
+                .unwrap()
+        };
+        let error = connect(deaf).await.search(&query).await.err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+        let error = connect(hasty).await.search(&query).await.err().unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("plugin dealer: unable to send the query"));
+    }
+
+    #[tokio::test]
+    async fn discovers_the_plugins_that_complete_the_handshake() {
+        let dir = plugin_dir("discovery");
+        plugin(&dir, "b", HANDSHAKE, "");
+        plugin(&dir, "a", &HANDSHAKE.replace("dealer", "another"), "");
+        plugin(
+            &dir,
+            "c",
+            &HANDSHAKE.replace("\"protocol\": 1", "\"protocol\": 2"),
+            "",
+        );
+        plugin(&dir, "d", "not json", "");
+        std::fs::write(dir.join("README"), "not a plugin").unwrap();
+
+        let plugins = discover_plugins(&dir, Duration::from_secs(5)).await;
+        let names: Vec<&str> = plugins.iter().map(|p| p.name()).collect();
+
+        assert_eq!(names, vec!["another", "dealer"]);
+        assert!(
+            discover_plugins(&dir.join("missing"), Duration::from_secs(5))
+                .await
+                .is_empty()
+        );
+    }
+}
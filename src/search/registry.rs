@@ -0,0 +1,301 @@
+use super::Searcher;
+
+/// A search engine that can be used, and how to create its searcher.
+pub struct Registration {
+    /// The name by which the search engine is selected, e.g. on the command line
+    pub name: String,
+    pub description: String,
+    /// The ISO 3166 code of the country the search engine covers
+    pub country: String,
+    /// The ISO 4217 code of the currency its listings are priced in
+    pub currency: String,
+    pub constructor: Box<dyn Fn() -> Box<dyn Searcher> + Send + Sync>,
+}
+
+impl Registration {
+    pub fn new(
+        name: &str,
+        description: &str,
+        country: &str,
+        currency: &str,
+        constructor: impl Fn() -> Box<dyn Searcher> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            country: country.to_string(),
+            currency: currency.to_string(),
+            constructor: Box::new(constructor),
+        }
+    }
+}
+
+/// The search engines that can be used, in the order in which they were registered.
+pub struct Registry {
+    registrations: Vec<Registration>,
+}
+
+/// The edit distance between two names, counting insertions, deletions and substitutions.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl Registry {
+    /// A registry of the search engines that ship with findcar, as far as their features are
+    /// enabled.
+    pub fn builtin() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self {
+            registrations: vec![],
+        };
+        #[cfg(feature = "carzone_ie")]
+        registry.register(Registration::new(
+            "carzone_ie",
+            "Carzone, used cars from dealers in Ireland",
+            "IE",
+            "EUR",
+            || Box::new(super::CarZoneIE {}),
+        ));
+        #[cfg(feature = "donedeal_ie")]
+        registry.register(Registration::new(
+            "donedeal_ie",
+            "DoneDeal, used cars from dealers and private sellers in Ireland",
+            "IE",
+            "EUR",
+            || Box::new(super::DoneDealIE {}),
+        ));
+        #[cfg(feature = "carsireland_ie")]
+        registry.register(Registration::new(
+            "carsireland_ie",
+            "CarsIreland, used cars from dealers in Ireland",
+            "IE",
+            "EUR",
+            || Box::new(super::Scraper::new(super::CarsIrelandIE::new())),
+        ));
+        #[cfg(feature = "adverts_ie")]
+        registry.register(Registration::new(
+            "adverts_ie",
+            "Adverts, used cars from private sellers and dealers in Ireland",
+            "IE",
+            "EUR",
+            || Box::new(super::Scraper::new(super::AdvertsIE::new())),
+        ));
+        #[cfg(feature = "autotrader_co_uk")]
+        registry.register(Registration::new(
+            "autotrader_co_uk",
+            "Autotrader, used cars from dealers and private sellers in the UK",
+            "GB",
+            "GBP",
+            || Box::new(super::Scraper::new(super::AutotraderUK::new())),
+        ));
+        #[cfg(feature = "mobile_de")]
+        registry.register(Registration::new(
+            "mobile_de",
+            "mobile.de, used cars from dealers and private sellers in Germany",
+            "DE",
+            "EUR",
+            || Box::new(super::Scraper::new(super::MobileDE::new())),
+        ));
+        #[cfg(feature = "cars_com")]
+        registry.register(Registration::new(
+            "cars_com",
+            "cars.com, used cars from dealers in the US",
+            "US",
+            "USD",
+            || Box::new(super::Scraper::new(super::CarsCom::new())),
+        ));
+        #[cfg(feature = "autoscout24")]
+        for country in super::autoscout24::COUNTRIES {
+            registry.register(Registration::new(
+                &format!("autoscout24_{}", country.code.to_lowercase()),
+                &format!(
+                    "AutoScout24, used cars from dealers and private sellers in {}",
+                    country.name
+                ),
+                country.code,
+                "EUR",
+                move || Box::new(super::Scraper::new(super::AutoScout24::new(country))),
+            ));
+        }
+        registry
+    }
+
+    /// Add a search engine, replacing any search engine of the same name.
+    pub fn register(&mut self, registration: Registration) {
+        self.registrations.retain(|r| r.name != registration.name);
+        self.registrations.push(registration);
+    }
+
+    pub fn registrations(&self) -> &[Registration] {
+        &self.registrations
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Registration> {
+        self.registrations.iter().find(|r| r.name == name)
+    }
+
+    /// The name of the registered search engine that is closest to the provided name, if any is
+    /// close enough to be a likely typo.
+    pub fn suggest(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.registrations
+            .iter()
+            .map(|r| (levenshtein(&name, &r.name), r.name.as_str()))
+            .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(2))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate)
+    }
+
+    /// The names of the selected search engines (all of them if none are selected), excluding the
+    /// disabled ones. Every name must be that of a registered search engine.
+    pub fn select(&self, selected: &[String], disabled: &[String]) -> Result<Vec<&str>, String> {
+        let unknown: Vec<String> = selected
+            .iter()
+            .chain(disabled)
+            .filter(|name| self.get(name).is_none())
+            .map(|name| match self.suggest(name) {
+                Some(suggestion) => format!(
+                    "Unknown search engine: {}. Did you mean {}?",
+                    name, suggestion
+                ),
+                None => format!(
+                    "Unknown search engine: {}. Options are: {}",
+                    name,
+                    self.registrations
+                        .iter()
+                        .map(|r| r.name.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            })
+            .collect();
+        if !unknown.is_empty() {
+            return Err(unknown.join("\n"));
+        }
+
+        Ok(self
+            .registrations
+            .iter()
+            .map(|r| r.name.as_str())
+            .filter(|name| selected.is_empty() || selected.iter().any(|s| s == name))
+            .filter(|name| !disabled.iter().any(|d| d == name))
+            .collect())
+    }
+
+    /// Create the searchers of the named search engines.
+    pub fn searchers(&self, names: &[&str]) -> Vec<Box<dyn Searcher>> {
+        names
+            .iter()
+            .filter_map(|name| self.get(name))
+            .map(|r| (r.constructor)())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::Query;
+    use crate::search::SearchResult;
+
+    use super::*;
+
+    struct Stub(&'static str);
+
+    #[async_trait::async_trait]
+    impl Searcher for Stub {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn search(&self, _q: &Query) -> SearchResult {
+            Ok(vec![])
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry {
+            registrations: vec![],
+        };
+        for name in ["carzone_ie", "donedeal_ie"] {
+            registry.register(Registration::new(name, "", "IE", "EUR", move || {
+                Box::new(Stub(name))
+            }));
+        }
+        registry
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(levenshtein("carzone_ie", "carzone_ie"), 0);
+        assert_eq!(levenshtein("carzone", "carzone_ie"), 3);
+        assert_eq!(levenshtein("donedeal_ei", "donedeal_ie"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn selects_engines_in_registration_order() {
+        let registry = registry();
+
+        assert_eq!(
+            registry.select(&[], &[]).unwrap(),
+            vec!["carzone_ie", "donedeal_ie"]
+        );
+        assert_eq!(
+            registry
+                .select(&names(&["donedeal_ie", "carzone_ie"]), &[])
+                .unwrap(),
+            vec!["carzone_ie", "donedeal_ie"]
+        );
+        assert_eq!(
+            registry.select(&[], &names(&["carzone_ie"])).unwrap(),
+            vec!["donedeal_ie"]
+        );
+    }
+
+    #[test]
+    fn unknown_engines_are_reported_with_suggestions() {
+        let registry = registry();
+
+        assert_eq!(
+            registry.select(&names(&["carzone"]), &[]).err().unwrap(),
+            "Unknown search engine: carzone. Did you mean carzone_ie?"
+        );
+        assert_eq!(
+            registry.select(&[], &names(&["ebay"])).err().unwrap(),
+            "Unknown search engine: ebay. Options are: carzone_ie, donedeal_ie"
+        );
+    }
+
+    #[test]
+    fn registering_an_engine_again_replaces_it() {
+        let mut registry = registry();
+        registry.register(Registration::new("carzone_ie", "", "GB", "GBP", || {
+            Box::new(Stub("carzone_ie"))
+        }));
+
+        assert_eq!(registry.registrations().len(), 2);
+        assert_eq!(registry.get("carzone_ie").unwrap().currency, "GBP");
+    }
+
+    #[test]
+    fn creates_searchers_by_name() {
+        let searchers = registry().searchers(&["donedeal_ie"]);
+
+        assert_eq!(searchers.len(), 1);
+        assert_eq!(searchers[0].name(), "donedeal_ie");
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Error;
+use std::sync::OnceLock;
+
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::hit::{Hit, Price};
+use crate::http;
+use crate::query::{MakeModel, Query};
+use crate::taxonomy::{split_prefix, Taxonomy};
+
+use super::parsing::{mileage, number, year, MileageUnit};
+use super::{search_make_models, SearchResult, Searcher};
+
+/// Parse a CSS selector of a site's rules.
+///
+/// Panics if the selector is invalid, as the rules of a site are part of its code.
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).unwrap_or_else(|e| panic!("invalid selector {}: {}", css, e))
+}
+
+/// How to extract a field of a listing: the text of the first element that matches the selector,
+/// or the value of one of its attributes. Without a selector, the listing itself is the element.
+#[derive(Debug, Clone)]
+struct Extraction {
+    selector: Option<Selector>,
+    attribute: Option<String>,
+}
+
+/// The rules by which listings are scraped from a page of search results. Fields are extracted
+/// from each element that matches the listing selector, by name. The names `make`, `model`, `url`,
+/// `year`, `price`, `currency`, `mileage`, `fuel`, `title` and `description` are used for hits;
+/// sites may extract other fields for their own use.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    listing: Selector,
+    fields: Vec<(&'static str, Extraction)>,
+    next: Selector,
+    currency: String,
+    mileage_unit: MileageUnit,
+    max_pages: u32,
+}
+
+impl Rules {
+    /// Rules for listings that match the provided selector, with prices in the provided currency
+    /// (as an ISO 4217 code) unless a listing's `currency` field says otherwise. The next page is
+    /// the one linked with `rel="next"`, and at most 20 pages are scraped.
+    pub fn new(listing: &str, currency: &str) -> Self {
+        Self {
+            listing: selector(listing),
+            fields: vec![],
+            next: selector(r#"a[rel~="next"], link[rel~="next"]"#),
+            currency: currency.to_string(),
+            mileage_unit: MileageUnit::Km,
+            max_pages: 20,
+        }
+    }
+
+    /// Extract a field as the text of the first element within the listing that matches the
+    /// selector, with whitespace collapsed.
+    pub fn text(mut self, field: &'static str, css: &str) -> Self {
+        self.fields.push((
+            field,
+            Extraction {
+                selector: Some(selector(css)),
+                attribute: None,
+            },
+        ));
+        self
+    }
+
+    /// Extract a field as the value of an attribute of the first element within the listing that
+    /// matches the selector. Links (`href` and `src`) are made absolute.
+    pub fn attribute(mut self, field: &'static str, css: &str, attribute: &str) -> Self {
+        self.fields.push((
+            field,
+            Extraction {
+                selector: Some(selector(css)),
+                attribute: Some(attribute.to_string()),
+            },
+        ));
+        self
+    }
+
+    /// Extract a field as the value of an attribute of the listing element itself, such as a
+    /// `data-price` that sites keep for their own scripts.
+    pub fn listing_attribute(mut self, field: &'static str, attribute: &str) -> Self {
+        self.fields.push((
+            field,
+            Extraction {
+                selector: None,
+                attribute: Some(attribute.to_string()),
+            },
+        ));
+        self
+    }
+
+    /// The selector of the link to the next page.
+    pub fn next(mut self, css: &str) -> Self {
+        self.next = selector(css);
+        self
+    }
+
+    /// The unit of mileages that do not mention one.
+    pub fn mileage_unit(mut self, unit: MileageUnit) -> Self {
+        self.mileage_unit = unit;
+        self
+    }
+
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+}
+
+/// The fields scraped from a single listing, by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Listing {
+    pub fields: HashMap<&'static str, String>,
+}
+
+impl Listing {
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(String::as_str)
+    }
+
+    /// The make and model of the listing, unless it is of another make than the one searched for,
+    /// as are the featured listings some sites place among the search results.
+    pub fn of_make(&self, searched: &MakeModel) -> Option<(String, String)> {
+        let (make, model) = (self.get("make")?, self.get("model")?);
+        if searched
+            .make
+            .as_deref()
+            .is_some_and(|searched| !make.eq_ignore_ascii_case(searched))
+        {
+            return None;
+        }
+        Some((make.to_string(), model.to_string()))
+    }
+}
+
+/// A page of search results, scraped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Page {
+    pub listings: Vec<Listing>,
+    /// The absolute URL of the next page, if any
+    pub next: Option<Url>,
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn extract(element: &ElementRef, extraction: &Extraction, base: &Url) -> Option<String> {
+    let found = match &extraction.selector {
+        Some(selector) => element.select(selector).next()?,
+        None => *element,
+    };
+    let value = match &extraction.attribute {
+        None => collapse_whitespace(&found.text().collect::<String>()),
+        Some(attribute) => {
+            let value = found.value().attr(attribute)?.trim();
+            match attribute.as_str() {
+                "href" | "src" => base.join(value).ok()?.to_string(),
+                _ => value.to_string(),
+            }
+        }
+    };
+    Some(value).filter(|value| !value.is_empty())
+}
+
+/// Scrape the listings, and the link to the next page, from a page at the provided URL.
+pub fn scrape(rules: &Rules, url: &Url, html: &str) -> Page {
+    let document = Html::parse_document(html);
+    let listings = document
+        .select(&rules.listing)
+        .map(|element| Listing {
+            fields: rules
+                .fields
+                .iter()
+                .filter_map(|(field, extraction)| {
+                    Some((*field, extract(&element, extraction, url)?))
+                })
+                .collect(),
+        })
+        .collect();
+    let next = document
+        .select(&rules.next)
+        .find_map(|link| link.value().attr("href"))
+        .and_then(|href| url.join(href.trim()).ok());
+    Page { listings, next }
+}
+
+/// Split the title of a listing into its make and model, for sites that only list them together:
+/// after the make that was searched for if the title starts with it, or else after any make in the
+/// bundled taxonomy, or else after the first word.
+pub fn split_title<'a>(title: &'a str, make: Option<&str>) -> Option<(&'a str, &'a str)> {
+    static TAXONOMY: OnceLock<Taxonomy> = OnceLock::new();
+    make.and_then(|make| split_prefix(title, make))
+        .or_else(|| TAXONOMY.get_or_init(Taxonomy::bundled).split_make(title))
+        .or_else(|| title.split_once(' '))
+}
+
+/// The smallest of the distances that a site lets searches be limited to that covers the distance,
+/// if any. Sites search further than their largest distance without one.
+pub fn covering(distances: &[i32], distance: f64) -> Option<i32> {
+    distances
+        .iter()
+        .copied()
+        .find(|option| *option as f64 >= distance)
+}
+
+/// A search engine whose results are scraped from HTML pages, to be searched with a [`Scraper`].
+pub trait Site: Send + Sync {
+    /// The name by which this search engine is identified, e.g. on the command line.
+    fn name(&self) -> &str;
+
+    /// The rules by which listings are scraped from a page of search results.
+    fn rules(&self) -> &Rules;
+
+    /// The URL of the first page of search results for a single make and model of the query.
+    fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error>;
+
+    /// Whether the site filters on the query's keywords itself, like [`Searcher::supports_keywords`].
+    fn supports_keywords(&self) -> bool {
+        false
+    }
+
+    /// The search engine that hits are attributed to, such as the domain of the site, e.g.
+    /// `carsireland.ie`. Defaults to the name.
+    fn search_engine(&self) -> &str {
+        self.name()
+    }
+
+    /// The make and model of a listing found when searching for the make and model of the query.
+    /// Sites whose listings only have them together, e.g. in a title, split them themselves.
+    fn make_model(&self, listing: &Listing, _searched: &MakeModel) -> Option<(String, String)> {
+        Some((
+            listing.get("make")?.to_string(),
+            listing.get("model")?.to_string(),
+        ))
+    }
+
+    /// The hit of a listing found when searching for the make and model, if it has a make, model
+    /// and URL.
+    fn hit(&self, listing: &Listing, searched: &MakeModel) -> Option<Hit> {
+        let rules = self.rules();
+        let currency = listing.get("currency").unwrap_or(&rules.currency);
+        let (make, model) = self.make_model(listing, searched)?;
+        Some(Hit {
+            search_engine: self.search_engine().to_string(),
+            make,
+            model,
+            url: listing.get("url")?.to_string(),
+            year: listing.get("year").and_then(year).unwrap_or(0),
+            price: listing
+                .get("price")
+                .and_then(number)
+                .map_or(Price::Unknown, |amount| {
+                    Price::in_currency(currency, amount)
+                }),
+            mileage: listing
+                .get("mileage")
+                .and_then(|text| mileage(text, rules.mileage_unit))
+                .unwrap_or_default(),
+            fuel: listing.get("fuel").map(String::from),
+            title: listing.get("title").map(String::from),
+            description: listing.get("description").map(String::from),
+            ..Default::default()
+        })
+    }
+}
+
+/// A searcher that scrapes the search results of a [`Site`], following the links to next pages.
+/// Pages are fetched like every other request, so they are rate limited and cached alike.
+pub struct Scraper<S> {
+    site: S,
+}
+
+impl<S: Site> Scraper<S> {
+    pub fn new(site: S) -> Self {
+        Self { site }
+    }
+
+    /// Search for a single make and model. Stops at a page
+    /// without listings or without a link to a next page that has not been scraped yet.
+    async fn search_make_model(
+        &self,
+        client: &reqwest::Client,
+        query: &Query,
+        make_model: &MakeModel,
+    ) -> SearchResult {
+        let rules = self.site.rules();
+        let mut url = self.site.search_url(query, make_model)?;
+        let mut visited = HashSet::new();
+        let mut hits = vec![];
+        for _ in 0..rules.max_pages {
+            visited.insert(url.clone());
+            let request = client.get(url.clone()).build().map_err(Error::other)?;
+            let page = scrape(rules, &url, &http::fetch_politely(client, request).await?);
+            if page.listings.is_empty() {
+                break;
+            }
+            hits.extend(
+                page.listings
+                    .iter()
+                    .filter_map(|listing| self.site.hit(listing, make_model)),
+            );
+            match page.next.filter(|next| !visited.contains(next)) {
+                Some(next) => url = next,
+                None => break,
+            }
+        }
+
+        Ok(hits)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Site> Searcher for Scraper<S> {
+    fn name(&self) -> &str {
+        self.site.name()
+    }
+
+    /// A search is performed for every make and model in the query.
+    async fn search(&self, query: &Query) -> SearchResult {
+        let client = &http::client();
+        search_make_models(query, |make_model| async move {
+            self.search_make_model(client, query, &make_model).await
+        })
+        .await
+    }
+
+    fn supports_keywords(&self) -> bool {
+        self.site.supports_keywords()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hit::Mileage;
+    use crate::search::testing::{serve, Received};
+
+    use super::*;
+
+    const PAGES: [&str; 3] = [
+        include_str!("fixtures/scraping/results_page_1.html"),
+        include_str!("fixtures/scraping/results_page_2.html"),
+        include_str!("fixtures/scraping/results_page_3.html"),
+    ];
+
+    /// A site of used cars in the UK, serving the fixtures.
+    struct Dealers {
+        base: Url,
+        rules: Rules,
+    }
+
+    impl Dealers {
+        fn new(base: &str) -> Self {
+            Self {
+                base: Url::parse(base).unwrap(),
+                rules: Rules::new("article.listing", "GBP")
+                    .text("make", ".make")
+                    .text("model", ".model")
+                    .attribute("url", "a.details", "href")
+                    .text("year", ".specs .year")
+                    .text("price", ".price")
+                    .text("mileage", ".specs .mileage")
+                    .text("fuel", ".specs .fuel")
+                    .text("title", "h2")
+                    .attribute("reference", "a.details", "data-reference")
+                    .listing_attribute("position", "data-position")
+                    .mileage_unit(MileageUnit::Mi),
+            }
+        }
+    }
+
+    impl Site for Dealers {
+        fn name(&self) -> &str {
+            "dealers_uk"
+        }
+
+        fn rules(&self) -> &Rules {
+            &self.rules
+        }
+
+        fn search_url(&self, query: &Query, make_model: &MakeModel) -> Result<Url, Error> {
+            let mut url = self.base.join("/used-cars").map_err(Error::other)?;
+            url.query_pairs_mut()
+                .append_pair("make", make_model.make.as_deref().unwrap_or_default());
+            if let Some(max_price) = &query.max_price {
+                url.query_pairs_mut().append_pair("price-to", max_price);
+            }
+            Ok(url)
+        }
+    }
+
+    fn page(request: &Received) -> Option<String> {
+        let page = match request.target.split_once("page=") {
+            None => 1,
+            Some((_, page)) => page.parse().ok()?,
+        };
+        PAGES.get(page - 1).map(|page| page.to_string())
+    }
+
+    #[test]
+    fn scrapes_listings_and_the_next_link() {
+        let site = Dealers::new("https://dealers.example");
+        let url = Url::parse("https://dealers.example/used-cars?make=Ford").unwrap();
+        let page = scrape(site.rules(), &url, PAGES[0]);
+
+        assert_eq!(page.listings.len(), 2);
+        assert_eq!(
+            page.next.map(String::from),
+            Some("https://dealers.example/used-cars?make=Ford&price-to=10000&page=2".to_string())
+        );
+        let listing = &page.listings[0];
+        assert_eq!(
+            listing.get("title"),
+            Some("2016 Ford Focus 1.0 EcoBoost Zetec")
+        );
+        assert_eq!(listing.get("reference"), Some("ff-2016-1"));
+        assert_eq!(listing.get("position"), Some("1"));
+        assert_eq!(
+            listing.get("url"),
+            Some("https://dealers.example/cars/ff-2016-1")
+        );
+        // Fields that are missing or empty are left out
+        assert_eq!(page.listings[1].get("fuel"), None);
+    }
+
+    #[test]
+    fn converts_listings_into_hits() {
+        let site = Dealers::new("https://dealers.example");
+        let url = Url::parse("https://dealers.example/used-cars").unwrap();
+        let page = scrape(site.rules(), &url, PAGES[0]);
+        let hits: Vec<Hit> = page
+            .listings
+            .iter()
+            .filter_map(|listing| site.hit(listing, &MakeModel::default()))
+            .collect();
+
+        assert_eq!(
+            hits[0],
+            Hit {
+                search_engine: "dealers_uk".to_string(),
+                make: "Ford".to_string(),
+                model: "Focus".to_string(),
+                mileage: Mileage::Mi(42500),
+                year: 2016,
+                price: Price::Gbp(8995),
+                url: "https://dealers.example/cars/ff-2016-1".to_string(),
+                fuel: Some("Petrol".to_string()),
+                title: Some("2016 Ford Focus 1.0 EcoBoost Zetec".to_string()),
+                ..Default::default()
+            }
+        );
+        // A mileage in kilometres, and a price on application
+        assert_eq!(hits[1].mileage, Mileage::Km(71000));
+        assert_eq!(hits[1].price, Price::Unknown);
+    }
+
+    #[tokio::test]
+    async fn follows_next_links_until_the_last_page() {
+        let (base, received) = serve(page).await;
+        let searcher = Scraper::new(Dealers::new(&base));
+        let query = Query {
+            make_models: vec!["Ford".parse().unwrap()],
+            max_price: Some("10000".to_string()),
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        let targets: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/used-cars?make=Ford&price-to=10000",
+                "/used-cars?make=Ford&price-to=10000&page=2",
+                "/used-cars?make=Ford&price-to=10000&page=3",
+            ]
+        );
+        // The third page has no listings, and the second one a listing without a model
+        assert_eq!(hits.len(), 3);
+        assert!(hits
+            .iter()
+            .all(|hit| hit.matched == Some("Ford".to_string())));
+        assert_eq!(searcher.name(), "dealers_uk");
+    }
+
+    #[tokio::test]
+    async fn does_not_revisit_pages() {
+        // The first page links back to itself
+        let (base, received) = serve(|_| Some(PAGES[0].replace("&amp;page=2", ""))).await;
+        let searcher = Scraper::new(Dealers::new(&base));
+        let query = Query {
+            make_models: vec!["Ford".parse().unwrap()],
+            max_price: Some("10000".to_string()),
+            ..Default::default()
+        };
+
+        let hits = searcher.search(&query).await.unwrap();
+
+        assert_eq!(received.lock().unwrap().len(), 1);
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn errors_are_propagated() {
+        let (base, _) = serve(|_| None).await;
+        let searcher = Scraper::new(Dealers::new(&base));
+
+        assert!(searcher.search(&Query::default()).await.is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A request received by the fixture server.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub method: String,
+    /// The path and query of the request, e.g. `/search?page=2`
+    pub target: String,
+    pub body: String,
+}
+
+/// Serve a single request from the connection, if it is a complete HTTP request.
+async fn receive(stream: &mut tokio::net::TcpStream) -> Option<Received> {
+    let mut buffer = vec![];
+    let mut chunk = [0; 4096];
+    let (head, body_start) = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break (String::from_utf8_lossy(&buffer[..end]).to_string(), end + 4);
+        }
+    };
+    let length: usize = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())?
+        })
+        .unwrap_or(0);
+    while buffer.len() < body_start + length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+
+    let mut request_line = head.lines().next()?.split_whitespace();
+    Some(Received {
+        method: request_line.next()?.to_string(),
+        target: request_line.next()?.to_string(),
+        body: String::from_utf8_lossy(&buffer[body_start..]).to_string(),
+    })
+}
+
+/// Serve fixtures over HTTP on a local port, replying to every request with the body that
+/// `respond` returns for it, or with a 404 if it returns None. Returns the base URL of the server,
+/// e.g. `http://127.0.0.1:12345`, and the requests it has received so far.
+pub async fn serve(
+    respond: impl Fn(&Received) -> Option<String> + Send + Sync + 'static,
+) -> (String, Arc<Mutex<Vec<Received>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let received = Arc::new(Mutex::new(vec![]));
+    let respond = Arc::new(respond);
+
+    let log = received.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let Some(request) = receive(&mut stream).await else {
+                continue;
+            };
+            let (status, body) = match respond(&request) {
+                Some(body) => ("200 OK", body),
+                None => ("404 Not Found", String::new()),
+            };
+            log.lock().unwrap().push(request);
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (format!("http://{}", address), received)
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use crate::search::Searcher;
+
+use super::{key, Taxonomy};
+
+/// How a search engine's support for a make or model is known.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Support {
+    /// Listed by the search engine itself
+    Listed,
+    /// Listed in the taxonomy, for a search engine that cannot list its makes and models
+    Bundled,
+    Unsupported,
+}
+
+/// The makes (or the models of a make) that any search engine knows of, and which search engine
+/// supports which.
+#[derive(Debug)]
+pub struct SupportMatrix {
+    /// What is listed: "make" or "model"
+    pub kind: &'static str,
+    pub engines: Vec<String>,
+    /// Every canonical name, in alphabetical order, with its support by each search engine
+    pub rows: Vec<(String, Vec<Support>)>,
+}
+
+impl Taxonomy {
+    /// The canonical names of all makes or, if a make is provided, of all models of that make.
+    fn names(&self, make: Option<&str>) -> Vec<String> {
+        match make {
+            None => self.makes.iter().map(|m| m.entry.name.clone()).collect(),
+            Some(make) => self
+                .make(make)
+                .map(|m| m.models.iter().map(|model| model.name.clone()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The canonical name of a make or, if a make is provided, of a model of that make.
+    fn canonical(&self, make: Option<&str>, name: &str) -> String {
+        let known = match make {
+            None => self.make(name).map(|m| &m.entry),
+            Some(make) => self.make(make).and_then(|m| m.model(name)),
+        };
+        known.map_or(name, |entry| &entry.name).to_string()
+    }
+}
+
+/// The makes, or models of a make, that a search engine supports: as listed by the search engine
+/// if it can, and as listed in the taxonomy otherwise.
+async fn listing(
+    searcher: &dyn Searcher,
+    taxonomy: &Taxonomy,
+    make: Option<&str>,
+) -> (Vec<String>, Support) {
+    let engine_make = make.map(|m| {
+        taxonomy
+            .make(m)
+            .map_or(m, |known| known.entry.name_for(searcher.name()))
+    });
+    match searcher.taxonomy(engine_make).await {
+        Some(Ok(names)) => (names, Support::Listed),
+        Some(Err(e)) => {
+            println!(
+                "Unable to retrieve the taxonomy of {}: {}, using the bundled list",
+                searcher.name(),
+                e
+            );
+            (taxonomy.names(make), Support::Bundled)
+        }
+        None => (taxonomy.names(make), Support::Bundled),
+    }
+}
+
+/// Retrieve the makes or, if a make is provided, the models of that make that each searcher
+/// supports, and combine them under their canonical names.
+pub async fn support_matrix(
+    searchers: &[Box<dyn Searcher>],
+    taxonomy: &Taxonomy,
+    make: Option<&str>,
+) -> SupportMatrix {
+    let listings = futures::future::join_all(
+        searchers
+            .iter()
+            .map(|searcher| listing(searcher.as_ref(), taxonomy, make)),
+    )
+    .await;
+
+    let mut rows: BTreeMap<String, (String, Vec<Support>)> = BTreeMap::new();
+    for (i, (names, support)) in listings.into_iter().enumerate() {
+        for name in names {
+            let name = taxonomy.canonical(make, &name);
+            let row = rows
+                .entry(key(&name))
+                .or_insert_with(|| (name, vec![Support::Unsupported; searchers.len()]));
+            row.1[i] = support;
+        }
+    }
+
+    SupportMatrix {
+        kind: if make.is_some() { "model" } else { "make" },
+        engines: searchers.iter().map(|s| s.name().to_string()).collect(),
+        rows: rows.into_values().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Error;
+
+    use async_trait::async_trait;
+
+    use crate::query::Query;
+    use crate::search::SearchResult;
+
+    use super::*;
+
+    struct Listing {}
+    struct Unlisted {}
+    struct Failing {}
+
+    #[async_trait]
+    impl Searcher for Listing {
+        fn name(&self) -> &str {
+            "listing"
+        }
+
+        async fn search(&self, _q: &Query) -> SearchResult {
+            Ok(vec![])
+        }
+
+        async fn taxonomy(&self, make: Option<&str>) -> Option<Result<Vec<String>, Error>> {
+            Some(Ok(match make {
+                None => vec!["VW".to_string(), "Trabant".to_string()],
+                Some(_) => vec!["golf".to_string(), "Beetle".to_string()],
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl Searcher for Unlisted {
+        fn name(&self) -> &str {
+            "unlisted"
+        }
+
+        async fn search(&self, _q: &Query) -> SearchResult {
+            Ok(vec![])
+        }
+    }
+
+    #[async_trait]
+    impl Searcher for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn search(&self, _q: &Query) -> SearchResult {
+            Ok(vec![])
+        }
+
+        async fn taxonomy(&self, _make: Option<&str>) -> Option<Result<Vec<String>, Error>> {
+            Some(Err(Error::other("oh no")))
+        }
+    }
+
+    fn taxonomy() -> Taxonomy {
+        toml::from_str(
+            r#"
+            [[make]]
+            name = "Volkswagen"
+            aliases = ["VW"]
+            model = [{ name = "Golf" }, { name = "Polo" }]
+
+            [[make]]
+            name = "Ford"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn makes_are_combined_under_canonical_names() {
+        let searchers: Vec<Box<dyn Searcher>> = vec![Box::new(Listing {}), Box::new(Unlisted {})];
+        let matrix = support_matrix(&searchers, &taxonomy(), None).await;
+
+        assert_eq!(matrix.kind, "make");
+        assert_eq!(matrix.engines, vec!["listing", "unlisted"]);
+        assert_eq!(
+            matrix.rows,
+            vec![
+                (
+                    "Ford".to_string(),
+                    vec![Support::Unsupported, Support::Bundled]
+                ),
+                (
+                    "Trabant".to_string(),
+                    vec![Support::Listed, Support::Unsupported]
+                ),
+                (
+                    "Volkswagen".to_string(),
+                    vec![Support::Listed, Support::Bundled]
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn models_fall_back_to_the_taxonomy_on_errors() {
+        let searchers: Vec<Box<dyn Searcher>> = vec![Box::new(Listing {}), Box::new(Failing {})];
+        let matrix = support_matrix(&searchers, &taxonomy(), Some("vw")).await;
+
+        assert_eq!(matrix.kind, "model");
+        assert_eq!(
+            matrix.rows,
+            vec![
+                (
+                    "Beetle".to_string(),
+                    vec![Support::Listed, Support::Unsupported]
+                ),
+                ("Golf".to_string(), vec![Support::Listed, Support::Bundled]),
+                (
+                    "Polo".to_string(),
+                    vec![Support::Unsupported, Support::Bundled]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::args::GlobalArgs;
+use crate::hit::Hit;
+use crate::query::{MakeModel, Query};
+
+pub mod discovery;
+
+/// The taxonomy of makes and models that ships with findcar.
+const BUNDLED: &str = include_str!("bundled.toml");
+
+/// A canonical make/model taxonomy. Every search engine spells makes and models in its own way
+/// ("VW" or "Volkswagen", "3 Series" or "3-Series"), so the taxonomy is used to translate the
+/// makes and models of a query into the spelling that each search engine expects, and to
+/// translate the makes and models of hits back into their canonical names.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Taxonomy {
+    #[serde(default, rename = "make")]
+    makes: Vec<Make>,
+}
+
+/// The names under which a make or model is known.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Entry {
+    /// The canonical name
+    pub name: String,
+    /// Other names that refer to the same make or model
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// The name that a search engine expects, by search engine, where it differs from the
+    /// canonical name
+    #[serde(default)]
+    pub engines: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Make {
+    #[serde(flatten)]
+    pub entry: Entry,
+    #[serde(default, rename = "model")]
+    pub models: Vec<Entry>,
+}
+
+/// Reduce a name to the form in which names are compared: lowercase, without accents, and
+/// ignoring anything but letters and digits.
+pub fn key(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'ç' | 'č' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ò' | 'ó' | 'ô' | 'ö' | 'õ' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'š' => 's',
+            'ž' => 'z',
+            c => c,
+        })
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Split text into a prefix, ignoring case, and the rest, if the prefix is followed by the end of
+/// the text or a space. The prefix is returned as it appears in the text.
+pub fn split_prefix<'a>(text: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let head = text.get(..prefix.len())?;
+    let rest = &text[prefix.len()..];
+    (head.to_lowercase() == prefix.to_lowercase() && (rest.is_empty() || rest.starts_with(' ')))
+        .then(|| (head, rest.trim()))
+}
+
+impl Entry {
+    /// Whether this entry is known by the provided name, be it its canonical name or an alias.
+    /// The names that search engines expect are not matched, as these may be identifiers, such as
+    /// the numeric makes of mobile_de.
+    pub fn is_known_as(&self, name: &str) -> bool {
+        let wanted = key(name);
+        std::iter::once(&self.name)
+            .chain(&self.aliases)
+            .any(|n| key(n) == wanted)
+    }
+
+    /// Whether this entry is known by the provided name, or the provided search engine uses it for
+    /// this entry.
+    pub fn is_known_to(&self, engine: &str, name: &str) -> bool {
+        self.is_known_as(name) || key(self.name_for(engine)) == key(name)
+    }
+
+    /// The name that the provided search engine expects for this entry. Search engines with a
+    /// site per country, such as `autoscout24_de` and `autoscout24_it`, share the names of their
+    /// family (`autoscout24`) unless they have their own.
+    pub fn name_for(&self, engine: &str) -> &str {
+        self.engines
+            .get(engine)
+            .or_else(|| {
+                let (family, _) = engine.rsplit_once('_')?;
+                self.engines.get(family)
+            })
+            .unwrap_or(&self.name)
+    }
+
+    fn merge(&mut self, other: Entry) {
+        for alias in std::iter::once(other.name).chain(other.aliases) {
+            if !self.is_known_as(&alias) {
+                self.aliases.push(alias);
+            }
+        }
+        self.engines.extend(other.engines);
+    }
+}
+
+impl Make {
+    /// The model of this make that is known by the provided name, if any.
+    pub fn model(&self, name: &str) -> Option<&Entry> {
+        self.models.iter().find(|model| model.is_known_as(name))
+    }
+}
+
+impl Taxonomy {
+    /// The taxonomy that ships with findcar.
+    pub fn bundled() -> Self {
+        toml::from_str(BUNDLED).expect("the bundled taxonomy is valid")
+    }
+
+    /// Read a taxonomy from a TOML file, in the same format as the bundled taxonomy.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    }
+
+    /// The make that is known by the provided name, if any.
+    pub fn make(&self, name: &str) -> Option<&Make> {
+        self.makes.iter().find(|make| make.entry.is_known_as(name))
+    }
+
+    /// Split a title such as `Land Rover Range Rover Sport` into the make it starts with, as it
+    /// is spelled in the title, and the rest, if it starts with the name or an alias of a make.
+    pub fn split_make<'a>(&self, title: &'a str) -> Option<(&'a str, &'a str)> {
+        self.makes
+            .iter()
+            .flat_map(|make| std::iter::once(&make.entry.name).chain(&make.entry.aliases))
+            .filter_map(|name| split_prefix(title, name))
+            .max_by_key(|(make, _)| make.len())
+    }
+
+    /// Add the makes, models and aliases of another taxonomy to this one. Makes and models that
+    /// are already known gain the other's names as aliases; unknown ones are added as they are.
+    pub fn merge(&mut self, other: Taxonomy) {
+        for make in other.makes {
+            let existing = self
+                .makes
+                .iter_mut()
+                .find(|m| m.entry.is_known_as(&make.entry.name));
+            match existing {
+                None => self.makes.push(make),
+                Some(existing) => {
+                    existing.entry.merge(make.entry);
+                    for model in make.models {
+                        match existing
+                            .models
+                            .iter_mut()
+                            .find(|m| m.is_known_as(&model.name))
+                        {
+                            Some(m) => m.merge(model),
+                            None => existing.models.push(model),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The query as the provided search engine expects it: every make and model that is known
+    /// is replaced by the name that the search engine uses for it. Unknown makes and models are
+    /// left as they are.
+    pub fn query_for(&self, engine: &str, query: &Query) -> Query {
+        self.rename(query, |entry| entry.name_for(engine))
+    }
+
+    /// The query with every make and model that is known replaced by its canonical name.
+    pub fn canonical_query(&self, query: &Query) -> Query {
+        self.rename(query, |entry| &entry.name)
+    }
+
+    fn rename(&self, query: &Query, name: impl Fn(&Entry) -> &str) -> Query {
+        let make_models = query
+            .make_models
+            .iter()
+            .map(|make_model| {
+                let make = make_model.make.as_deref().and_then(|m| self.make(m));
+                MakeModel {
+                    make: match make {
+                        Some(make) => Some(name(&make.entry).to_string()),
+                        None => make_model.make.clone(),
+                    },
+                    model: match (make, &make_model.model) {
+                        (Some(make), Some(model)) => {
+                            Some(make.model(model).map_or(model.as_str(), &name).to_string())
+                        }
+                        (_, model) => model.clone(),
+                    },
+                }
+            })
+            .collect();
+
+        Query {
+            make_models,
+            ..query.clone()
+        }
+    }
+
+    /// Replace the make and model of a hit from the provided search engine with their canonical
+    /// names, if they are known by their names, their aliases or the names the engine uses.
+    pub fn normalise(&self, engine: &str, hit: &mut Hit) {
+        let make = self
+            .makes
+            .iter()
+            .find(|make| make.entry.is_known_to(engine, &hit.make));
+        if let Some(make) = make {
+            let model = make
+                .models
+                .iter()
+                .find(|model| model.is_known_to(engine, &hit.model));
+            if let Some(model) = model {
+                hit.model = model.name.clone();
+            }
+            hit.make = make.entry.name.clone();
+        }
+    }
+}
+
+impl From<&GlobalArgs> for Taxonomy {
+    fn from(args: &GlobalArgs) -> Self {
+        let mut taxonomy = Taxonomy::bundled();
+        if let Some(path) = &args.aliases {
+            match Taxonomy::from_file(path) {
+                Ok(aliases) => taxonomy.merge(aliases),
+                Err(e) => eprintln!(
+                    "Unable to read alias file {}: {}, using the bundled aliases only",
+                    path, e
+                ),
+            }
+        }
+        taxonomy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(make: &str, model: &str) -> Hit {
+        Hit {
+            make: make.to_string(),
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn query(vehicles: &[&str]) -> Query {
+        Query {
+            make_models: vehicles.iter().map(|v| v.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bundled_taxonomy_is_valid() {
+        let taxonomy = Taxonomy::bundled();
+        assert!(taxonomy.make("Volkswagen").is_some());
+        assert!(taxonomy.makes.iter().all(|m| !m.entry.name.is_empty()));
+    }
+
+    #[test]
+    fn engine_identifiers_are_not_names() {
+        let taxonomy = Taxonomy::bundled();
+        assert!(taxonomy.make("Alfa").is_some());
+        assert!(taxonomy.make("900").is_none());
+        assert!(taxonomy.make("BMW").unwrap().model("1er-(alle)").is_none());
+    }
+
+    #[test]
+    fn normalises_hits() {
+        let taxonomy = Taxonomy::bundled();
+        let mut hits = [
+            hit("VW", "golf"),
+            hit("bmw", "3-Series"),
+            hit("Škoda", "Octavia"),
+            hit("Trabant", "601"),
+        ];
+        hits.iter_mut().for_each(|h| taxonomy.normalise("foo", h));
+
+        let names: Vec<(&str, &str)> = hits
+            .iter()
+            .map(|h| (h.make.as_str(), h.model.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Volkswagen", "Golf"),
+                ("BMW", "3 Series"),
+                ("Skoda", "Octavia"),
+                ("Trabant", "601"),
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_query_to_engine_names() {
+        let mut taxonomy = Taxonomy::bundled();
+        taxonomy.merge(
+            toml::from_str(
+                r#"
+                [[make]]
+                name = "BMW"
+                model = [{ name = "3 Series", engines = { foo = "3-Series" } }]
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let mapped = taxonomy.query_for("foo", &query(&["vw:golf", "bmw:3er", "Trabant:601"]));
+        assert_eq!(
+            mapped.make_models,
+            query(&["Volkswagen:Golf", "BMW:3-Series", "Trabant:601"]).make_models
+        );
+
+        let mapped = taxonomy.query_for("bar", &query(&["bmw:3er"]));
+        assert_eq!(mapped.make_models, query(&["BMW:3 Series"]).make_models);
+
+        // Sites of a family share its names
+        let mapped = taxonomy.query_for("foo_de", &query(&["bmw:3er"]));
+        assert_eq!(mapped.make_models, query(&["BMW:3-Series"]).make_models);
+    }
+
+    #[test]
+    fn splits_titles_at_the_make() {
+        let taxonomy = Taxonomy::bundled();
+
+        assert_eq!(
+            taxonomy.split_make("LAND ROVER Range Rover Sport"),
+            Some(("LAND ROVER", "Range Rover Sport"))
+        );
+        assert_eq!(taxonomy.split_make("VW Golf"), Some(("VW", "Golf")));
+        assert_eq!(taxonomy.split_make("Volkswagen"), Some(("Volkswagen", "")));
+        assert_eq!(taxonomy.split_make("Volvo"), Some(("Volvo", "")));
+        assert_eq!(taxonomy.split_make("Trabant 601"), None);
+        assert_eq!(taxonomy.split_make("Fordson tractor"), None);
+    }
+
+    #[test]
+    fn user_aliases_extend_the_taxonomy() {
+        let mut taxonomy = Taxonomy::bundled();
+        taxonomy.merge(
+            toml::from_str(
+                r#"
+                [[make]]
+                name = "VW"
+                aliases = ["Vee-Dub"]
+                model = [{ name = "Golf", aliases = ["Mk7"] }]
+
+                [[make]]
+                name = "Trabant"
+                model = [{ name = "601", aliases = ["Trabi"] }]
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let mut hits = [hit("vee dub", "mk7"), hit("trabant", "trabi")];
+        hits.iter_mut().for_each(|h| taxonomy.normalise("foo", h));
+
+        assert_eq!(
+            (hits[0].make.as_str(), hits[0].model.as_str()),
+            ("Volkswagen", "Golf")
+        );
+        assert_eq!(
+            (hits[1].make.as_str(), hits[1].model.as_str()),
+            ("Trabant", "601")
+        );
+    }
+}